@@ -0,0 +1,132 @@
+//! Import library generation benchmarks
+//!
+//! Measures the module-definitions data selection, the tool discovery
+//! and the end-to-end `generate()` time for each import library tool flavor,
+//! as the baseline for the tool discovery and invocation changes.
+//!
+//! The tool flavors not installed on the host are skipped,
+//! run with `cargo bench` and compare the `generate/<env>` groups.
+
+use std::path::PathBuf;
+
+use criterion::{criterion_group, criterion_main, BenchmarkId, Criterion};
+use python3_dll_a::{Error, ImportLibraryGenerator, PythonImplementation};
+
+/// Python configuration: the name, version and ABI flags
+type Python = (&'static str, Option<(u8, u8)>, Option<&'static str>);
+
+/// Benchmarked Python configurations
+const PYTHONS: [Python; 3] = [
+    ("python3", None, None),
+    ("python312", Some((3, 12)), None),
+    ("python313t", Some((3, 13)), Some("t")),
+];
+
+/// Target environments selecting the import library tool flavors
+const ENVS: [&str; 3] = ["msvc", "gnu", "gnullvm"];
+
+/// Creates the `x86_64` generator for the Python configuration.
+fn generator(
+    env: &str,
+    version: Option<(u8, u8)>,
+    abiflags: Option<&str>,
+) -> ImportLibraryGenerator {
+    let mut generator = ImportLibraryGenerator::new("x86_64", env);
+    generator.version(version).abiflags(abiflags);
+    generator
+}
+
+/// Returns the benchmark output directory for the target environment.
+fn out_dir(env: &str) -> PathBuf {
+    let mut dir = PathBuf::from(env!("CARGO_MANIFEST_DIR"));
+    dir.push("target");
+    dir.push(format!("x86_64-pc-windows-{}", env));
+    dir.push("python3-bench");
+    dir
+}
+
+/// Finds the import library tool used for `env`, `None` if not installed.
+fn find_tool(env: &str) -> Option<String> {
+    let mut generator = ImportLibraryGenerator::new("x86_64", env);
+
+    match generator.dry_run(true).generate(&out_dir(env)) {
+        Ok(report) => Some(report.tool),
+        Err(Error::ToolNotFound { .. }) => {
+            eprintln!("no import library tool found for {}, skipping", env);
+            None
+        }
+        Err(e) => panic!("{}", e),
+    }
+}
+
+/// Benchmarks the embedded definitions selection, parsing and filtering.
+fn def_selection(c: &mut Criterion) {
+    let mut group = c.benchmark_group("def_to_string");
+
+    for (name, version, abiflags) in PYTHONS {
+        let generator = generator("msvc", version, abiflags);
+        group.bench_function(name, |b| b.iter(|| generator.def_to_string().unwrap()));
+    }
+
+    let mut pypy = generator("msvc", Some((3, 10)), None);
+    pypy.implementation(PythonImplementation::PyPy);
+    group.bench_function("libpypy3.10-c", |b| {
+        b.iter(|| pypy.def_to_string().unwrap())
+    });
+
+    // The Stable ABI level subset is derived from the versioned definitions.
+    let mut abi3 = generator("msvc", None, None);
+    abi3.limited_api(Some((3, 9)));
+    group.bench_function("python3-abi3-py39", |b| {
+        b.iter(|| abi3.def_to_string().unwrap())
+    });
+
+    group.finish();
+}
+
+/// Benchmarks the tool discovery and the output planning without running the tools.
+fn tool_discovery(c: &mut Criterion) {
+    let mut group = c.benchmark_group("dry_run");
+
+    for env in ENVS {
+        let Some(tool) = find_tool(env) else {
+            continue;
+        };
+
+        let mut generator = generator(env, Some((3, 12)), None);
+        generator.dry_run(true);
+
+        let dir = out_dir(env);
+        group.bench_function(BenchmarkId::new(env, tool), |b| {
+            b.iter(|| generator.generate(&dir).unwrap())
+        });
+    }
+
+    group.finish();
+}
+
+/// Benchmarks the end-to-end import library generation for each tool flavor.
+fn generate(c: &mut Criterion) {
+    for env in ENVS {
+        let Some(tool) = find_tool(env) else {
+            continue;
+        };
+
+        let mut group = c.benchmark_group(format!("generate/{}", env));
+        // Each iteration spawns the tool process.
+        group.sample_size(20);
+
+        let dir = out_dir(env);
+        for (name, version, abiflags) in PYTHONS {
+            let generator = generator(env, version, abiflags);
+            group.bench_function(BenchmarkId::new(&tool, name), |b| {
+                b.iter(|| generator.generate(&dir).unwrap())
+            });
+        }
+
+        group.finish();
+    }
+}
+
+criterion_group!(benches, def_selection, tool_discovery, generate);
+criterion_main!(benches);
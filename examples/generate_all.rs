@@ -0,0 +1,101 @@
+//! Generates the Python DLL import libraries for several compile targets
+//! and Python versions using the public API only.
+//!
+//! Usage: `cargo run --example generate_all [OUT_DIR] [TARGET_SPEC.json]...`
+//!
+//! The extra rustc JSON target specification files are generated
+//! for in addition to the built-in target list. The targets lacking
+//! the import library tool on the host are skipped.
+
+use std::env;
+use std::path::{Path, PathBuf};
+use std::process::ExitCode;
+
+use python3_dll_a::{Error, ImportLibraryGenerator, PythonImplementation, StableAbi};
+
+/// Built-in compile targets as (`arch`, `env`) pairs
+const TARGETS: [(&str, &str); 5] = [
+    ("x86_64", "gnu"),
+    ("x86", "gnu"),
+    ("x86_64", "msvc"),
+    ("x86", "msvc"),
+    ("aarch64", "msvc"),
+];
+
+fn main() -> ExitCode {
+    let mut args = env::args_os().skip(1);
+
+    let out_dir = args.next().map_or_else(
+        || PathBuf::from("target/python3-dll-a-example"),
+        PathBuf::from,
+    );
+
+    let mut targets = Vec::new();
+
+    for (arch, env) in TARGETS {
+        targets.push((
+            format!("{}-{}", arch, env),
+            ImportLibraryGenerator::new(arch, env),
+        ));
+    }
+
+    for spec in args {
+        match ImportLibraryGenerator::from_target_spec(Path::new(&spec)) {
+            Ok(generator) => targets.push((spec.to_string_lossy().into_owned(), generator)),
+            Err(e) => {
+                eprintln!("{}: {}", spec.to_string_lossy(), e);
+                return ExitCode::FAILURE;
+            }
+        }
+    }
+
+    let mut failed = false;
+
+    for (name, target) in targets {
+        let target_dir = out_dir.join(name.replace(['/', '\\'], "_"));
+
+        for generator in configurations(&target) {
+            match generator.generate(&target_dir) {
+                Ok(report) => println!("{}", report),
+                // The tool is not installed on this host.
+                Err(e @ (Error::ToolNotFound { .. } | Error::ToolSpawn { .. })) => {
+                    eprintln!("skipping {}: {}", name, e);
+                    break;
+                }
+                Err(e) => {
+                    eprintln!("{}: {}", name, e);
+                    failed = true;
+                }
+            }
+        }
+    }
+
+    if failed {
+        ExitCode::FAILURE
+    } else {
+        ExitCode::SUCCESS
+    }
+}
+
+/// Lists the Python configurations to generate for the target.
+fn configurations(target: &ImportLibraryGenerator) -> Vec<ImportLibraryGenerator> {
+    let mut stable = target.clone();
+    stable.stable_abi(Some(StableAbi::default()));
+
+    let mut stable_free_threaded = target.clone();
+    stable_free_threaded.stable_abi(Some(StableAbi {
+        free_threaded: true,
+    }));
+
+    let mut versioned = target.clone();
+    versioned.version(Some((3, 13)));
+
+    let mut free_threaded = versioned.clone();
+    free_threaded.abiflags(Some("t"));
+
+    let mut pypy = target.clone();
+    pypy.version(Some((3, 10)))
+        .implementation(PythonImplementation::PyPy);
+
+    vec![stable, stable_free_threaded, versioned, free_threaded, pypy]
+}
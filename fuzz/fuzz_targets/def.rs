@@ -0,0 +1,24 @@
+//! Module-definition file parser fuzz target
+//!
+//! The caller-provided definitions must be either rejected with an error
+//! or normalized into the definitions accepted as-is.
+
+#![no_main]
+
+use libfuzzer_sys::fuzz_target;
+use python3_dll_a::ImportLibraryGenerator;
+
+fuzz_target!(|def: &str| {
+    let mut generator = ImportLibraryGenerator::new("x86_64", "msvc");
+    generator
+        .custom_def(def, "python3.dll")
+        .data_symbols(&["Py_None"]);
+
+    if let Ok(normalized) = generator.def_to_string() {
+        let renormalized = generator
+            .custom_def(&normalized, "python3.dll")
+            .def_to_string()
+            .unwrap();
+        assert_eq!(renormalized, normalized);
+    }
+});
@@ -0,0 +1,22 @@
+//! DLL export table reader fuzz target
+//!
+//! Any DLL image accepted by `from_dll()` must produce valid definitions.
+
+#![no_main]
+
+use std::env;
+use std::fs;
+use std::process;
+
+use libfuzzer_sys::fuzz_target;
+use python3_dll_a::ImportLibraryGenerator;
+
+fuzz_target!(|image: &[u8]| {
+    // `from_dll()` reads the image from a file.
+    let path = env::temp_dir().join(format!("python3-dll-a-fuzz-{}.dll", process::id()));
+    fs::write(&path, image).unwrap();
+
+    if let Ok(generator) = ImportLibraryGenerator::from_dll(&path, "msvc") {
+        generator.def_to_string().unwrap();
+    }
+});
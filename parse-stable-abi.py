@@ -1,22 +1,42 @@
 #!/usr/bin/env python3
 # Parses Python Stable ABI symbol definitions from the manifest in the CPython repository located at https://github.com/python/cpython/blob/main/Misc/stable_abi.toml
 # and produces a definition file following the format described at https://docs.microsoft.com/en-us/cpp/build/reference/module-definition-dot-def-files.
+# The DLL name defaults to `python3.dll`, pass `python3t.dll` for the free-threaded Stable ABI.
+# The symbols conditional on the feature macros not defined on Windows (e.g. `HAVE_FORK`)
+# or defined in the debug builds only (`Py_REF_DEBUG`) are skipped.
+# `PyOS_CheckStack` (`USE_STACKCHECK`) is kept, it is exported by the 32-bit x86 DLL only
+# and the generator drops it for the other architectures.
 import sys
 import tomli
 
+dll_name = sys.argv[1] if len(sys.argv) > 1 else "python3.dll"
+
 stable_abi = tomli.load(sys.stdin.buffer)
 
-print("LIBRARY python3.dll")
+windows_macros = {
+    name for name, macro in stable_abi["feature_macro"].items() if macro.get("windows") is True
+}
+windows_macros.add("USE_STACKCHECK")
+
+
+def on_windows(item):
+    ifdef = item.get("ifdef")
+    return ifdef is None or ifdef in windows_macros
+
+
+print(f"LIBRARY {dll_name}")
 print("EXPORTS")
 
 count = 0
 
-for function in stable_abi["function"].keys():
-    print(function)
-    count += 1
+for function, item in stable_abi["function"].items():
+    if on_windows(item):
+        print(function)
+        count += 1
 
-for data in stable_abi["data"].keys():
-    print(f"{data} DATA")
-    count += 1
+for data, item in stable_abi["data"].items():
+    if on_windows(item):
+        print(f"{data} DATA")
+        count += 1
 
 assert count >= 859
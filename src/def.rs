@@ -0,0 +1,751 @@
+//! Module-definition (`.def`) file parser
+//!
+//! Parses the `LIBRARY` and `EXPORTS` statements including the export
+//! aliases, ordinals and attributes. Comments, blank lines and the other
+//! statements are kept as-is, so that the parsed file can be written back
+//! after filtering the exports without losing information.
+
+use std::fmt;
+
+use crate::{Error, Result};
+
+/// Parsed module-definition file
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub(crate) struct ModuleDef {
+    /// The file lines in order
+    lines: Vec<Line>,
+}
+
+/// Module-definition file line
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum Line {
+    /// Empty or whitespace-only line
+    Blank,
+    /// Full line comment (the text after `;`)
+    Comment(String),
+    /// `LIBRARY` statement
+    Library {
+        /// The DLL name
+        name: String,
+        /// The DLL name was double-quoted
+        quoted: bool,
+        /// Further statement arguments, e.g. `BASE=0x1000000`
+        args: Option<String>,
+    },
+    /// `EXPORTS` section header
+    Exports,
+    /// `EXPORTS` section entry
+    Export(Export),
+    /// Any other statement, kept verbatim
+    Statement(String),
+}
+
+/// `EXPORTS` section entry
+///
+/// `entryname[=internalname] [@ordinal [NONAME]] [DATA | CONSTANT] [PRIVATE]`
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub(crate) struct Export {
+    /// The exported symbol name
+    pub name: String,
+    /// The internal symbol name aliased by the export
+    pub internal_name: Option<String>,
+    /// The export ordinal number
+    pub ordinal: Option<u16>,
+    /// The export is only available by ordinal
+    pub noname: bool,
+    /// The export refers to data, not code
+    pub data: bool,
+    /// The export refers to data (obsolete `DATA` synonym)
+    pub constant: bool,
+    /// The export is omitted from the import library
+    pub private: bool,
+    /// Trailing comment (the text after `;`)
+    pub comment: Option<String>,
+}
+
+/// Statement keywords ending the `EXPORTS` section
+const KEYWORDS: [&str; 10] = [
+    "LIBRARY",
+    "NAME",
+    "EXPORTS",
+    "IMPORTS",
+    "DESCRIPTION",
+    "STACKSIZE",
+    "HEAPSIZE",
+    "SECTIONS",
+    "STUB",
+    "VERSION",
+];
+
+impl ModuleDef {
+    /// Parses the module-definition file text.
+    pub fn parse(def: &str) -> Result<Self> {
+        let def = def.strip_prefix('\u{feff}').unwrap_or(def);
+
+        let mut lines = Vec::new();
+        let mut exports = false;
+
+        for (i, line) in def.lines().enumerate() {
+            let trimmed = line.trim();
+            let keyword = trimmed.split_whitespace().next().unwrap_or_default();
+
+            let parsed = if trimmed.is_empty() {
+                Line::Blank
+            } else if let Some(comment) = trimmed.strip_prefix(';') {
+                Line::Comment(comment.to_owned())
+            } else if keyword == "EXPORTS" && trimmed == keyword {
+                exports = true;
+                Line::Exports
+            } else if KEYWORDS.contains(&keyword) {
+                exports = false;
+
+                if keyword == "LIBRARY" {
+                    parse_library(trimmed[keyword.len()..].trim_start())
+                        .map_err(|msg| invalid(i, &msg))?
+                } else {
+                    Line::Statement(trimmed.to_owned())
+                }
+            } else if exports {
+                Line::Export(Export::parse(trimmed).map_err(|msg| invalid(i, &msg))?)
+            } else {
+                Line::Statement(trimmed.to_owned())
+            };
+
+            lines.push(parsed);
+        }
+
+        Ok(ModuleDef { lines })
+    }
+
+    /// Returns the DLL name from the `LIBRARY` statement, if any.
+    pub fn library(&self) -> Option<&str> {
+        self.lines.iter().find_map(|line| match line {
+            Line::Library { name, .. } => Some(name.as_str()),
+            _ => None,
+        })
+    }
+
+    /// Replaces the DLL name in the `LIBRARY` statement,
+    /// adding the statement if missing.
+    pub fn set_library(&mut self, dll_name: &str) {
+        let library = self.lines.iter_mut().find_map(|line| match line {
+            Line::Library { name, quoted, .. } => Some((name, quoted)),
+            _ => None,
+        });
+
+        match library {
+            Some((name, quoted)) => {
+                *name = dll_name.to_owned();
+                *quoted = *quoted || dll_name.contains(char::is_whitespace);
+            }
+            None => self.lines.insert(
+                0,
+                Line::Library {
+                    name: dll_name.to_owned(),
+                    quoted: dll_name.contains(char::is_whitespace),
+                    args: None,
+                },
+            ),
+        }
+    }
+
+    /// Iterates over the `EXPORTS` section entries.
+    pub fn exports(&self) -> impl Iterator<Item = &Export> {
+        self.lines.iter().filter_map(|line| match line {
+            Line::Export(export) => Some(export),
+            _ => None,
+        })
+    }
+
+    /// Drops the forwarding targets from the forwarder exports,
+    /// keeping the exported names only.
+    ///
+    /// The import libraries refer to the forwarder exports by name,
+    /// the Windows loader resolves the forwarding at run time.
+    /// The tools would treat the `dll.symbol` target as the internal
+    /// symbol alias otherwise, producing broken import thunks.
+    ///
+    /// Returns the number of the forwarder exports found.
+    pub fn strip_forwarders(&mut self) -> usize {
+        let mut count = 0;
+
+        for line in &mut self.lines {
+            if let Line::Export(export) = line {
+                if export.forward_target().is_some() {
+                    export.internal_name = None;
+                    count += 1;
+                }
+            }
+        }
+
+        count
+    }
+
+    /// Marks the exports named in `names` as data, overriding
+    /// their classification.
+    ///
+    /// Returns the number of the reclassified exports.
+    pub fn mark_data(&mut self, names: &[String]) -> usize {
+        let mut count = 0;
+
+        for line in &mut self.lines {
+            if let Line::Export(export) = line {
+                if !export.data && names.contains(&export.name) {
+                    // `CONSTANT` is the obsolete `DATA` synonym.
+                    export.data = true;
+                    export.constant = false;
+                    count += 1;
+                }
+            }
+        }
+
+        count
+    }
+
+    /// Marks the exports with the ordinal numbers as `NONAME`,
+    /// so that the import library refers to them by ordinal only.
+    ///
+    /// Returns the number of the exports with the ordinal numbers.
+    pub fn import_by_ordinal(&mut self) -> usize {
+        let mut count = 0;
+
+        for line in &mut self.lines {
+            if let Line::Export(export) = line {
+                if export.ordinal.is_some() {
+                    export.noname = true;
+                    count += 1;
+                }
+            }
+        }
+
+        count
+    }
+
+    /// Retains only the exports matching the predicate.
+    pub fn retain_exports(&mut self, mut f: impl FnMut(&Export) -> bool) {
+        self.lines.retain(|line| match line {
+            Line::Export(export) => f(export),
+            _ => true,
+        });
+    }
+}
+
+/// Formats the module-definition file text in the normalized form,
+/// one statement per line with the LF line endings.
+impl fmt::Display for ModuleDef {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for line in &self.lines {
+            match line {
+                Line::Blank => {}
+                Line::Comment(comment) => write!(f, ";{}", comment)?,
+                Line::Library { name, quoted, args } => {
+                    if *quoted {
+                        write!(f, "LIBRARY \"{}\"", name)?;
+                    } else {
+                        write!(f, "LIBRARY {}", name)?;
+                    }
+                    if let Some(args) = args {
+                        write!(f, " {}", args)?;
+                    }
+                }
+                Line::Exports => f.write_str("EXPORTS")?,
+                Line::Export(export) => export.fmt(f)?,
+                Line::Statement(statement) => f.write_str(statement)?,
+            }
+            f.write_str("\n")?;
+        }
+
+        Ok(())
+    }
+}
+
+impl Export {
+    /// Parses the trimmed `EXPORTS` section entry line.
+    fn parse(line: &str) -> std::result::Result<Self, String> {
+        let (tokens, comment) = tokenize(line)?;
+        let mut tokens = tokens.into_iter().peekable();
+
+        let mut export = Export {
+            name: tokens.next().ok_or("missing export name")?,
+            comment,
+            ..Export::default()
+        };
+
+        if tokens.next_if_eq("=").is_some() {
+            let internal_name = tokens.next().ok_or("missing internal name after '='")?;
+            export.internal_name = Some(internal_name);
+        }
+
+        if let Some(ordinal) = tokens.next_if(|token| token.starts_with('@')) {
+            // Both `@1` and `@ 1` are valid.
+            let ordinal = match &ordinal[1..] {
+                "" => tokens.next().ok_or("missing ordinal after '@'")?,
+                ordinal => ordinal.to_owned(),
+            };
+            let ordinal = ordinal
+                .parse()
+                .map_err(|_| format!("invalid ordinal '{}'", ordinal))?;
+            export.ordinal = Some(ordinal);
+
+            export.noname = tokens.next_if_eq("NONAME").is_some();
+        }
+
+        for token in tokens {
+            let flag = match token.as_str() {
+                "DATA" => &mut export.data,
+                "CONSTANT" => &mut export.constant,
+                "PRIVATE" => &mut export.private,
+                _ => return Err(format!("unexpected '{}'", token)),
+            };
+
+            if *flag {
+                return Err(format!("duplicate '{}'", token));
+            }
+            *flag = true;
+        }
+
+        Ok(export)
+    }
+
+    /// Returns the forwarding target DLL name and symbol name (or `#ordinal`)
+    /// for the forwarder exports, e.g. `PyFoo = otherdll.PyFoo`.
+    pub fn forward_target(&self) -> Option<(&str, &str)> {
+        self.internal_name.as_deref()?.split_once('.')
+    }
+}
+
+/// Formats the `EXPORTS` section entry in the normalized form.
+impl fmt::Display for Export {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write_name(f, &self.name)?;
+
+        if let Some(internal_name) = &self.internal_name {
+            f.write_str("=")?;
+            write_name(f, internal_name)?;
+        }
+
+        if let Some(ordinal) = self.ordinal {
+            write!(f, " @{}", ordinal)?;
+
+            if self.noname {
+                f.write_str(" NONAME")?;
+            }
+        }
+
+        for (flag, keyword) in [
+            (self.data, "DATA"),
+            (self.constant, "CONSTANT"),
+            (self.private, "PRIVATE"),
+        ] {
+            if flag {
+                write!(f, " {}", keyword)?;
+            }
+        }
+
+        if let Some(comment) = &self.comment {
+            write!(f, " ;{}", comment)?;
+        }
+
+        Ok(())
+    }
+}
+
+/// Parses the `LIBRARY` statement arguments.
+fn parse_library(args: &str) -> std::result::Result<Line, String> {
+    let (name, quoted, rest) = if let Some(quoted) = args.strip_prefix('"') {
+        let (name, rest) = quoted
+            .split_once('"')
+            .ok_or("unterminated quoted library name")?;
+        (name, true, rest)
+    } else {
+        let (name, rest) = args.split_once(char::is_whitespace).unwrap_or((args, ""));
+        (name, false, rest)
+    };
+
+    if name.is_empty() {
+        return Err("missing library name".to_owned());
+    }
+
+    let rest = rest.trim();
+
+    Ok(Line::Library {
+        name: name.to_owned(),
+        quoted,
+        args: Some(rest.to_owned()).filter(|rest| !rest.is_empty()),
+    })
+}
+
+/// Splits the export entry into whitespace-separated tokens,
+/// keeping `=` as a separate token and unquoting the quoted names.
+///
+/// Also returns the trailing comment text.
+fn tokenize(line: &str) -> std::result::Result<(Vec<String>, Option<String>), String> {
+    let mut tokens = Vec::new();
+    let mut chars = line.chars().peekable();
+
+    while let Some(c) = chars.next() {
+        match c {
+            c if c.is_whitespace() => {}
+            ';' => return Ok((tokens, Some(chars.collect()))),
+            '=' => tokens.push("=".to_owned()),
+            '"' => {
+                let mut token = String::new();
+                loop {
+                    match chars.next() {
+                        Some('"') => break,
+                        Some(c) => token.push(c),
+                        None => return Err("unterminated quoted name".to_owned()),
+                    }
+                }
+                tokens.push(token);
+            }
+            c => {
+                let mut token = c.to_string();
+                while let Some(c) = chars.next_if(|&c| !c.is_whitespace() && !"=;".contains(c)) {
+                    token.push(c);
+                }
+                tokens.push(token);
+            }
+        }
+    }
+
+    Ok((tokens, None))
+}
+
+/// Writes the symbol name, quoting it when necessary.
+///
+/// The names spelled as the statement keywords are quoted as well,
+/// they would end the `EXPORTS` section otherwise.
+fn write_name(f: &mut fmt::Formatter<'_>, name: &str) -> fmt::Result {
+    if name.contains(|c: char| c.is_whitespace() || "=@;".contains(c)) || KEYWORDS.contains(&name) {
+        write!(f, "\"{}\"", name)
+    } else {
+        f.write_str(name)
+    }
+}
+
+/// Constructs the invalid module-definition file error.
+fn invalid(index: usize, msg: &str) -> Error {
+    Error::InvalidDef(format!("line {}: {}", index + 1, msg))
+}
+
+#[cfg(test)]
+mod tests {
+    use proptest::collection::vec;
+    use proptest::prelude::*;
+
+    use super::*;
+
+    #[test]
+    fn parse_exports() {
+        let def = ModuleDef::parse(
+            "LIBRARY \"python3.dll\" BASE=0x1000000\n\
+             EXPORTS\n\
+             \tPy_Initialize\n\
+             Py_None DATA\n\
+             PyAlias = Py_Initialize @12 NONAME PRIVATE ; alias\n\
+             \"Quoted Name\"=internal @3\n\
+             Py_Const @ 7 CONSTANT\n",
+        )
+        .unwrap();
+
+        let exports: Vec<_> = def.exports().collect();
+        assert_eq!(exports.len(), 5);
+
+        assert_eq!(exports[0].name, "Py_Initialize");
+        assert_eq!(
+            exports[0],
+            &Export {
+                name: "Py_Initialize".to_owned(),
+                ..Export::default()
+            }
+        );
+
+        assert!(exports[1].data);
+
+        assert_eq!(exports[2].name, "PyAlias");
+        assert_eq!(exports[2].internal_name.as_deref(), Some("Py_Initialize"));
+        assert_eq!(exports[2].ordinal, Some(12));
+        assert!(exports[2].noname && exports[2].private && !exports[2].data);
+        assert_eq!(exports[2].comment.as_deref(), Some(" alias"));
+
+        assert_eq!(exports[3].name, "Quoted Name");
+        assert_eq!(exports[3].internal_name.as_deref(), Some("internal"));
+        assert_eq!(exports[3].ordinal, Some(3));
+
+        assert_eq!(exports[4].ordinal, Some(7));
+        assert!(exports[4].constant);
+
+        assert_eq!(
+            def.to_string(),
+            "LIBRARY \"python3.dll\" BASE=0x1000000\n\
+             EXPORTS\n\
+             Py_Initialize\n\
+             Py_None DATA\n\
+             PyAlias=Py_Initialize @12 NONAME PRIVATE ; alias\n\
+             \"Quoted Name\"=internal @3\n\
+             Py_Const @7 CONSTANT\n"
+        );
+    }
+
+    #[test]
+    fn invalid_exports() {
+        for line in [
+            "Py_Initialize @",
+            "Py_Initialize @x",
+            "Py_Initialize @70000",
+            "Py_Initialize =",
+            "Py_Initialize DATA DATA",
+            "Py_Initialize NONAME",
+            "Py_Initialize @1 DATA NONAME",
+            "\"Py_Initialize",
+        ] {
+            let def = format!("LIBRARY python3.dll\nEXPORTS\n{}\n", line);
+            let err = ModuleDef::parse(&def).unwrap_err();
+            assert!(err.to_string().contains("line 3"), "{}: {}", line, err);
+        }
+
+        assert!(ModuleDef::parse("LIBRARY\n").is_err());
+        assert!(ModuleDef::parse("LIBRARY \"python3.dll\n").is_err());
+    }
+
+    #[test]
+    fn round_trip() {
+        let def = "; comment\n\
+                   ;\n\
+                   LIBRARY python3.dll\n\
+                   \n\
+                   EXPORTS\n\
+                   Py_Initialize\n\
+                   Py_None DATA\n\
+                   PyAlias=Py_Initialize @1 NONAME PRIVATE\n\
+                   Py_Ordinal @2 DATA ; trailing\n\
+                   HEAPSIZE 4096\n";
+
+        let parsed = ModuleDef::parse(def).unwrap();
+        assert_eq!(parsed.to_string(), def);
+        assert_eq!(ModuleDef::parse(&parsed.to_string()).unwrap(), parsed);
+
+        // The `HEAPSIZE` statement ends the `EXPORTS` section.
+        assert_eq!(parsed.exports().count(), 4);
+    }
+
+    #[test]
+    fn embedded_defs() {
+        let defs = [
+            include_str!("python3.def"),
+            include_str!("python3t.def"),
+            include_str!("python37.def"),
+            include_str!("python313t.def"),
+            include_str!("libpypy3-c.def"),
+            include_str!("libpypy3.10-c.def"),
+        ];
+
+        for def in defs {
+            let parsed = ModuleDef::parse(def).unwrap();
+            assert_eq!(parsed.to_string(), crate::normalize_def(def));
+            assert!(parsed.exports().any(|export| export.data));
+        }
+    }
+
+    #[test]
+    fn set_library() {
+        let mut def = ModuleDef::parse("; python3\nLIBRARY \"python3.dll\"\nEXPORTS\n").unwrap();
+        def.set_library("python39.dll");
+        assert_eq!(
+            def.to_string(),
+            "; python3\nLIBRARY \"python39.dll\"\nEXPORTS\n"
+        );
+
+        assert_eq!(def.library(), Some("python39.dll"));
+
+        let mut def = ModuleDef::parse("EXPORTS\n").unwrap();
+        def.set_library("My Python.dll");
+        assert_eq!(def.to_string(), "LIBRARY \"My Python.dll\"\nEXPORTS\n");
+    }
+
+    #[test]
+    fn forwarded_exports() {
+        let mut def = ModuleDef::parse(
+            "LIBRARY python3.dll\n\
+             EXPORTS\n\
+             Py_Initialize=python313.Py_Initialize\n\
+             Py_None = python313.Py_None DATA\n\
+             PyOrdinal=python313.#12 @5\n\
+             PyAlias=Py_Initialize\n",
+        )
+        .unwrap();
+
+        let targets: Vec<_> = def.exports().map(Export::forward_target).collect();
+        assert_eq!(
+            targets,
+            [
+                Some(("python313", "Py_Initialize")),
+                Some(("python313", "Py_None")),
+                Some(("python313", "#12")),
+                None
+            ]
+        );
+
+        assert_eq!(def.strip_forwarders(), 3);
+        assert_eq!(
+            def.to_string(),
+            "LIBRARY python3.dll\n\
+             EXPORTS\n\
+             Py_Initialize\n\
+             Py_None DATA\n\
+             PyOrdinal @5\n\
+             PyAlias=Py_Initialize\n"
+        );
+        assert_eq!(def.strip_forwarders(), 0);
+    }
+
+    #[test]
+    fn mark_data() {
+        let mut def = ModuleDef::parse(
+            "EXPORTS\nPy_Version\nPy_None DATA\nPy_Const CONSTANT\nPy_Initialize\n",
+        )
+        .unwrap();
+
+        let names = ["Py_Version", "Py_None", "Py_Const", "Py_Missing"].map(str::to_owned);
+        assert_eq!(def.mark_data(&names), 2);
+        assert_eq!(
+            def.to_string(),
+            "EXPORTS\nPy_Version DATA\nPy_None DATA\nPy_Const DATA\nPy_Initialize\n"
+        );
+        assert_eq!(def.mark_data(&names), 0);
+    }
+
+    #[test]
+    fn import_by_ordinal() {
+        let mut def = ModuleDef::parse(
+            "EXPORTS
+Py_Initialize @1
+Py_None @2 NONAME DATA
+Py_Main
+",
+        )
+        .unwrap();
+
+        assert_eq!(def.import_by_ordinal(), 2);
+        assert_eq!(
+            def.to_string(),
+            "EXPORTS
+Py_Initialize @1 NONAME
+Py_None @2 NONAME DATA
+Py_Main
+"
+        );
+    }
+
+    #[test]
+    fn retain_exports() {
+        let mut def = ModuleDef::parse("EXPORTS\nPy_Initialize\nPy_None DATA\n").unwrap();
+        def.retain_exports(|export| !export.data);
+        assert_eq!(def.to_string(), "EXPORTS\nPy_Initialize\n");
+    }
+
+    /// Generates the symbol names, including the ones requiring quotes
+    /// and the ones spelled as the statement keywords.
+    fn name() -> impl Strategy<Value = String> {
+        prop_oneof![
+            4 => "[A-Za-z_?$][A-Za-z0-9_?@$.]{0,15}",
+            1 => "[A-Za-z_][A-Za-z0-9_ =@;]{0,15}[A-Za-z0-9_]",
+            1 => prop::sample::select(&KEYWORDS[..]).prop_map(str::to_owned),
+            1 => prop::sample::select(&["DATA", "CONSTANT", "PRIVATE", "NONAME"][..])
+                .prop_map(str::to_owned),
+        ]
+    }
+
+    /// Generates the comment text without the trailing whitespace.
+    fn comment() -> impl Strategy<Value = String> {
+        "([ -~]{0,20}[!-~])?"
+    }
+
+    /// Generates the `EXPORTS` section entries.
+    fn export() -> impl Strategy<Value = Export> {
+        (
+            name(),
+            prop::option::of(name()),
+            prop::option::of((any::<u16>(), any::<bool>())),
+            any::<[bool; 3]>(),
+            prop::option::of(comment()),
+        )
+            .prop_map(
+                |(name, internal_name, ordinal, [data, constant, private], comment)| Export {
+                    name,
+                    internal_name,
+                    ordinal: ordinal.map(|(ordinal, _)| ordinal),
+                    noname: ordinal.is_some_and(|(_, noname)| noname),
+                    data,
+                    constant,
+                    private,
+                    comment,
+                },
+            )
+    }
+
+    /// Generates the module-definition files with an optional `LIBRARY`
+    /// statement followed by the `EXPORTS` section.
+    fn module_def() -> impl Strategy<Value = ModuleDef> {
+        let library = prop::option::of(
+            (
+                "[A-Za-z0-9_.]{1,12}",
+                any::<bool>(),
+                prop::option::of("BASE=0x[0-9a-f]{1,8}"),
+            )
+                .prop_map(|(name, quoted, args)| Line::Library { name, quoted, args }),
+        );
+        let header = vec(
+            prop_oneof![Just(Line::Blank), comment().prop_map(Line::Comment)],
+            0..3,
+        );
+        let entries = vec(
+            prop_oneof![
+                8 => export().prop_map(Line::Export),
+                1 => Just(Line::Blank),
+                1 => comment().prop_map(Line::Comment),
+            ],
+            0..20,
+        );
+
+        (library, header, entries).prop_map(|(library, header, entries)| {
+            let mut lines: Vec<_> = library.into_iter().chain(header).collect();
+            lines.push(Line::Exports);
+            lines.extend(entries);
+            ModuleDef { lines }
+        })
+    }
+
+    proptest! {
+        #[test]
+        fn export_round_trip(export in export()) {
+            let line = export.to_string();
+            prop_assert_eq!(Export::parse(&line), Ok(export), "{}", line);
+        }
+
+        #[test]
+        fn module_def_round_trip(def in module_def()) {
+            let text = def.to_string();
+            prop_assert_eq!(ModuleDef::parse(&text).unwrap(), def, "{}", text);
+        }
+
+        /// The normalized output of any successfully parsed file
+        /// parses back to the same definitions.
+        #[test]
+        fn parse_normalized(text in "(?s)[ -~\\t\\n\"]{0,200}") {
+            if let Ok(def) = ModuleDef::parse(&text) {
+                let normalized = def.to_string();
+                prop_assert_eq!(ModuleDef::parse(&normalized).unwrap(), def, "{}", normalized);
+            }
+        }
+
+        /// Malformed input must be rejected with an error, never a panic.
+        #[test]
+        fn parse_arbitrary(text in any::<String>()) {
+            let _ = ModuleDef::parse(&text);
+            let _ = ModuleDef::parse(&format!("EXPORTS\n{}", text));
+            let _ = ModuleDef::parse(&format!("LIBRARY {}", text));
+        }
+    }
+}
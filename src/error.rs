@@ -0,0 +1,129 @@
+//! Import library generator error type
+
+use std::fmt;
+use std::io;
+
+use crate::ImportLibraryGenerator;
+
+/// Python DLL import library generator error
+#[derive(Debug)]
+#[non_exhaustive]
+pub enum Error {
+    /// I/O error while preparing the output directory or the intermediate files
+    Io(io::Error),
+    /// Unsupported compile target architecture name
+    UnsupportedArch(String),
+    /// Unsupported compile target environment ABI name
+    UnsupportedEnv(String),
+    /// Unsupported Python implementation, version or ABI flags combination
+    UnsupportedPython(String),
+    /// The requested feature is not supported by the selected tool
+    UnsupportedFeature(String),
+    /// Invalid or unsupported rustc JSON target specification
+    InvalidTargetSpec(String),
+    /// Invalid module-definition file syntax
+    InvalidDef(String),
+    /// Invalid PyO3 interpreter configuration file
+    InvalidPyO3Config(String),
+    /// Invalid or unsupported DLL image file
+    InvalidDll(String),
+    /// No usable import library tool was found for the compile target
+    ToolNotFound {
+        /// The tool programs tried, in the order of preference
+        tried: Vec<String>,
+        /// The environment variables selecting the tool
+        env_vars: Vec<String>,
+    },
+    /// The import library tool could not be started
+    ToolSpawn {
+        /// The tool invocation command line
+        command: String,
+        /// The underlying process spawn error
+        source: io::Error,
+    },
+    /// The import library tool has failed or produced unusable output
+    ToolFailed {
+        /// The tool invocation command line
+        command: String,
+        /// The tool failure description
+        reason: String,
+    },
+    /// Some of the batch generated import libraries have failed
+    BatchFailed {
+        /// The failed generator configurations with their errors
+        failed: Vec<(ImportLibraryGenerator, Error)>,
+    },
+}
+
+impl fmt::Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Error::Io(e) => e.fmt(f),
+            Error::UnsupportedArch(arch) => write!(f, "Unsupported target arch '{}'", arch),
+            Error::UnsupportedEnv(env) => write!(f, "Unsupported target env ABI '{}'", env),
+            Error::UnsupportedPython(msg) => f.write_str(msg),
+            Error::UnsupportedFeature(msg) => f.write_str(msg),
+            Error::InvalidTargetSpec(msg) => write!(f, "Invalid target specification: {}", msg),
+            Error::InvalidDef(msg) => write!(f, "Invalid module-definition file: {}", msg),
+            Error::InvalidPyO3Config(msg) => write!(f, "Invalid PyO3 config file: {}", msg),
+            Error::InvalidDll(msg) => write!(f, "Invalid DLL file: {}", msg),
+            Error::ToolNotFound { tried, env_vars } => {
+                write!(
+                    f,
+                    "No import library tool found, tried {}",
+                    tried.join(", ")
+                )?;
+                if !env_vars.is_empty() {
+                    write!(f, "; set {} to select one", env_vars.join(" or "))?;
+                }
+                Ok(())
+            }
+            Error::ToolSpawn { command, source } => {
+                write!(f, "{} failed with {}", command, source)
+            }
+            Error::ToolFailed { command, reason } => {
+                write!(f, "{} failed with {}", command, reason)
+            }
+            Error::BatchFailed { failed } => {
+                f.write_str("Import library batch generation failed")?;
+                for (i, (_, e)) in failed.iter().enumerate() {
+                    f.write_str(if i == 0 { ": " } else { "; " })?;
+                    e.fmt(f)?;
+                }
+                Ok(())
+            }
+        }
+    }
+}
+
+impl std::error::Error for Error {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            Error::Io(e) => Some(e),
+            Error::ToolSpawn { source, .. } => Some(source),
+            _ => None,
+        }
+    }
+}
+
+impl From<io::Error> for Error {
+    fn from(e: io::Error) -> Self {
+        Error::Io(e)
+    }
+}
+
+/// Converts back to `std::io::Error` for the callers still using `std::io::Result`.
+impl From<Error> for io::Error {
+    fn from(e: Error) -> Self {
+        match e {
+            Error::Io(e) => e,
+            // Preserve the error kind, e.g. `NotFound` for missing tools.
+            Error::ToolSpawn { ref source, .. } => io::Error::new(source.kind(), e),
+            Error::ToolNotFound { .. } => io::Error::new(io::ErrorKind::NotFound, e),
+            e => io::Error::other(e),
+        }
+    }
+}
+
+/// Import library generator result type
+pub type Result<T> = std::result::Result<T, Error>;
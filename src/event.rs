@@ -0,0 +1,78 @@
+//! Import library generation progress events
+
+use std::fmt;
+use std::path::Path;
+use std::sync::Arc;
+
+use crate::{DllToolFlavor, GenerationReport};
+
+/// Import library generation progress event
+///
+/// Passed to the callback registered with
+/// [`ImportLibraryGenerator::on_event()`].
+///
+/// [`ImportLibraryGenerator::on_event()`]: crate::ImportLibraryGenerator::on_event
+#[derive(Debug, Clone, Copy)]
+#[non_exhaustive]
+pub enum Event<'a> {
+    /// The tool discovery for the compile target has started
+    DiscoveryStarted {
+        /// The compile target architecture name
+        arch: &'a str,
+        /// The compile target environment ABI name
+        env: &'a str,
+        /// The Python version, if any
+        version: Option<(u8, u8)>,
+    },
+    /// The import library tool has been chosen
+    ToolSelected {
+        /// The tool program
+        tool: &'a str,
+        /// The tool command line argument style
+        flavor: DllToolFlavor,
+    },
+    /// A symbol passed to the generator or listed by the caller-provided
+    /// definitions is deprecated or has been removed in the target Python
+    /// version, see [`ImportLibraryGenerator::data_symbols()`]
+    ///
+    /// [`ImportLibraryGenerator::data_symbols()`]: crate::ImportLibraryGenerator::data_symbols
+    DeprecatedSymbol {
+        /// The symbol name
+        name: &'a str,
+        /// The CPython version deprecating the symbol, if known
+        deprecated_in: Option<(u8, u8)>,
+        /// The first CPython version no longer exporting the symbol, if any
+        removed_in: Option<(u8, u8)>,
+    },
+    /// The module-definitions file has been written,
+    /// before running the import library tool
+    DefWritten {
+        /// The module-definitions file path
+        path: &'a Path,
+    },
+    /// The import library has been generated
+    Generated {
+        /// The generated file paths and the generation timings
+        report: &'a GenerationReport,
+    },
+}
+
+/// Shared progress event callback
+#[derive(Clone)]
+pub(crate) struct EventCallback(Arc<dyn Fn(Event<'_>) + Send + Sync>);
+
+impl EventCallback {
+    pub fn new(callback: impl Fn(Event<'_>) + Send + Sync + 'static) -> Self {
+        EventCallback(Arc::new(callback))
+    }
+
+    pub fn emit(&self, event: Event<'_>) {
+        (self.0)(event)
+    }
+}
+
+impl fmt::Debug for EventCallback {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str("EventCallback")
+    }
+}
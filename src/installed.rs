@@ -0,0 +1,245 @@
+//! Installed Python DLL discovery for Windows hosts
+//!
+//! Finds the versioned `pythonXY.dll` and `pythonXYt.dll` files in `PATH`
+//! and in the standard python.org installer locations.
+
+// The discovery is only exposed on Windows hosts.
+#![cfg_attr(not(windows), allow(dead_code))]
+
+use std::env;
+use std::fs::read_dir;
+use std::path::{Path, PathBuf};
+
+use crate::{generate_batch, parse_version, AbiFlags, BatchReport, ImportLibraryGenerator};
+
+/// Python DLL installed on the host system
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[non_exhaustive]
+pub struct InstalledPython {
+    /// The discovered DLL file path, e.g. `C:\Python312\python312.dll`
+    pub dll_path: PathBuf,
+    /// Major and minor Python version
+    pub version: (u8, u8),
+    /// Python ABI flags, e.g. free-threaded for `python313t.dll`
+    pub abiflags: AbiFlags,
+}
+
+/// Finds the versioned CPython DLLs installed on the host system.
+///
+/// Searches the `PATH` directories first, then the `Python*` directories
+/// in the per-user (`%LOCALAPPDATA%\Programs\Python`) and the all-users
+/// (`%ProgramFiles%`, `%ProgramFiles(x86)%`) python.org installer locations.
+/// Only the first DLL found is listed for each version and ABI flags
+/// combination. The Stable ABI `python3.dll` and the debug `pythonXY_d.dll`
+/// are skipped.
+///
+/// Only available on Windows hosts.
+pub fn find_installed_pythons() -> Vec<InstalledPython> {
+    find_in_dirs(search_dirs())
+}
+
+/// Generates the import libraries for all the Python DLLs
+/// found by [`find_installed_pythons()`] for the specified compile target
+/// in `out_dir`.
+///
+/// The import libraries are generated from the embedded definitions data
+/// matching the discovered DLL names, the DLLs are not read. Does not stop
+/// on the first failure, e.g. for a Python version newer than this crate
+/// supports, the returned report lists both the generated import libraries
+/// and the failed configurations.
+///
+/// Only available on Windows hosts.
+pub fn generate_installed(out_dir: &Path, arch: &str, env: &str) -> BatchReport {
+    let specs: Vec<_> = find_installed_pythons()
+        .iter()
+        .map(|python| {
+            let mut generator = ImportLibraryGenerator::new(arch, env);
+            generator
+                .version(Some(python.version))
+                .abiflags_typed(python.abiflags);
+            generator
+        })
+        .collect();
+
+    generate_batch(&specs, out_dir)
+}
+
+/// Lists the directories to search for the Python DLLs in the order of preference.
+fn search_dirs() -> Vec<PathBuf> {
+    let mut dirs = Vec::new();
+
+    if let Some(paths) = env::var_os("PATH") {
+        dirs.extend(env::split_paths(&paths));
+    }
+
+    if let Some(local_app_data) = env::var_os("LOCALAPPDATA") {
+        let mut dir = PathBuf::from(local_app_data);
+        dir.push("Programs");
+        dir.push("Python");
+        dirs.extend(python_subdirs(&dir));
+    }
+
+    for var in ["ProgramFiles", "ProgramFiles(x86)"] {
+        if let Some(dir) = env::var_os(var) {
+            dirs.extend(python_subdirs(Path::new(&dir)));
+        }
+    }
+
+    dirs
+}
+
+/// Lists the `Python*` subdirectories of `dir`, e.g. `Python312`.
+fn python_subdirs(dir: &Path) -> Vec<PathBuf> {
+    let Ok(entries) = read_dir(dir) else {
+        return Vec::new();
+    };
+
+    let mut subdirs: Vec<_> = entries
+        .flatten()
+        .filter(|entry| {
+            let name = entry.file_name().to_string_lossy().to_ascii_lowercase();
+            name.starts_with("python") && entry.path().is_dir()
+        })
+        .map(|entry| entry.path())
+        .collect();
+
+    // `read_dir()` order is unspecified.
+    subdirs.sort();
+    subdirs
+}
+
+/// Finds the versioned Python DLLs in `dirs`, the first one found wins.
+fn find_in_dirs(dirs: impl IntoIterator<Item = PathBuf>) -> Vec<InstalledPython> {
+    let mut found: Vec<InstalledPython> = Vec::new();
+
+    for dir in dirs {
+        let Ok(entries) = read_dir(&dir) else {
+            continue;
+        };
+
+        let mut dlls: Vec<_> = entries
+            .flatten()
+            .filter_map(|entry| {
+                let (version, abiflags) = parse_dll_name(entry.file_name().to_str()?)?;
+                Some(InstalledPython {
+                    dll_path: entry.path(),
+                    version,
+                    abiflags,
+                })
+            })
+            .collect();
+
+        dlls.sort_by(|a, b| a.dll_path.cmp(&b.dll_path));
+
+        for dll in dlls {
+            let known = found
+                .iter()
+                .any(|python| python.version == dll.version && python.abiflags == dll.abiflags);
+
+            if !known && dll.dll_path.is_file() {
+                found.push(dll);
+            }
+        }
+    }
+
+    found
+}
+
+/// Maps the Python DLL file name such as `python312.dll` or `python313t.dll`
+/// to the Python version and ABI flags.
+///
+/// Returns `None` for the other file names, including the Stable ABI
+/// `python3.dll` and the debug `python312_d.dll`.
+pub(crate) fn parse_dll_name(file_name: &str) -> Option<((u8, u8), AbiFlags)> {
+    let file_name = file_name.to_ascii_lowercase();
+    let version = file_name.strip_prefix("python")?.strip_suffix(".dll")?;
+
+    // The `"XY"` version form only, `parse_version()` accepts `"X.Y"` too.
+    if !version.starts_with('3') || version.contains('.') {
+        return None;
+    }
+
+    match parse_version(version) {
+        Ok((version, abiflags)) if !abiflags.debug => Some((version, abiflags)),
+        _ => None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::fs::{create_dir_all, write};
+
+    use super::*;
+
+    #[test]
+    fn dll_names() {
+        let free_threaded = AbiFlags {
+            free_threaded: true,
+            debug: false,
+        };
+
+        assert_eq!(
+            parse_dll_name("python312.dll"),
+            Some(((3, 12), AbiFlags::default()))
+        );
+        assert_eq!(
+            parse_dll_name("Python37.DLL"),
+            Some(((3, 7), AbiFlags::default()))
+        );
+        assert_eq!(
+            parse_dll_name("python313t.dll"),
+            Some(((3, 13), free_threaded))
+        );
+
+        assert_eq!(parse_dll_name("python3.dll"), None);
+        assert_eq!(parse_dll_name("python3t.dll"), None);
+        assert_eq!(parse_dll_name("python312_d.dll"), None);
+        assert_eq!(parse_dll_name("python3.12.dll"), None);
+        assert_eq!(parse_dll_name("python27.dll"), None);
+        assert_eq!(parse_dll_name("python312.lib"), None);
+        assert_eq!(parse_dll_name("pythoncom312.dll"), None);
+    }
+
+    #[test]
+    fn find_dlls() {
+        let mut dir = PathBuf::from(env!("CARGO_MANIFEST_DIR"));
+        dir.push("target");
+        dir.push("python3-installed");
+
+        let first = dir.join("first");
+        let second = dir.join("second");
+        create_dir_all(&first).unwrap();
+        create_dir_all(&second).unwrap();
+
+        for (dir, file_name) in [
+            (&first, "python312.dll"),
+            (&first, "python3.dll"),
+            (&first, "vcruntime140.dll"),
+            (&second, "python312.dll"),
+            (&second, "python313t.dll"),
+        ] {
+            write(dir.join(file_name), b"").unwrap();
+        }
+
+        let found = find_in_dirs([dir.join("missing"), first.clone(), second.clone()]);
+
+        assert_eq!(
+            found,
+            [
+                InstalledPython {
+                    dll_path: first.join("python312.dll"),
+                    version: (3, 12),
+                    abiflags: AbiFlags::default(),
+                },
+                InstalledPython {
+                    dll_path: second.join("python313t.dll"),
+                    version: (3, 13),
+                    abiflags: AbiFlags {
+                        free_threaded: true,
+                        debug: false,
+                    },
+                },
+            ]
+        );
+    }
+}
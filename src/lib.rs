@@ -7,20 +7,58 @@
 //!
 //! This crate **does not require** Python 3 distribution files
 //! to be present on the (cross-)compile host system.
+//! Only the dynamically linked Python DLLs are supported, the statically
+//! linked Python builds use the static Python library from the build instead.
 //!
 //! This crate uses the binutils `dlltool` program to generate
 //! the Python DLL import libraries for MinGW-w64 targets.
 //! Setting `PYO3_MINGW_DLLTOOL` environment variable overrides
 //! the default `dlltool` command name for the target.
+//! The conventional `DLLTOOL` environment variable set by many
+//! cross-compilation frameworks is used as a fallback, both for the MinGW
+//! `dlltool` and `llvm-dlltool`, so the precedence is
+//! `PYO3_MINGW_DLLTOOL` > `DLLTOOL` > the default command name.
+//! The UCRT MinGW-w64 `dlltool` (e.g. `x86_64-w64-mingw32ucrt-dlltool`)
+//! is used when the classic one is not installed, setting `PYO3_MINGW_UCRT=1`
+//! prefers it when both are installed, and `PYO3_MINGW_UCRT=0` disables it.
+//! On Windows hosts, the unprefixed `dlltool` found in the native
+//! MSYS2 MinGW-w64 shells is used when the prefixed one is not available.
+//!
+//! The GNU-style import libraries written without the archive symbol index
+//! by some `dlltool` builds are indexed with the matching `ranlib` program,
+//! e.g. `x86_64-w64-mingw32-ranlib` or `llvm-ranlib`, which is overridden
+//! by the `PYO3_RANLIB` or the conventional `RANLIB` environment variables.
+//!
+//! Cygwin targets (aka `x86_64-pc-cygwin`) are selected by the `"cygwin"`
+//! target env name and use the GNU-style import libraries generated by
+//! the Cygwin `x86_64-pc-cygwin-dlltool` (or the unprefixed `dlltool`
+//! in the native Cygwin shells), with the same environment variable overrides.
+//! Note that the Cygwin Python DLLs are named like `libpython3.12.dll`
+//! and there is no Stable ABI `python3.dll` for Cygwin,
+//! so the DLL name has to be set via `ImportLibraryGenerator::dll_name()`,
+//! or via `ImportLibraryGenerator::naming_convention()` with
+//! `NamingConvention::Msys2` for the MSYS2 and Cygwin style DLL names.
 //!
 //! **Note:** MSVC cross-compile targets require either LLVM binutils
 //! or Zig to be available on the host system.
 //! More specifically, `python3-dll-a` requires `llvm-dlltool` executable
 //! to be present in `PATH` when targeting `*-pc-windows-msvc` from Linux.
 //!
+//! On Windows hosts, the Visual Studio `lib.exe` is preferred for the MSVC
+//! targets when installed. Its discovery relies on the `cc` crate enabled
+//! by the default `lib-exe` crate feature. Disabling the default features
+//! removes the `cc` dependency, then `llvm-dlltool` or Zig is always used.
+//!
 //! Alternatively, `ZIG_COMMAND` environment variable may be set to e.g. `"zig"`
 //! or `"python -m ziglang"`, then `zig dlltool` will be used in place
 //! of `llvm-dlltool` (or MinGW binutils).
+//! Setting `PYO3_NO_ZIG_DLLTOOL` environment variable disables this
+//! and selects the target environment specific tool instead.
+//! Note that `ZIG_COMMAND` takes precedence over `PYO3_MINGW_DLLTOOL`,
+//! a warning is emitted when both are set.
+//!
+//! The import library generation fails early with a `ToolNotFound` error
+//! listing all the tried programs when none of the tools is found.
 //!
 //! PyO3 integration
 //! ----------------
@@ -94,16 +132,94 @@
 //! specific import libraries such as `python39.lib` or `python313t.lib`.
 //!
 //! See the [`ImportLibraryGenerator`] builder API description for details.
+//! The [`support_matrix()`] function lists all the supported Python
+//! implementation, version and ABI flags combinations.
+//!
+//! For the packages shipping the import libraries for several architectures,
+//! [`generate_per_arch()`] writes them into the per-architecture subdirectories.
+//!
+//! On Windows hosts, `generate_installed()` generates the import libraries
+//! for all the versioned Python DLLs found in `PATH` and the standard
+//! installation directories, which is handy for setting up
+//! a native development environment.
+//!
+//! For a patched or a custom-built Python DLL not matching the embedded
+//! definitions, [`ImportLibraryGenerator::from_dll()`] reads the exported
+//! symbols from the DLL itself, and [`custom_def()`] accepts a module-definitions file
+//! for any other Python-compatible DLL.
+//!
+//! [`custom_def()`]: ImportLibraryGenerator::custom_def
+//!
+//! Upgrading from 0.2
+//! ------------------
+//!
+//! Version 0.3 reports the generation failures with the crate [`Error`] enum
+//! instead of `std::io::Error`: [`ImportLibraryGenerator::generate()`] returns
+//! `python3_dll_a::Result<GenerationReport>`, so the callers match on the error variants
+//! such as `ToolNotFound` or `UnsupportedPython` instead of the `io::ErrorKind`.
+//! `Error` converts into `io::Error` preserving the error kind, so the `?`
+//! operator keeps working in the functions returning `io::Result`.
+//!
+//! `generate_implib_for_target()` keeps returning `io::Result<()>`,
+//! the build scripts using only this function need no changes.
+//!
+//! The minimum supported Rust version is 1.74.
 
 #![deny(missing_docs)]
 #![allow(clippy::needless_doctest_main)]
 #![allow(clippy::uninlined_format_args)]
 
+use std::borrow::Cow;
+use std::collections::{BTreeMap, HashSet};
 use std::env;
-use std::fs::{create_dir_all, write};
-use std::io::{Error, ErrorKind, Result};
+use std::ffi::{OsStr, OsString};
+use std::fmt;
+use std::fs::{create_dir_all, read, read_to_string, remove_file, rename, write, File};
+use std::io::{self, Read, Write};
 use std::path::{Path, PathBuf};
-use std::process::Command;
+use std::process::{self, Child, Command, Stdio};
+use std::str::FromStr;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::thread::sleep;
+use std::time::{Duration, Instant};
+
+use def::ModuleDef;
+pub use error::{Error, Result};
+pub use event::Event;
+use event::EventCallback;
+#[cfg(windows)]
+pub use installed::{find_installed_pythons, generate_installed, InstalledPython};
+#[cfg(feature = "manifest")]
+pub use manifest::generate_manifest;
+use pe::DllExports;
+pub use provenance::{def_data_provenance, DefProvenance};
+use pyo3_config::PyO3Config;
+pub use report::{BatchReport, GenerationReport};
+#[cfg(feature = "stub-dll")]
+pub use stub::generate_stub_dll;
+use target_spec::TargetSpec;
+pub use temp::TempPath;
+
+mod def;
+mod error;
+mod event;
+mod installed;
+#[cfg(feature = "manifest")]
+mod manifest;
+mod pe;
+mod provenance;
+mod pyo3_config;
+mod report;
+#[cfg(feature = "stub-dll")]
+mod stub;
+mod target_spec;
+mod temp;
+
+/// Archive file signature shared by both the GNU and MSVC import libraries
+const ARCHIVE_MAGIC: &[u8] = b"!<arch>\n";
+
+/// Module-definitions file used for probing the tool capabilities
+const PROBE_DEF: &str = "LIBRARY python3-dll-a-probe.dll\nEXPORTS\nprobe\n";
 
 /// Import library file extension for the GNU environment ABI (MinGW-w64)
 const IMPLIB_EXT_GNU: &str = ".dll.a";
@@ -111,26 +227,380 @@ const IMPLIB_EXT_GNU: &str = ".dll.a";
 /// Import library file extension for the MSVC environment ABI
 const IMPLIB_EXT_MSVC: &str = ".lib";
 
+/// Conventional `dlltool` override environment variable name
+/// used by the cross-compilation frameworks
+const DLLTOOL_ENV: &str = "DLLTOOL";
+
+/// `ranlib` override environment variable name
+const RANLIB_ENV: &str = "PYO3_RANLIB";
+
+/// Conventional `ranlib` override environment variable name
+/// used by the cross-compilation frameworks
+const RANLIB_ENV_FALLBACK: &str = "RANLIB";
+
 /// Canonical MinGW-w64 `dlltool` program name
 const DLLTOOL_GNU: &str = "x86_64-w64-mingw32-dlltool";
 
 /// Canonical MinGW-w64 `dlltool` program name (32-bit version)
 const DLLTOOL_GNU_32: &str = "i686-w64-mingw32-dlltool";
 
+/// UCRT MinGW-w64 `dlltool` program name
+const DLLTOOL_GNU_UCRT: &str = "x86_64-w64-mingw32ucrt-dlltool";
+
+/// UCRT MinGW-w64 `dlltool` program name (32-bit version)
+const DLLTOOL_GNU_32_UCRT: &str = "i686-w64-mingw32ucrt-dlltool";
+
+/// UCRT MinGW-w64 `dlltool` preference environment variable name
+const MINGW_UCRT_ENV: &str = "PYO3_MINGW_UCRT";
+
+/// Unprefixed `dlltool` program name in the native MSYS2 MinGW-w64 environments
+const DLLTOOL_GNU_NATIVE: &str = "dlltool";
+
+/// Canonical Cygwin cross `dlltool` program name
+const DLLTOOL_CYGWIN: &str = "x86_64-pc-cygwin-dlltool";
+
+/// Canonical LLVM MinGW-w64 `dlltool` program name (ARM64 version)
+const DLLTOOL_GNU_ARM64: &str = "aarch64-w64-mingw32-dlltool";
+
 /// Canonical `dlltool` program name for the MSVC environment ABI (LLVM dlltool)
 const DLLTOOL_MSVC: &str = "llvm-dlltool";
 
 /// Canonical `lib` program name for the MSVC environment ABI (MSVC lib.exe)
-#[cfg(windows)]
 const LIB_MSVC: &str = "lib.exe";
 
+/// The oldest CPython version with the embedded `pythonXY.def` file
+const CPYTHON_OLDEST: (u8, u8) = (3, 7);
+
+/// The newest CPython version with the embedded `pythonXY.def` file
+const CPYTHON_LATEST: (u8, u8) = (3, 13);
+
+/// The oldest free-threaded CPython version
+const CPYTHON_FREE_THREADED_OLDEST: (u8, u8) = (3, 13);
+
+/// CPython versions tracked by the Pyston releases
+const PYSTON_VERSIONS: [(u8, u8); 1] = [(3, 8)];
+
+/// PyPy 3.7 and 3.8 module-definitions file (shared `libpypy3-c.dll` name)
+const LIBPYPY3_C_DEF: &str = include_str!("libpypy3-c.def");
+
+/// Embedded PyPy module-definitions files by the Python language version
+const PYPY_DEFS: [((u8, u8), &str, &str); 4] = [
+    ((3, 7), "libpypy3-c.def", LIBPYPY3_C_DEF),
+    ((3, 8), "libpypy3-c.def", LIBPYPY3_C_DEF),
+    ((3, 9), "libpypy3.9-c.def", include_str!("libpypy3.9-c.def")),
+    (
+        (3, 10),
+        "libpypy3.10-c.def",
+        include_str!("libpypy3.10-c.def"),
+    ),
+];
+
+/// Python API functions deprecated in CPython, by the deprecation version
+///
+/// Covers the embedding and the threading APIs pending removal,
+/// the removed symbols are detected using the embedded definitions.
+const DEPRECATED_SYMBOLS: [(&str, (u8, u8)); 16] = [
+    ("PyEval_CallFunction", (3, 9)),
+    ("PyEval_CallMethod", (3, 9)),
+    ("PyEval_CallObjectWithKeywords", (3, 9)),
+    ("PyEval_InitThreads", (3, 9)),
+    ("PySys_SetArgv", (3, 11)),
+    ("PySys_SetArgvEx", (3, 11)),
+    ("Py_SetPath", (3, 11)),
+    ("Py_SetProgramName", (3, 11)),
+    ("Py_SetPythonHome", (3, 11)),
+    ("PyImport_ImportModuleNoBlock", (3, 13)),
+    ("Py_GetExecPrefix", (3, 13)),
+    ("Py_GetPath", (3, 13)),
+    ("Py_GetPrefix", (3, 13)),
+    ("Py_GetProgramFullPath", (3, 13)),
+    ("Py_GetProgramName", (3, 13)),
+    ("Py_GetPythonHome", (3, 13)),
+];
+
+/// Stable ABI exports available in the 32-bit x86 `python3(t).dll` only
+///
+/// CPython defines `USE_STACKCHECK` for the 32-bit x86 MSVC builds only,
+/// the other DLLs forward `PyOS_CheckStack` to a missing symbol.
+const X86_ONLY_EXPORTS: [&str; 1] = ["PyOS_CheckStack"];
+
+/// The initial delay before retrying a transient tool spawn failure,
+/// doubled on each subsequent attempt
+const SPAWN_RETRY_DELAY: Duration = Duration::from_millis(100);
+
+/// Minimum Stable ABI `python3(t).def` export count
+const MIN_STABLE_ABI_EXPORTS: usize = 850;
+
+/// Minimum versioned CPython `pythonXY(t).def` export count
+const MIN_CPYTHON_EXPORTS: usize = 1500;
+
+/// Minimum PyPy `libpypy3*-c.def` export count
+const MIN_PYPY_EXPORTS: usize = 900;
+
+// Fail the build when an embedded module-definitions file gets truncated
+// by a botched update, a tiny symbol list makes a useless import library.
+const _: () = {
+    assert!(
+        def_export_count(include_str!("python3.def")) >= MIN_STABLE_ABI_EXPORTS,
+        "truncated python3.def"
+    );
+    assert!(
+        def_export_count(include_str!("python3t.def")) >= MIN_STABLE_ABI_EXPORTS,
+        "truncated python3t.def"
+    );
+    assert!(
+        def_export_count(include_str!("python37.def")) >= MIN_CPYTHON_EXPORTS,
+        "truncated python37.def"
+    );
+    assert!(
+        def_export_count(include_str!("python38.def")) >= MIN_CPYTHON_EXPORTS,
+        "truncated python38.def"
+    );
+    assert!(
+        def_export_count(include_str!("python39.def")) >= MIN_CPYTHON_EXPORTS,
+        "truncated python39.def"
+    );
+    assert!(
+        def_export_count(include_str!("python310.def")) >= MIN_CPYTHON_EXPORTS,
+        "truncated python310.def"
+    );
+    assert!(
+        def_export_count(include_str!("python311.def")) >= MIN_CPYTHON_EXPORTS,
+        "truncated python311.def"
+    );
+    assert!(
+        def_export_count(include_str!("python312.def")) >= MIN_CPYTHON_EXPORTS,
+        "truncated python312.def"
+    );
+    assert!(
+        def_export_count(include_str!("python313.def")) >= MIN_CPYTHON_EXPORTS,
+        "truncated python313.def"
+    );
+    assert!(
+        def_export_count(include_str!("python313t.def")) >= MIN_CPYTHON_EXPORTS,
+        "truncated python313t.def"
+    );
+
+    let mut i = 0;
+    while i < PYPY_DEFS.len() {
+        assert!(
+            def_export_count(PYPY_DEFS[i].2) >= MIN_PYPY_EXPORTS,
+            "truncated libpypy3*-c.def"
+        );
+        i += 1;
+    }
+};
+
+/// Counts the `EXPORTS` section entries in the module-definitions file
+/// at compile time.
+///
+/// Only the blank and the comment lines are skipped, which is enough
+/// for the embedded files.
+const fn def_export_count(def: &str) -> usize {
+    let bytes = def.as_bytes();
+    let mut count = 0;
+    let mut exports = false;
+    let mut i = 0;
+
+    while i < bytes.len() {
+        let mut start = i;
+        while i < bytes.len() && bytes[i] != b'\n' {
+            i += 1;
+        }
+        let mut end = i;
+
+        // Skip the line terminator.
+        i += 1;
+
+        while start < end && bytes[start].is_ascii_whitespace() {
+            start += 1;
+        }
+        while end > start && bytes[end - 1].is_ascii_whitespace() {
+            end -= 1;
+        }
+
+        if start == end || bytes[start] == b';' {
+            continue;
+        }
+
+        let keyword = b"EXPORTS";
+        let mut is_keyword = end - start == keyword.len();
+        let mut j = 0;
+        while is_keyword && j < keyword.len() {
+            is_keyword = bytes[start + j] == keyword[j];
+            j += 1;
+        }
+
+        if is_keyword {
+            exports = true;
+        } else if exports {
+            count += 1;
+        }
+    }
+
+    count
+}
+
 /// Python interpreter implementations
-#[derive(Debug, Clone, Copy)]
+///
+/// See [`ImportLibraryGenerator::custom_def()`] for the other
+/// Python-compatible DLLs.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[non_exhaustive]
 pub enum PythonImplementation {
     /// CPython
     CPython,
     /// PyPy
     PyPy,
+    /// Pyston
+    ///
+    /// Pyston is a binary compatible fork of CPython 3.8. Its import library
+    /// is generated from the CPython 3.8 exports, so the Pyston-specific
+    /// extension symbols are not available.
+    Pyston,
+}
+
+impl PythonImplementation {
+    /// Parses the wheel Python or ABI tag such as `"cp312"`, `"cp313t"`
+    /// or `"pp310"` into the Python implementation, version and ABI flags.
+    ///
+    /// The results map to the `ImportLibraryGenerator` `implementation()`,
+    /// `version()` and `abiflags()` settings respectively. The ABI flags
+    /// are `None` when the tag has no suffix.
+    ///
+    /// The `cp` (CPython) and `pp` (PyPy) tag prefixes are supported.
+    pub fn from_abi_tag(tag: &str) -> Result<(Self, (u8, u8), Option<String>)> {
+        let (prefix, version) =
+            tag.split_at(tag.find(|c: char| c.is_ascii_digit()).unwrap_or(tag.len()));
+
+        let implementation = match prefix {
+            "cp" => PythonImplementation::CPython,
+            "pp" => PythonImplementation::PyPy,
+            _ => {
+                let msg = format!("Unknown Python ABI tag prefix '{}' in '{}'", prefix, tag);
+                return Err(unsupported(&msg));
+            }
+        };
+
+        // The tags use the `sys.version_info` based `"XY"` version form.
+        if version.contains('.') {
+            return Err(unsupported(&format!("Invalid Python ABI tag '{}'", tag)));
+        }
+
+        let (version, flags) = parse_version(version)?;
+        let flags = Some(flags.to_string()).filter(|flags| !flags.is_empty());
+
+        Ok((implementation, version, flags))
+    }
+}
+
+/// `dlltool` command line argument style
+///
+/// Describes how the module-definition file, the output file
+/// and the target machine arguments are passed to a user-provided
+/// tool command, see [`ImportLibraryGenerator::dlltool_command()`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[non_exhaustive]
+pub enum DllToolFlavor {
+    /// MinGW `dlltool`: `--input-def`, `--output-lib`
+    Mingw,
+    /// `llvm-dlltool`: `-m`, `-d`, `-l`
+    Llvm,
+    /// MSVC `lib.exe`: `/MACHINE:`, `/DEF:`, `/OUT:`
+    LibExe,
+    /// `zig dlltool`: same as `llvm-dlltool` after the `dlltool` subcommand
+    Zig,
+    /// MSYS2 `genlib`: `-a`, `-o`, followed by the definitions file
+    Genlib,
+}
+
+/// Python ABI flags
+///
+/// Describes the Python interpreter build variant as reported
+/// by `sys.abiflags` on Unix-like systems, e.g. `"t"` for the free-threaded
+/// CPython v3.13 build aka CPython `3.13t`.
+///
+/// The normalized string form lists the flags in the CPython order (`"td"`).
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash)]
+pub struct AbiFlags {
+    /// Free-threaded (no GIL) Python build, ABI flag `t`
+    pub free_threaded: bool,
+    /// Python debug build, ABI flag `d`
+    pub debug: bool,
+}
+
+/// Stable ABI import library configuration
+///
+/// Selects between the `python3.dll` and the free-threaded `python3t.dll`
+/// Stable ABI import libraries, see [`ImportLibraryGenerator::stable_abi()`].
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash)]
+pub struct StableAbi {
+    /// Free-threaded Stable ABI, `python3t.dll`
+    pub free_threaded: bool,
+}
+
+/// Python DLL naming convention of the target Python distribution
+///
+/// Selects the versioned Python DLL name referenced by the import library
+/// and naming the generated files, see
+/// [`ImportLibraryGenerator::naming_convention()`].
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash)]
+#[non_exhaustive]
+pub enum NamingConvention {
+    /// python.org installer and embeddable package names,
+    /// e.g. `python312.dll` and `python313t.dll`
+    #[default]
+    Standard,
+    /// Conda and conda-forge names, the same as the python.org ones
+    Conda,
+    /// MSYS2 MinGW-w64 and Cygwin names, e.g. `libpython3.12.dll`
+    /// and `libpython3.13t.dll`
+    Msys2,
+}
+
+impl AbiFlags {
+    /// Returns `true` if no ABI flags are set.
+    pub fn is_empty(&self) -> bool {
+        *self == AbiFlags::default()
+    }
+}
+
+impl FromStr for AbiFlags {
+    type Err = Error;
+
+    /// Parses the ABI flags string such as `"t"`, `"d"`, `"td"` or `"dt"`.
+    fn from_str(s: &str) -> Result<Self> {
+        let mut flags = AbiFlags::default();
+
+        for c in s.chars() {
+            let flag = match c {
+                't' => &mut flags.free_threaded,
+                'd' => &mut flags.debug,
+                _ => return Err(unsupported_abiflags(s)),
+            };
+
+            // Repeated flags are likely a typo.
+            if *flag {
+                return Err(unsupported_abiflags(s));
+            }
+
+            *flag = true;
+        }
+
+        Ok(flags)
+    }
+}
+
+impl fmt::Display for AbiFlags {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if self.free_threaded {
+            f.write_str("t")?;
+        }
+        if self.debug {
+            f.write_str("d")?;
+        }
+        Ok(())
+    }
 }
 
 /// Windows import library generator for Python
@@ -146,7 +616,7 @@ pub enum PythonImplementation {
 ///
 /// ```no_run
 /// # use std::path::Path;
-/// # use python3_dll_a::ImportLibraryGenerator;
+/// # use python3_dll_a::{ImportLibraryGenerator, StableAbi};
 /// // Generate `python3.dll.a` in "target/python3-dll-a"
 /// ImportLibraryGenerator::new("x86_64", "gnu")
 ///     .generate(Path::new("target/python3-dll-a"))
@@ -175,6 +645,12 @@ pub enum PythonImplementation {
 ///     .abiflags(Some("t"))
 ///     .generate(Path::new("target/python3-lib"))
 ///     .unwrap();
+///
+/// // Generate `python3t.lib` in "target/python3-lib"
+/// ImportLibraryGenerator::new("x86_64", "msvc")
+///     .stable_abi(Some(StableAbi { free_threaded: true }))
+///     .generate(Path::new("target/python3-lib"))
+///     .unwrap();
 /// ```
 #[derive(Debug, Clone)]
 pub struct ImportLibraryGenerator {
@@ -186,30 +662,314 @@ pub struct ImportLibraryGenerator {
     version: Option<(u8, u8)>,
     /// Python interpreter implementation
     implementation: PythonImplementation,
-    /// Optional Python ABI flags
-    ///
-    /// For example, `"t"` stands for the free-threaded CPython v3.13 build
-    /// aka CPython `3.13t`.
-    abiflags: Option<String>,
+    /// Python ABI flags (for `pythonXY<abi>.dll` only)
+    abiflags: AbiFlags,
+    /// Unparsable ABI flags string passed to `abiflags()`
+    invalid_abiflags: Option<String>,
+    /// Keep the `.exp` exports file produced by `lib.exe`
+    export_file: bool,
+    /// Generate the delay-load import library
+    delay_load: bool,
+    /// MinGW `dlltool` deterministic archive mode override
+    deterministic_archive: Option<bool>,
+    /// Additional raw `dlltool` arguments
+    extra_tool_args: Vec<String>,
+    /// Additional tool process environment variables
+    tool_env: Vec<(OsString, OsString)>,
+    /// Pass unknown architecture names to the tool as-is
+    permissive: bool,
+    /// Raw `dlltool` target machine name override
+    machine_override: Option<String>,
+    /// Stable ABI level (for `python3.dll` only)
+    limited_api: Option<(u8, u8)>,
+    /// Explicit Stable ABI `python3(t).dll` product selection
+    stable_abi: Option<StableAbi>,
+    /// Python patch release number (for `pythonXY.dll` only)
+    patch_level: Option<u8>,
+    /// Use `zig dlltool` when `ZIG_COMMAND` is set
+    prefer_zig: bool,
+    /// Use MinGW `dlltool` for MSVC targets when `llvm-dlltool` is absent
+    mingw_for_msvc: bool,
+    /// User-provided base tool command
+    dlltool_command: Option<UserCommand>,
+    /// Runtime DLL name override for the `LIBRARY` statement
+    dll_name: Option<String>,
+    /// Python distribution DLL naming convention
+    naming: NamingConvention,
+    /// Caller-provided definitions replacing the embedded ones
+    custom_def: Option<String>,
+    /// Caller-provided definitions replacing the embedded ones
+    /// for a single Python implementation and version
+    def_overrides: Vec<DefOverride>,
+    /// Import library file extension override, e.g. `.lib`
+    implib_extension: Option<String>,
+    /// Exported symbols forced to be emitted as data
+    data_symbols: Vec<String>,
+    /// Import the exports with the ordinal numbers by ordinal
+    ordinal_imports: bool,
+    /// Resolve the generation plan without writing or running anything
+    dry_run: bool,
+    /// Show the verbose tool output
+    verbose: bool,
+    /// Existing import library to merge the generated one into
+    append_to: Option<PathBuf>,
+    /// Module-definitions file directory override
+    def_dir: Option<PathBuf>,
+    /// Unix permissions for the created output directories
+    out_dir_mode: Option<u32>,
+    /// Retry count for the transient tool spawn failures
+    spawn_retries: u32,
+    /// Progress event callback
+    on_event: Option<EventCallback>,
+}
+
+/// User-provided base tool command with its argument style
+#[derive(Debug)]
+struct UserCommand {
+    command: Command,
+    flavor: DllToolFlavor,
+}
+
+impl Clone for UserCommand {
+    fn clone(&self) -> Self {
+        UserCommand {
+            command: clone_command(&self.command),
+            flavor: self.flavor,
+        }
+    }
+}
+
+/// Caller-provided definitions replacing the embedded ones,
+/// see [`ImportLibraryGenerator::override_def()`]
+#[derive(Debug, Clone)]
+struct DefOverride {
+    /// The Python implementation
+    implementation: PythonImplementation,
+    /// The Python version, `None` for the Stable ABI
+    version: Option<(u8, u8)>,
+    /// The module-definitions file text
+    def: String,
 }
 
 impl ImportLibraryGenerator {
     /// Creates a new import library generator for the specified compile target.
     ///
     /// The compile target architecture name (as in `CARGO_CFG_TARGET_ARCH`)
-    /// is passed in `arch`.
+    /// is passed in `arch`. The common architecture aliases used by other
+    /// ecosystems are accepted too and normalized to the cargo names:
+    /// `"amd64"` and `"x64"` to `"x86_64"`, `"arm64"` to `"aarch64"`,
+    /// `"i386"` and `"i686"` to `"x86"`.
     ///
     /// The compile target environment ABI name (as in `CARGO_CFG_TARGET_ENV`)
-    /// is passed in `env`.
+    /// is passed in `env`. Additionally, `"gnullvm"` selects `llvm-dlltool`
+    /// for the LLVM MinGW-w64 targets, and `"cygwin"` selects the Cygwin
+    /// `dlltool` for the Cygwin targets.
+    /// The empty, `"sdk"` and `"none"` environment names reported by
+    /// some bare Windows SDK cross-compile setups mean the default
+    /// Windows ABI and are treated as `"msvc"`.
+    ///
+    /// MinGW-w64 and Cygwin targets get the `.dll.a` import libraries, MSVC targets
+    /// get the `.lib` import libraries, whichever tool is used to generate them.
     #[must_use]
     pub fn new(arch: &str, env: &str) -> Self {
         ImportLibraryGenerator {
-            arch: arch.to_string(),
-            env: env.to_string(),
+            arch: canonical_arch(arch).to_string(),
+            env: canonical_env(env).to_string(),
             version: None,
             implementation: PythonImplementation::CPython,
-            abiflags: None,
+            abiflags: AbiFlags::default(),
+            invalid_abiflags: None,
+            export_file: false,
+            delay_load: false,
+            deterministic_archive: None,
+            extra_tool_args: Vec::new(),
+            tool_env: Vec::new(),
+            permissive: false,
+            machine_override: None,
+            limited_api: None,
+            stable_abi: None,
+            patch_level: None,
+            prefer_zig: true,
+            mingw_for_msvc: false,
+            dlltool_command: None,
+            dll_name: None,
+            naming: NamingConvention::Standard,
+            custom_def: None,
+            def_overrides: Vec::new(),
+            implib_extension: None,
+            data_symbols: Vec::new(),
+            ordinal_imports: false,
+            dry_run: false,
+            verbose: false,
+            append_to: None,
+            def_dir: None,
+            out_dir_mode: None,
+            spawn_retries: 0,
+            on_event: None,
+        }
+    }
+
+    /// Creates a new import library generator for the custom compile target
+    /// described by the rustc JSON target specification file at `path`.
+    ///
+    /// The target `arch` and `env` fields are used the same way
+    /// as `CARGO_CFG_TARGET_ARCH` and `CARGO_CFG_TARGET_ENV` in `new()`.
+    /// The target `os` must be `"windows"`.
+    pub fn from_target_spec(path: &Path) -> Result<Self> {
+        let spec = TargetSpec::parse(&read_to_string(path)?)?;
+
+        Ok(Self::new(&spec.arch, &spec.env))
+    }
+
+    /// Creates a new import library generator for the Python interpreter
+    /// described by the PyO3 interpreter configuration file at `path`
+    /// (as in `PYO3_CONFIG_FILE`) and the specified compile target.
+    ///
+    /// The Python implementation, version and ABI flags (derived from
+    /// the `build_flags`) are read from the configuration file,
+    /// so that the import library matches the interpreter PyO3 is configured
+    /// for. The PyO3 configuration does not record the compile target,
+    /// `arch` and `env` are used the same way as in `new()`.
+    ///
+    /// The `abi3` configurations get the Stable ABI import library
+    /// restricted to the configured minimum Python version, see `stable_abi()`.
+    ///
+    /// The statically linked Python configurations (`shared=false`)
+    /// are rejected with [`Error::UnsupportedPython`]: the import libraries
+    /// only reference the Python DLL, the static Python library
+    /// (e.g. `libpython3.12.a`) comes from the Python build itself.
+    pub fn from_pyo3_config(path: &Path, arch: &str, env: &str) -> Result<Self> {
+        let config = PyO3Config::parse(&read_to_string(path)?)?;
+
+        if !config.shared {
+            let msg = "The PyO3 configuration links Python statically (shared=false), \
+                       import libraries only apply to the Python DLL, \
+                       link the static Python library from the Python build instead";
+            return Err(Error::UnsupportedPython(msg.to_owned()));
+        }
+
+        let mut generator = Self::new(arch, env);
+        generator
+            .version(Some(config.version))
+            .implementation(config.implementation);
+
+        // PyO3 only uses the Stable ABI with CPython.
+        if config.abi3 && matches!(config.implementation, PythonImplementation::CPython) {
+            generator.stable_abi(Some(StableAbi {
+                free_threaded: config.abiflags.free_threaded,
+            }));
+        } else {
+            generator.abiflags_typed(config.abiflags);
+        }
+
+        Ok(generator)
+    }
+
+    /// Creates a new import library generator for the DLL at `path`
+    /// using its export table instead of the embedded definitions data,
+    /// e.g. for a patched or a custom-built `pythonXY.dll`.
+    ///
+    /// The target architecture comes from the DLL machine type and the DLL
+    /// name from its export directory, `env` is used the same way as in `new()`.
+    /// The Python version and ABI flags are derived from the DLL name
+    /// when it is a versioned `pythonXY.dll` or `pythonXYt.dll` name.
+    ///
+    /// The PE export table does not tell data from code, so the exports
+    /// located in the non-executable sections are classified as data.
+    /// Use `data_symbols()` to override the classification. The version,
+    /// `limited_api()` and `stable_abi()` settings do not restrict
+    /// the exports read from the DLL.
+    ///
+    /// Returns [`Error::InvalidDll`] if the file is not a DLL with an export table.
+    pub fn from_dll(path: &Path, env: &str) -> Result<Self> {
+        let dll = DllExports::parse(&read(path)?)?;
+
+        let mut generator = Self::new(dll.arch, env);
+        if let Some((version, abiflags)) = installed::parse_dll_name(&dll.dll_name) {
+            generator.version(Some(version)).abiflags_typed(abiflags);
         }
+        generator.custom_def(&dll.to_def(), &dll.dll_name);
+
+        Ok(generator)
+    }
+
+    /// Creates a new generator for the Stable ABI `python3.dll` import
+    /// library restricted to the `level` Python version symbols,
+    /// as used by the `abi3-pyXY` PyO3 features.
+    ///
+    /// Equivalent to `new(arch, env)` followed by `limited_api(Some(level))`.
+    /// The import library is named `python3.lib` or `python3.dll.a`.
+    #[must_use]
+    pub fn preset_abi3(arch: &str, env: &str, level: (u8, u8)) -> Self {
+        let mut generator = Self::new(arch, env);
+        generator.limited_api(Some(level));
+        generator
+    }
+
+    /// Creates a new generator for the Stable ABI `python3.dll` import
+    /// library restricted to the Python 3.7 symbols, the oldest supported
+    /// level, as used by the `abi3-py37` PyO3 feature.
+    ///
+    /// Equivalent to `preset_abi3(arch, env, (3, 7))`.
+    #[must_use]
+    pub fn preset_abi3_py37(arch: &str, env: &str) -> Self {
+        Self::preset_abi3(arch, env, CPYTHON_OLDEST)
+    }
+
+    /// Creates a new generator for the Stable ABI `python3.dll` import
+    /// library restricted to the symbols of the newest CPython version
+    /// known to this crate, see [`support_matrix()`].
+    ///
+    /// This always pins the version-agnostic `python3.dll`, never a specific
+    /// `pythonXY.dll`: the import library is named `python3.lib`
+    /// or `python3.dll.a`. Updating this crate may extend the symbol set
+    /// with the newer Stable ABI additions, which then require the newer
+    /// Python at run time, so use `preset_abi3()` with a fixed level
+    /// to keep supporting the older interpreters.
+    ///
+    /// Equivalent to `preset_abi3(arch, env, level)` with the newest
+    /// CPython version in `support_matrix()`.
+    #[must_use]
+    pub fn stable_abi_latest(arch: &str, env: &str) -> Self {
+        let level = support_matrix()
+            .iter()
+            .filter(|entry| entry.implementation == PythonImplementation::CPython)
+            .filter_map(|entry| entry.version)
+            .max()
+            .unwrap_or(CPYTHON_LATEST);
+
+        Self::preset_abi3(arch, env, level)
+    }
+
+    /// Creates a new generator for the free-threaded Stable ABI
+    /// `python3t.dll` import library.
+    ///
+    /// Equivalent to `new(arch, env)` followed by
+    /// `stable_abi(Some(StableAbi { free_threaded: true }))`.
+    /// The import library is named `python3t.lib` or `python3t.dll.a`.
+    #[must_use]
+    pub fn preset_abi3t(arch: &str, env: &str) -> Self {
+        let mut generator = Self::new(arch, env);
+        generator.stable_abi(Some(StableAbi {
+            free_threaded: true,
+        }));
+        generator
+    }
+
+    /// Creates a new generator for the free-threaded CPython `pythonXYt.dll`
+    /// import library, e.g. `python313t.lib` for `(3, 13)`.
+    ///
+    /// Equivalent to `new(arch, env)` followed by `version(Some(version))`
+    /// and `abiflags(Some("t"))`. The free-threaded builds are available
+    /// since Python 3.13, `generate()` fails for the older versions.
+    #[must_use]
+    pub fn preset_freethreaded(arch: &str, env: &str, version: (u8, u8)) -> Self {
+        let mut generator = Self::new(arch, env);
+        generator.version(Some(version)).abiflags_typed(AbiFlags {
+            free_threaded: true,
+            debug: false,
+        });
+        generator
     }
 
     /// Sets major and minor version for the `pythonXY.dll` import library.
@@ -220,428 +980,5540 @@ impl ImportLibraryGenerator {
         self
     }
 
+    /// Sets the Python version and the ABI flags from a string
+    /// such as `"3.12"`, `"3.13t"` or `"312"`.
+    ///
+    /// The trailing ABI flags, if any, are set as with `abiflags()`,
+    /// otherwise the ABI flags are cleared. This is convenient for
+    /// the versions coming from the environment variables or configuration files.
+    ///
+    /// Returns an error if the version string can not be parsed.
+    pub fn version_str(&mut self, version: &str) -> Result<&mut Self> {
+        let (version, flags) = parse_version(version)?;
+
+        Ok(self.version(Some(version)).abiflags_typed(flags))
+    }
+
+    /// Sets the patch release number of the `pythonXY.dll` version,
+    /// e.g. `Some(5)` for Python 3.12.5.
+    ///
+    /// The embedded `pythonXY.def` files reflect the latest patch release
+    /// of each minor version and may include symbols absent from the older
+    /// patch release DLLs. `generate()` emits a warning about that
+    /// when the patch level is set, the generated import library
+    /// is the same for all patch releases.
+    pub fn patch_level(&mut self, patch: Option<u8>) -> &mut Self {
+        self.patch_level = patch;
+        self
+    }
+
     /// Sets the ABI flags for the `pythonXY<abi>.dll` import library.
     ///
     /// For example, `"t"` stands for the free-threaded CPython v3.13 build
     /// aka CPython `3.13t`.
     /// In this case, `python313t.dll` import library will be generated.
     ///
+    /// The free-threaded Stable ABI `python3t.dll` import library
+    /// is generated when no version is set. This is kept for compatibility,
+    /// prefer `stable_abi(Some(StableAbi { free_threaded: true }))`
+    /// for the Stable ABI import libraries.
+    ///
     /// The untagged versioned `pythonXY.dll` import library
     /// is generated by default.
+    ///
+    /// Invalid ABI flags strings are reported as errors by `generate()`,
+    /// same as the unsupported combinations: the ABI flags apply
+    /// to CPython only, the free-threaded builds require Python 3.13
+    /// or newer, and the debug builds are not supported.
+    /// See [`AbiFlags`] for the typed equivalent.
     pub fn abiflags(&mut self, flags: Option<&str>) -> &mut Self {
-        self.abiflags = flags.map(ToOwned::to_owned);
-        self
+        match flags.unwrap_or_default().parse() {
+            Ok(flags) => self.abiflags_typed(flags),
+            Err(_) => {
+                self.invalid_abiflags = flags.map(ToOwned::to_owned);
+                self
+            }
+        }
     }
 
-    /// Sets Python interpreter implementation
-    pub fn implementation(&mut self, implementation: PythonImplementation) -> &mut Self {
-        self.implementation = implementation;
+    /// Sets the typed ABI flags for the `pythonXY<abi>.dll` import library.
+    ///
+    /// Overrides any ABI flags previously set via `abiflags()`.
+    pub fn abiflags_typed(&mut self, flags: AbiFlags) -> &mut Self {
+        self.abiflags = flags;
+        self.invalid_abiflags = None;
         self
     }
 
-    /// Generates the Python DLL import library in `out_dir`.
+    /// Restricts the `python3.dll` import library to the Stable ABI
+    /// symbols available in the specified Python version.
     ///
-    /// The version-agnostic `python3.dll` import library is generated
-    /// by default unless the version-specific `pythonXY.dll` import
-    /// was requested via `version()`.
-    pub fn generate(&self, out_dir: &Path) -> Result<()> {
-        create_dir_all(out_dir)?;
+    /// This corresponds to the `Py_LIMITED_API` value, e.g. `Some((3, 10))`
+    /// for `0x030A0000` aka the `abi3-py310` PyO3 feature. Using a Stable ABI
+    /// symbol newer than the declared level then fails at link time
+    /// instead of at the extension module load time.
+    ///
+    /// The symbol availability is derived from the embedded `pythonXY.def`
+    /// exports, so levels older than Python 3.7 are not supported.
+    /// The full Stable ABI symbol set is used by default.
+    ///
+    /// When combined with `version()`, the import library still links
+    /// against the Stable ABI `python3.dll` and is named accordingly,
+    /// e.g. `python3.lib`, but only contains the Stable ABI symbols available
+    /// in both the declared level and the interpreter version. The extension
+    /// module then loads `python3.dll` at run time, which forwards the calls
+    /// to the `pythonXY.dll` of the running interpreter. Use `dll_name()`
+    /// to link against the version-specific DLL directly instead.
+    ///
+    /// The Stable ABI level is supported for CPython only.
+    pub fn limited_api(&mut self, level: Option<(u8, u8)>) -> &mut Self {
+        self.limited_api = level;
+        self
+    }
 
-        let defpath = self.write_def_file(out_dir)?;
+    /// Explicitly selects the Stable ABI `python3.dll` or the free-threaded
+    /// `python3t.dll` import library.
+    ///
+    /// The `version()` and `abiflags()` settings describe the versioned
+    /// `pythonXY<abi>.dll` import libraries. When the Stable ABI is selected,
+    /// the import library always links against `python3(t).dll`
+    /// and is named accordingly, whatever the version is. The version,
+    /// if any, restricts the symbols to the ones available in that interpreter
+    /// version, the same as `limited_api()`. Combining the Stable ABI
+    /// with the ABI flags is an error, use [`StableAbi::free_threaded`] instead.
+    ///
+    /// The callers generating `python3t.dll` via `abiflags(Some("t"))`
+    /// without a version should migrate to
+    /// `stable_abi(Some(StableAbi { free_threaded: true }))`,
+    /// and the callers using `limited_api()` with `version()`
+    /// to get the Stable ABI linkage may add `stable_abi(Some(StableAbi::default()))`
+    /// to make it explicit.
+    ///
+    /// The Stable ABI is supported for CPython only.
+    pub fn stable_abi(&mut self, stable_abi: Option<StableAbi>) -> &mut Self {
+        self.stable_abi = stable_abi;
+        self
+    }
 
-        // Try to guess the `dlltool` executable name from the target triple.
-        let dlltool_command = DllToolCommand::find_for_target(&self.arch, &self.env)?;
+    /// Overrides the Python DLL name referenced by the import library,
+    /// e.g. `Some("python39.dll")`.
+    ///
+    /// This only changes the `LIBRARY` name the linked binaries load at
+    /// run time, the symbol set is still selected by `version()`,
+    /// `abiflags()` and `limited_api()`. The generated import library
+    /// and module-definitions file names follow the DLL name,
+    /// e.g. `python39.lib` and `python39.def`.
+    ///
+    /// MinGW `dlltool` also gets the DLL name via `--dllname`, so that
+    /// the import library references the exact runtime DLL name.
+    ///
+    /// The `.dll` extension is added to the names without an extension,
+    /// e.g. `Some("python3")` for an embedder's `python3.dll` which is
+    /// a full versioned Python DLL renamed: combined with `version()`,
+    /// the import library gets the version-specific symbols but resolves
+    /// against `python3.dll` at load time.
+    ///
+    /// The DLL name must be a file name without the directory components.
+    /// The tools strip them inconsistently (MinGW `dlltool` with a warning,
+    /// `llvm-dlltool` and `zig dlltool` depending on the host path separator),
+    /// so `generate()` fails with [`Error::UnsupportedFeature`] instead.
+    /// The embedders shipping the Python DLL in a subdirectory should add it
+    /// to the DLL search path at run time, e.g. with `AddDllDirectory()`
+    /// combined with `delay_load()`.
+    pub fn dll_name(&mut self, name: Option<&str>) -> &mut Self {
+        self.dll_name = name.map(dll_file_name);
+        self
+    }
 
-        // Get the import library file extension from the used `dlltool` flavor.
-        let implib_ext = dlltool_command.implib_file_ext();
+    /// Sets the Python DLL naming convention of the target distribution,
+    /// [`NamingConvention::Standard`] by default.
+    ///
+    /// The convention selects the versioned CPython DLL name referenced
+    /// by the `LIBRARY` statement and naming the generated files,
+    /// e.g. [`NamingConvention::Msys2`] gets `libpython3.12.dll`,
+    /// `libpython3.12.dll.a` and `libpython3.12.def` for Python 3.12.
+    /// The Stable ABI import libraries always link against `python3.dll`
+    /// or `python3t.dll`, and the other Python implementations keep
+    /// their own DLL names.
+    ///
+    /// An explicit `dll_name()` takes precedence over the convention.
+    pub fn naming_convention(&mut self, naming: NamingConvention) -> &mut Self {
+        self.naming = naming;
+        self
+    }
 
-        let implib_file = self.implib_file_path(out_dir, implib_ext);
+    /// Uses the caller-provided module-definitions file text `def`
+    /// instead of the embedded definitions data, targeting any
+    /// Python-compatible DLL named `dll_name`, e.g. an embedder's
+    /// bespoke interpreter build or an implementation this crate does not
+    /// know about.
+    ///
+    /// The `LIBRARY` statement of `def` is replaced with `dll_name`, which
+    /// also names the generated files the same way as `dll_name()` does,
+    /// e.g. `mypython.dll` gets `mypython.lib` and `mypython.def`.
+    /// The `.dll` extension is added to the names without an extension.
+    /// The Python implementation, version, ABI flags and Stable ABI settings
+    /// do not restrict the provided exports, `data_symbols()`
+    /// still applies.
+    ///
+    /// `def` is parsed by `generate()`, which fails with
+    /// [`Error::InvalidDef`] on the syntax errors.
+    pub fn custom_def(&mut self, def: &str, dll_name: &str) -> &mut Self {
+        self.custom_def = Some(def.to_owned());
+        self.dll_name = Some(dll_file_name(dll_name));
+        self
+    }
 
-        // Build the complete `dlltool` command with all required arguments.
-        let mut command = dlltool_command.build(&defpath, &implib_file);
+    /// Replaces the embedded definitions data for a single Python
+    /// `implementation` and `version` (`None` for the Stable ABI)
+    /// with the module-definitions file text `def`, keeping all the other
+    /// embedded definitions, e.g. to test a freshly extracted symbol list
+    /// of a Python pre-release.
+    ///
+    /// The override applies when the generator is configured for the same
+    /// implementation and version, and the `LIBRARY` statement of `def`
+    /// names the DLL the configuration links against, e.g. `python314.dll`
+    /// or `python314t.dll` for the free-threaded build. Several overrides
+    /// for the same version with different `LIBRARY` names may be set.
+    /// `generate()` fails with [`Error::InvalidDef`] if none of the overrides
+    /// for the configured version name the expected DLL.
+    pub fn override_def(
+        &mut self,
+        implementation: PythonImplementation,
+        version: Option<(u8, u8)>,
+        def: &str,
+    ) -> &mut Self {
+        self.def_overrides.push(DefOverride {
+            implementation,
+            version,
+            def: def.to_owned(),
+        });
+        self
+    }
 
-        // Run the selected `dlltool` executable to generate the import library.
-        let status = command.status().map_err(|e| {
-            let msg = format!("{:?} failed with {}", command, e);
-            Error::new(e.kind(), msg)
-        })?;
+    /// Overrides the generated import library file extension,
+    /// e.g. `".lib"` for a MinGW-w64 target.
+    ///
+    /// MinGW-w64 targets get the `.dll.a` import libraries and MSVC targets
+    /// get the `.lib` import libraries by default. The override only changes
+    /// the file name for the build systems with rigid naming expectations,
+    /// the tool is still chosen based on the target.
+    ///
+    /// The extension must start with a dot, `generate()` returns
+    /// an error otherwise.
+    pub fn implib_extension_override(&mut self, ext: &str) -> &mut Self {
+        self.implib_extension = Some(ext.to_owned());
+        self
+    }
 
-        if status.success() {
-            Ok(())
-        } else {
-            let msg = format!("{:?} failed with {}", command, status);
-            Err(Error::new(ErrorKind::Other, msg))
-        }
-    }
-
-    /// Writes out the embedded Python library definitions file to `out_dir`.
-    ///
-    /// Returns the newly created `python3.def` or `pythonXY.def` file path.
-    fn write_def_file(&self, out_dir: &Path) -> Result<PathBuf> {
-        let (def_file, def_file_content) = match self.implementation {
-            PythonImplementation::CPython => match self.version {
-                None => ("python3.def", include_str!("python3.def")),
-                Some((3, 7)) => ("python37.def", include_str!("python37.def")),
-                Some((3, 8)) => ("python38.def", include_str!("python38.def")),
-                Some((3, 9)) => ("python39.def", include_str!("python39.def")),
-                Some((3, 10)) => ("python310.def", include_str!("python310.def")),
-                Some((3, 11)) => ("python311.def", include_str!("python311.def")),
-                Some((3, 12)) => ("python312.def", include_str!("python312.def")),
-                Some((3, 13)) => match self.abiflags.as_deref() {
-                    Some("t") => ("python313t.def", include_str!("python313t.def")),
-                    None => ("python313.def", include_str!("python313.def")),
-                    _ => return Err(Error::new(ErrorKind::Other, "Unsupported Python ABI flags")),
-                },
-                _ => return Err(Error::new(ErrorKind::Other, "Unsupported Python version")),
-            },
-            PythonImplementation::PyPy => match self.version {
-                Some((3, 7)) | Some((3, 8)) => ("libpypy3-c.def", include_str!("libpypy3-c.def")),
-                Some((3, 9)) => ("libpypy3.9-c.def", include_str!("libpypy3.9-c.def")),
-                Some((3, 10)) => ("libpypy3.10-c.def", include_str!("libpypy3.10-c.def")),
-                _ => return Err(Error::new(ErrorKind::Other, "Unsupported PyPy version")),
-            },
-        };
+    /// Forces the listed exported symbols to be emitted with the `DATA`
+    /// attribute, overriding their classification in the definitions.
+    ///
+    /// The data exports must be imported via the `__imp_` pointers,
+    /// the code exports get the jump thunks instead. A misclassified data
+    /// symbol links successfully but fails with the runtime relocation
+    /// errors or reads garbage, which is easy to get wrong when the symbol
+    /// list comes from `dumpbin /exports` not telling data from functions.
+    ///
+    /// The names not exported by the selected definitions are ignored.
+    ///
+    /// `generate()` warns about the CPython symbols deprecated in the target
+    /// Python version and the ones removed by it, also reporting them via
+    /// [`Event::DeprecatedSymbol`]. The exports listed by `custom_def()`
+    /// and `override_def()` are checked the same way.
+    pub fn data_symbols(&mut self, names: &[&str]) -> &mut Self {
+        self.data_symbols = names.iter().map(|&name| name.to_owned()).collect();
+        self
+    }
 
-        let mut defpath = out_dir.to_owned();
-        defpath.push(def_file);
+    /// Enables importing the exports listed with the ordinal numbers
+    /// in the definitions by ordinal instead of by name.
+    ///
+    /// Marks such exports as `NONAME`, which is supported by all the tools.
+    /// The ordinal imports skip the export name lookup at load time,
+    /// but break when the DLL renumbers its exports. The exports
+    /// without the ordinal numbers are still imported by name.
+    ///
+    /// Note that none of the embedded definitions files list the ordinal
+    /// numbers, as the Python DLLs do not guarantee stable ordinals,
+    /// so `generate()` fails with [`Error::UnsupportedFeature`]
+    /// when this is enabled for them.
+    ///
+    /// Disabled by default.
+    pub fn ordinal_imports(&mut self, enable: bool) -> &mut Self {
+        self.ordinal_imports = enable;
+        self
+    }
 
-        write(&defpath, def_file_content)?;
+    /// Sets Python interpreter implementation
+    pub fn implementation(&mut self, implementation: PythonImplementation) -> &mut Self {
+        self.implementation = implementation;
+        self
+    }
 
-        Ok(defpath)
+    /// Requests the `.exp` exports file to be generated alongside the `.lib`.
+    ///
+    /// The exports file is only useful when building a DLL re-exporting
+    /// the Python symbols. It is produced by MSVC `lib.exe` only,
+    /// `generate()` fails with other `dlltool` flavors when this is enabled.
+    pub fn export_file(&mut self, enable: bool) -> &mut Self {
+        self.export_file = enable;
+        self
     }
 
-    /// Builds the generated import library file name.
+    /// Requests the delay-load import library in place of the regular one.
     ///
-    /// The output file extension is passed in `libext`.
+    /// The extension module linked against the delay-load import library
+    /// loads the Python DLL lazily, on the first call of a Python API
+    /// function, rather than at the module load time. A missing DLL is then
+    /// reported at that call, which raises an SEH exception instead of
+    /// failing the `LoadLibrary()` call of the module. The delay-load helper
+    /// (`__delayLoadHelper2`, e.g. from the MinGW-w64 `-ldelayimp` library)
+    /// must be linked in as well, and the imported data such as
+    /// `_Py_NoneStruct` can not be delay-loaded.
     ///
-    /// Returns the full import library file path under `out_dir`.
-    fn implib_file_path(&self, out_dir: &Path, libext: &str) -> PathBuf {
-        let abiflags = self.abiflags.as_deref().unwrap_or_default();
-        let libname = match self.version {
-            Some((major, minor)) => {
-                format!("python{}{}{}{}", major, minor, abiflags, libext)
-            }
-            None => format!("python3{}", libext),
+    /// Only MinGW `dlltool` generates the delay-load import libraries
+    /// (via `--output-delaylib`), `generate()` fails with other `dlltool`
+    /// flavors when this is enabled. The MSVC-style linkers delay-load
+    /// the DLLs linked against the regular import libraries instead,
+    /// see the `/DELAYLOAD` linker option.
+    ///
+    /// The import library file name is not affected.
+    pub fn delay_load(&mut self, enable: bool) -> &mut Self {
+        self.delay_load = enable;
+        self
+    }
+
+    /// Merges the generated import library into the existing archive
+    /// at `existing_lib` after generating it.
+    ///
+    /// The existing archive is atomically replaced with the superset archive
+    /// containing both its original members and the Python DLL imports,
+    /// which is useful for bundling the Python imports with the imports
+    /// of the other DLLs in a single import library.
+    /// The generated import library is kept in the output directory as well.
+    ///
+    /// The archive tool is selected to match the `dlltool` flavor:
+    ///
+    /// - MinGW `ar` (e.g. `x86_64-w64-mingw32-ar`) for MinGW `dlltool`,
+    /// - `llvm-lib` for `llvm-dlltool`,
+    /// - `lib.exe` for MSVC `lib.exe`,
+    /// - `zig lib` for `zig dlltool`.
+    ///
+    /// `generate()` fails with MSYS2 `genlib` when this is set.
+    pub fn append_to(&mut self, existing_lib: &Path) -> &mut Self {
+        self.append_to = Some(existing_lib.to_owned());
+        self
+    }
+
+    /// Writes the intermediate module-definitions file to `dir`
+    /// instead of the output directory.
+    ///
+    /// This keeps the output directory free of the scratch files,
+    /// e.g. when it is a package staging directory. The directory
+    /// is created when missing, `std::env::temp_dir()` is a good choice.
+    /// The import library is still written to the `generate()` output
+    /// directory, the report lists the actual definitions file path.
+    pub fn def_temp_dir(&mut self, dir: &Path) -> &mut Self {
+        self.def_dir = Some(dir.to_owned());
+        self
+    }
+
+    /// Sets the Unix permission bits of the output directories
+    /// created by `generate()`, e.g. `0o775`.
+    ///
+    /// The directories are created with the process umask applied
+    /// by default, which may leave a shared output directory
+    /// (e.g. `PYO3_CROSS_LIB_DIR` on a multi-user build server)
+    /// unwritable for the other users' build steps. The mode is set
+    /// on the newly created directories only, including the missing parent
+    /// directories, the existing ones are left as-is.
+    ///
+    /// Has no effect on non-Unix hosts.
+    pub fn out_dir_mode(&mut self, mode: Option<u32>) -> &mut Self {
+        self.out_dir_mode = mode;
+        self
+    }
+
+    /// Retries starting the tools up to `retries` times on the transient
+    /// spawn failures, with an exponential backoff starting at 100 ms.
+    ///
+    /// The heavily loaded CI hosts occasionally fail to start the tools
+    /// because of the file locks held by antivirus scanners
+    /// (`ERROR_SHARING_VIOLATION` or `ERROR_LOCK_VIOLATION` on Windows,
+    /// `ETXTBSY` on Unix). Missing tools, permission errors
+    /// and the tool failures are never retried.
+    ///
+    /// Disabled by default.
+    pub fn spawn_retries(&mut self, retries: u32) -> &mut Self {
+        self.spawn_retries = retries;
+        self
+    }
+
+    /// Enables the verbose tool output for debugging.
+    ///
+    /// Passes the verbose option to the tools supporting it
+    /// (`-v` for MinGW `dlltool`, `/VERBOSE` for MSVC `lib.exe`)
+    /// and shows the tool standard output, which is discarded by default.
+    /// The tool error output is always shown.
+    pub fn verbose(&mut self, enable: bool) -> &mut Self {
+        self.verbose = enable;
+        self
+    }
+
+    /// Enables the dry run mode for diagnosing the tool
+    /// and the file name selection.
+    ///
+    /// When enabled, `generate()` resolves the `dlltool` command,
+    /// the target machine, the output file names and the module-definitions
+    /// data as usual, then returns the planned `GenerationReport` without
+    /// creating the output directory, writing any files or running the tool.
+    ///
+    /// Note that finding MSVC `lib.exe` on Windows hosts still involves
+    /// a quick probe run of the candidate tool in the temporary directory.
+    pub fn dry_run(&mut self, enable: bool) -> &mut Self {
+        self.dry_run = enable;
+        self
+    }
+
+    /// Selects the MinGW `dlltool` archive member header mode.
+    ///
+    /// `Some(true)` passes `--deterministic-libraries`, zeroing the archive
+    /// member timestamps, owner and permissions, `Some(false)` passes
+    /// `--non-deterministic-libraries` keeping the real values.
+    /// The `dlltool` build default is used when unset.
+    ///
+    /// Only MinGW `dlltool` supports this, `generate()` fails with other
+    /// `dlltool` flavors when this is set. Note that thin archives
+    /// are not supported, as the import library members are temporary
+    /// objects which only exist inside the archive.
+    pub fn deterministic_archive(&mut self, deterministic: Option<bool>) -> &mut Self {
+        self.deterministic_archive = deterministic;
+        self
+    }
+
+    /// Sets the environment variable `key` to `value` for the tool processes,
+    /// e.g. `ZIG_GLOBAL_CACHE_DIR` for `zig dlltool`.
+    ///
+    /// The variables apply to the import library tool and the archive merge
+    /// tool (see `append_to()`) without changing the build script
+    /// environment. The tool discovery still uses the build script `PATH`.
+    /// Can be called multiple times to set several variables.
+    pub fn tool_env(&mut self, key: impl AsRef<OsStr>, value: impl AsRef<OsStr>) -> &mut Self {
+        self.tool_env
+            .push((key.as_ref().to_owned(), value.as_ref().to_owned()));
+        self
+    }
+
+    /// Appends additional raw arguments to the `dlltool` command line.
+    ///
+    /// The arguments are inserted before the output file argument
+    /// and passed to the tool unchecked. Note that the accepted flags
+    /// are specific to the `dlltool` flavor, e.g. `-k` for `dlltool`
+    /// and `llvm-dlltool`, `/LTCG` for `lib.exe`.
+    pub fn extra_tool_args(&mut self, args: &[&str]) -> &mut Self {
+        self.extra_tool_args
+            .extend(args.iter().map(|&arg| arg.to_owned()));
+        self
+    }
+
+    /// Enables passing unknown target architecture names to the tool as-is.
+    ///
+    /// By default, only the `x86_64`, `x86`, `aarch64`, `arm64ec` and `arm` architectures
+    /// are accepted, and `generate()` fails early on anything else.
+    /// The permissive mode allows experimenting with new architectures
+    /// which the underlying `dlltool` might already support.
+    pub fn permissive(&mut self, enable: bool) -> &mut Self {
+        self.permissive = enable;
+        self
+    }
+
+    /// Overrides the target machine name passed to the tool.
+    ///
+    /// The machine name is passed verbatim as the `-m` argument
+    /// of `llvm-dlltool` and `zig dlltool` or the `/MACHINE:` argument
+    /// of `lib.exe`, e.g. `"ARM64EC"`. MinGW `dlltool` ignores it.
+    ///
+    /// This is an escape hatch for the targets not supported by this crate yet,
+    /// it bypasses the target architecture validation.
+    pub fn machine_override(&mut self, machine: &str) -> &mut Self {
+        self.machine_override = Some(machine.to_owned());
+        self
+    }
+
+    /// Controls whether `zig dlltool` is preferred when `ZIG_COMMAND` is set.
+    ///
+    /// Enabled by default. When disabled, the target environment specific
+    /// tool is used regardless of `ZIG_COMMAND`. Setting the
+    /// `PYO3_NO_ZIG_DLLTOOL` environment variable has the same effect.
+    pub fn prefer_zig(&mut self, enable: bool) -> &mut Self {
+        self.prefer_zig = enable;
+        self
+    }
+
+    /// Controls whether MinGW `dlltool` generates the MSVC target
+    /// import libraries when neither `lib.exe` nor `llvm-dlltool`
+    /// are available.
+    ///
+    /// Disabled by default. The binutils `dlltool` writes the COFF import
+    /// libraries accepted by `link.exe`, which lets the pure MinGW-w64 hosts
+    /// target MSVC without installing LLVM. The `.lib` file extension
+    /// is used as usual for the MSVC targets.
+    ///
+    /// The resulting import libraries are link-tested with `lld-link` only,
+    /// not with the Visual Studio `link.exe` itself.
+    pub fn mingw_for_msvc(&mut self, enable: bool) -> &mut Self {
+        self.mingw_for_msvc = enable;
+        self
+    }
+
+    /// Sets the base tool command to be used instead of the automatically
+    /// detected one.
+    ///
+    /// The generator appends the module-definition file, the output file
+    /// and the target machine arguments in the `flavor` style to `command`,
+    /// keeping the environment variables and the working directory
+    /// configured on it. For [`DllToolFlavor::Zig`], `command` should
+    /// invoke `zig` itself, the `dlltool` subcommand is appended too.
+    ///
+    /// This is useful for running the tool through wrappers
+    /// or with a custom child process environment.
+    pub fn dlltool_command(&mut self, command: Command, flavor: DllToolFlavor) -> &mut Self {
+        self.dlltool_command = Some(UserCommand { command, flavor });
+        self
+    }
+
+    /// Registers the callback receiving the `generate()` progress events.
+    ///
+    /// The events report the tool discovery start, the chosen tool,
+    /// the written module-definitions file and the generated import library,
+    /// which allows embedders to display progress or collect timings.
+    /// The callback is shared by the clones of this generator.
+    pub fn on_event(&mut self, callback: impl Fn(Event<'_>) + Send + Sync + 'static) -> &mut Self {
+        self.on_event = Some(EventCallback::new(callback));
+        self
+    }
+
+    /// Generates the Python DLL import library in `out_dir`.
+    ///
+    /// The version-agnostic `python3.dll` import library is generated
+    /// by default unless the version-specific `pythonXY.dll` import
+    /// was requested via `version()`.
+    ///
+    /// Returns the report listing the generated file paths.
+    pub fn generate(&self, out_dir: &Path) -> Result<GenerationReport> {
+        let def_dir = self.def_dir.as_deref().unwrap_or(out_dir);
+
+        if !self.dry_run {
+            prepare_out_dir(out_dir, self.out_dir_mode)?;
+            if def_dir != out_dir {
+                prepare_out_dir(def_dir, self.out_dir_mode)?;
+            }
+        }
+
+        if let (Some((major, minor)), Some(patch)) = (self.version, self.patch_level) {
+            warn(&format!(
+                "the embedded {}.{} symbol list reflects the latest patch release \
+                 and may include symbols absent from {}.{}.{}",
+                major, minor, major, minor, patch
+            ));
+        }
+
+        let (def_file, def_file_content) = self.def_file()?;
+        let defpath = def_dir.join(def_file);
+
+        self.check_deprecated_symbols(&def_file_content)?;
+
+        self.emit(Event::DiscoveryStarted {
+            arch: &self.arch,
+            env: &self.env,
+            version: self.version,
+        });
+
+        // The machine name override bypasses the architecture validation.
+        let permissive = self.permissive || self.machine_override.is_some();
+
+        let prefer_zig = self.prefer_zig && env::var_os("PYO3_NO_ZIG_DLLTOOL").is_none();
+
+        let mut dlltool_command = match &self.dlltool_command {
+            Some(user) => DllToolCommand::from_user(user.clone(), &self.arch, permissive)?,
+            // Try to guess the `dlltool` executable name from the target triple.
+            None => DllToolCommand::find_for_target(
+                &self.arch,
+                &self.env,
+                permissive,
+                prefer_zig,
+                self.mingw_for_msvc,
+            )?,
+        };
+
+        if let Some(machine) = &self.machine_override {
+            dlltool_command.set_machine(machine);
+        }
+
+        dlltool_command
+            .command_mut()
+            .envs(self.tool_env.iter().cloned());
+
+        if self.delay_load {
+            dlltool_command.set_delay_load()?;
+        }
+
+        self.emit(Event::ToolSelected {
+            tool: &dlltool_command.command().get_program().to_string_lossy(),
+            flavor: dlltool_command.flavor(),
+        });
+
+        // The import library naming convention follows the target env,
+        // regardless of the `dlltool` flavor used to produce it.
+        let implib_ext = match &self.implib_extension {
+            Some(ext) => check_implib_extension(ext)?,
+            None => implib_file_ext(&self.env)?,
+        };
+
+        let implib_file = self.implib_file_path(out_dir, implib_ext);
+
+        // `lib.exe` writes the exports file next to the import library.
+        let export_file = if !self.export_file {
+            None
+        } else if let DllToolCommand::LibExe { .. } = dlltool_command {
+            Some(implib_file.with_extension("exp"))
+        } else {
+            let msg = "Only MSVC lib.exe can generate the .exp exports file";
+            return Err(Error::UnsupportedFeature(msg.to_owned()));
+        };
+
+        let mut tool_args = Vec::new();
+
+        if let Some(deterministic) = self.deterministic_archive {
+            if !matches!(dlltool_command, DllToolCommand::Mingw { .. }) {
+                let msg = "Only MinGW dlltool supports selecting the archive mode";
+                return Err(Error::UnsupportedFeature(msg.to_owned()));
+            }
+
+            tool_args.push(if deterministic {
+                "--deterministic-libraries".to_owned()
+            } else {
+                "--non-deterministic-libraries".to_owned()
+            });
+        }
+
+        // MinGW `dlltool` prefers the command line DLL name over `LIBRARY`.
+        if let (Some(dll_name), DllToolCommand::Mingw { .. }) =
+            (self.resolved_dll_name(), &dlltool_command)
+        {
+            tool_args.push("--dllname".to_owned());
+            tool_args.push(dll_name.clone());
+        }
+
+        if let Some(flag) = dlltool_command.verbose_flag().filter(|_| self.verbose) {
+            tool_args.push(flag.to_owned());
+        }
+
+        tool_args.extend_from_slice(&self.extra_tool_args);
+
+        // The merge is prepared upfront to fail before writing anything.
+        let merge = match &self.append_to {
+            Some(existing_lib) => {
+                if !existing_lib.is_file() {
+                    let msg = format!(
+                        "no import library to append to in {}",
+                        existing_lib.display()
+                    );
+                    return Err(io::Error::new(io::ErrorKind::NotFound, msg).into());
+                }
+
+                let temp_merged_file = temp_file_path(existing_lib);
+                let (mut command, script) =
+                    dlltool_command.merge(&temp_merged_file, &[existing_lib, &implib_file])?;
+
+                if !self.verbose {
+                    command.stdout(Stdio::null());
+                }
+
+                command.envs(self.tool_env.iter().cloned());
+
+                Some((command, script, temp_merged_file, existing_lib))
+            }
+            None => None,
+        };
+
+        // Generate into temporary files first, so that the readers never see
+        // partially written files. The tool reads a private copy
+        // of the definitions, as the concurrent generators may share
+        // the module-definitions file name.
+        let temp_def_file = temp_file_path(&defpath);
+        let temp_published_def_file = temp_file_path(&defpath);
+        let temp_implib_file = temp_file_path(&implib_file);
+        let temp_export_file = export_file
+            .as_ref()
+            .map(|_| temp_implib_file.with_extension("exp"));
+
+        // Kept for diagnosing the tool failures.
+        let tool = dlltool_command.try_clone();
+
+        // The reported command line references the final file paths.
+        let tool_argv = command_argv(&dlltool_command.try_clone().build(
+            &defpath,
+            &implib_file,
+            &tool_args,
+        ));
+
+        // Build the complete `dlltool` command with all required arguments.
+        let mut command = dlltool_command.build(&temp_def_file, &temp_implib_file, &tool_args);
+
+        if !self.verbose {
+            // `lib.exe` prints its banner and progress messages there.
+            command.stdout(Stdio::null());
+        }
+
+        if self.dry_run {
+            return Ok(GenerationReport {
+                implib_path: implib_file,
+                def_path: defpath,
+                export_path: export_file,
+                tool: command.get_program().to_string_lossy().into_owned(),
+                tool_argv,
+                link_lib_name: self.link_lib_name(),
+                def_write_time: Duration::ZERO,
+                tool_time: Duration::ZERO,
+            });
+        }
+
+        let mut def_write_time = Duration::ZERO;
+        let mut tool_time = Duration::ZERO;
+
+        let start = Instant::now();
+        let result = write(&temp_def_file, &def_file_content)
+            .and_then(|()| write(&temp_published_def_file, &def_file_content))
+            .and_then(|()| rename(&temp_published_def_file, &defpath))
+            .map_err(Error::from)
+            .and_then(|()| {
+                def_write_time = start.elapsed();
+
+                // The definitions are published before running the tool,
+                // which is useful for diagnosing the tool failures.
+                self.emit(Event::DefWritten { path: &defpath });
+
+                let start = Instant::now();
+                let result = run_dlltool(
+                    &mut command,
+                    &temp_implib_file,
+                    temp_export_file.as_deref(),
+                    self.spawn_retries,
+                )
+                .map_err(|e| tool.diagnose_failure(e))
+                .and_then(|()| self.index_archive(&tool, &temp_implib_file));
+                tool_time = start.elapsed();
+
+                result
+            })
+            .and_then(|()| {
+                remove_file(&temp_def_file)?;
+                if let (Some(from), Some(to)) = (&temp_export_file, &export_file) {
+                    rename(from, to)?;
+                }
+                // Publish the import library last.
+                rename(&temp_implib_file, &implib_file)?;
+                Ok(())
+            });
+
+        if result.is_err() {
+            // Best effort cleanup, the original error is more relevant.
+            let _ = remove_file(&temp_def_file);
+            let _ = remove_file(&temp_published_def_file);
+            let _ = remove_file(&temp_implib_file);
+            if let Some(temp_export_file) = &temp_export_file {
+                let _ = remove_file(temp_export_file);
+            }
+        }
+
+        result?;
+
+        if let Some((mut command, script, temp_merged_file, existing_lib)) = merge {
+            let result = run_merge(
+                &mut command,
+                script.as_deref(),
+                &temp_merged_file,
+                self.spawn_retries,
+            )
+            .and_then(|()| Ok(rename(&temp_merged_file, existing_lib)?));
+
+            if result.is_err() {
+                let _ = remove_file(&temp_merged_file);
+            }
+
+            result?;
+        }
+
+        let report = GenerationReport {
+            implib_path: implib_file,
+            def_path: defpath,
+            export_path: export_file,
+            tool: command.get_program().to_string_lossy().into_owned(),
+            tool_argv,
+            link_lib_name: self.link_lib_name(),
+            def_write_time,
+            tool_time,
+        };
+
+        self.emit(Event::Generated { report: &report });
+
+        Ok(report)
+    }
+
+    /// Generates the Python DLL import library in a new temporary directory.
+    ///
+    /// This is convenient for the transient builds and test harnesses
+    /// passing the import library to a linker invocation in the same process.
+    /// The returned [`TempPath`] directory guard removes the generated files
+    /// when dropped, so it must outlive the uses of the reported paths.
+    pub fn generate_temp(&self) -> Result<(TempPath, GenerationReport)> {
+        let out_dir = TempPath::create()?;
+        let report = self.generate(&out_dir)?;
+
+        Ok((out_dir, report))
+    }
+
+    /// Returns the import library tool command line `generate()` would run
+    /// for `out_dir`, the program followed by its arguments, without writing
+    /// or running anything.
+    ///
+    /// This allows running the tool from the caller's own process
+    /// orchestration, after writing the `def_to_string()` contents
+    /// to the reported `def_path`, see [`GenerationReport::tool_argv`].
+    /// The tool environment variables, e.g. the Visual Studio ones
+    /// for `lib.exe`, and the `ranlib` run indexing the archives written
+    /// without the symbol index are not included.
+    pub fn tool_argv(&self, out_dir: &Path) -> Result<Vec<OsString>> {
+        let mut plan = self.clone();
+        plan.on_event = None;
+
+        let report = plan.dry_run(true).generate(out_dir)?;
+
+        Ok(report.tool_argv)
+    }
+
+    /// Runs `ranlib` on the generated archive lacking the symbol index,
+    /// which some `dlltool` builds do not write. The linkers reject
+    /// such archives with the "archive has no index" error.
+    fn index_archive(&self, tool: &DllToolCommand, implib_file: &Path) -> Result<()> {
+        if has_symbol_index(implib_file)? {
+            return Ok(());
+        }
+
+        let Some(mut ranlib) = tool.ranlib() else {
+            return Ok(());
+        };
+
+        ranlib
+            .envs(self.tool_env.iter().cloned())
+            .arg(implib_file)
+            .stdout(Stdio::null());
+
+        run_ranlib(&mut ranlib, implib_file, self.spawn_retries)
+    }
+
+    /// Warns about the user-provided symbol names deprecated in or removed
+    /// by the target CPython version, including the exports
+    /// of the caller-provided definitions.
+    fn check_deprecated_symbols(&self, def_file_content: &str) -> Result<()> {
+        if self.implementation != PythonImplementation::CPython {
+            return Ok(());
+        }
+
+        let caller_def = self.custom_def.is_some()
+            || self
+                .def_overrides
+                .iter()
+                .any(|o| o.implementation == self.implementation && o.version == self.version);
+
+        let def = ModuleDef::parse(def_file_content)?;
+        let mut names: Vec<&str> = self.data_symbols.iter().map(String::as_str).collect();
+
+        // The caller-provided definitions are checked against the embedded ones.
+        let exports: HashSet<&str> = if caller_def {
+            names.extend(def.exports().map(|export| export.name.as_str()));
+            HashSet::new()
+        } else {
+            def.exports().map(|export| export.name.as_str()).collect()
+        };
+
+        let version = self.version.or(self.limited_api).unwrap_or(CPYTHON_LATEST);
+        let mut history = None;
+        let mut checked = HashSet::new();
+
+        for name in names {
+            if !checked.insert(name) {
+                continue;
+            }
+
+            let deprecated_in = DEPRECATED_SYMBOLS
+                .iter()
+                .find(|&&(symbol, deprecated_in)| symbol == name && deprecated_in <= version)
+                .map(|&(_, deprecated_in)| deprecated_in);
+
+            let removed_in = if exports.contains(name) {
+                None
+            } else {
+                if history.is_none() {
+                    history = Some(export_history(version)?);
+                }
+                removal_version(name, version, history.as_deref().unwrap_or_default())
+            };
+
+            let msg = match (deprecated_in, removed_in) {
+                (_, Some((major, minor))) => {
+                    format!("{} has been removed in Python {}.{}", name, major, minor)
+                }
+                (Some((major, minor)), None) => {
+                    format!("{} is deprecated since Python {}.{}", name, major, minor)
+                }
+                (None, None) => continue,
+            };
+            warn(&msg);
+
+            self.emit(Event::DeprecatedSymbol {
+                name,
+                deprecated_in,
+                removed_in,
+            });
+        }
+
+        Ok(())
+    }
+
+    /// Passes the progress event to the registered callback, if any.
+    fn emit(&self, event: Event<'_>) {
+        if let Some(callback) = &self.on_event {
+            callback.emit(event);
+        }
+    }
+
+    /// Returns the Python library module-definitions file contents
+    /// exactly as they would be written by `generate()`.
+    ///
+    /// All the configured symbol set transformations, such as the Stable ABI
+    /// level restriction, are applied to the embedded definitions data.
+    pub fn def_to_string(&self) -> Result<String> {
+        let (_, def_file_content) = self.def_file()?;
+
+        Ok(def_file_content)
+    }
+
+    /// Builds the Python library module-definitions file.
+    ///
+    /// Returns the `python3.def` or `pythonXY.def` file name and contents.
+    ///
+    /// The embedded Stable ABI definitions list the exports of all the Windows
+    /// architectures, the x86-only ones are dropped for the other targets.
+    /// The versioned definitions list the 64-bit DLL exports, the export sets
+    /// of the other architectures are assumed to be the same.
+    ///
+    /// The same definitions serve both the MinGW and MSVC environments:
+    /// they list the undecorated C names, and all the supported tools add
+    /// the leading underscore of the 32-bit x86 `__cdecl` convention
+    /// to the import symbols themselves, e.g. `__imp__Py_Initialize`.
+    fn def_file(&self) -> Result<(String, String)> {
+        // The Python settings do not apply to the caller-provided definitions.
+        if self.custom_def.is_none() {
+            self.check_abiflags()?;
+        }
+
+        let stable_abi = self.stable_abi.is_some();
+        // The explicit Stable ABI linkage restricts the symbols
+        // to the interpreter version the same way.
+        let limited_api = self.limited_api.or(self.version.filter(|_| stable_abi));
+
+        let (def_file, def_file_content) = match (limited_api, self.version) {
+            // The caller-provided definitions replace the embedded ones.
+            _ if self.custom_def.is_some() => {
+                let dll_name = self.dll_name.as_deref().unwrap_or("python3.dll");
+                let def_file = format!("{}.def", dll_stem(dll_name));
+                (def_file, self.custom_def.clone().unwrap_or_default())
+            }
+            _ if (limited_api.is_some() || stable_abi)
+                && !matches!(self.implementation, PythonImplementation::CPython) =>
+            {
+                let msg = "The Stable ABI level applies to CPython only";
+                return Err(unsupported(msg));
+            }
+            // Stable ABI linkage for a known interpreter version
+            (Some(level), Some(version)) => {
+                let (def_file, def_file_content) = cpython_def(None, self.effective_abiflags())?;
+                let subset = limited_api_subset(def_file_content, level.min(version))?;
+                (def_file.to_owned(), subset)
+            }
+            (Some(level), None) => {
+                let (def_file, def_file_content) = self.selected_def()?;
+                (def_file, limited_api_subset(&def_file_content, level)?)
+            }
+            (None, _) => self.selected_def()?,
+        };
+
+        let mut def = ModuleDef::parse(&def_file_content)?;
+        let exports = def.exports().count();
+
+        if self.arch != "x86" {
+            def.retain_exports(|export| !X86_ONLY_EXPORTS.contains(&export.name.as_str()));
+        }
+
+        let mut changed = def.strip_forwarders() + def.mark_data(&self.data_symbols);
+
+        if self.ordinal_imports {
+            match def.import_by_ordinal() {
+                0 => {
+                    let msg = format!(
+                        "Ordinal imports are not available, {} lists no export ordinals",
+                        def_file
+                    );
+                    return Err(Error::UnsupportedFeature(msg));
+                }
+                count => changed += count,
+            }
+        }
+        let modified = changed > 0 || def.exports().count() < exports;
+
+        match self.resolved_dll_name() {
+            Some(dll_name) if dll_name.contains(['/', '\\']) => {
+                let msg = format!(
+                    "The DLL name '{}' has directory components, \
+                     which the import library tools do not support",
+                    dll_name
+                );
+                Err(Error::UnsupportedFeature(msg))
+            }
+            Some(dll_name) => {
+                def.set_library(&dll_name);
+
+                Ok((format!("{}.def", dll_stem(&dll_name)), def.to_string()))
+            }
+            None if modified => Ok((def_file.to_owned(), def.to_string())),
+            None => Ok((def_file.to_owned(), normalize_def(&def_file_content))),
+        }
+    }
+
+    /// Validates the ABI flags against the Python implementation and version.
+    fn check_abiflags(&self) -> Result<()> {
+        if let Some(flags) = &self.invalid_abiflags {
+            return Err(unsupported_abiflags(flags));
+        }
+
+        if self.stable_abi.is_some() && !self.abiflags.is_empty() {
+            let msg = format!(
+                "Python ABI flags '{}' apply to the versioned import libraries, \
+                 use StableAbi::free_threaded for the Stable ABI",
+                self.abiflags
+            );
+            return Err(unsupported(&msg));
+        }
+
+        let flags = self.effective_abiflags();
+
+        if flags.is_empty() {
+            return Ok(());
+        }
+
+        if !matches!(self.implementation, PythonImplementation::CPython) {
+            let msg = format!(
+                "Python ABI flags '{}' apply to CPython only, not {:?}",
+                flags, self.implementation
+            );
+            return Err(unsupported(&msg));
+        }
+
+        if flags.debug {
+            let msg = format!(
+                "Unsupported Python ABI flags '{}', the debug build symbol lists are not embedded",
+                flags
+            );
+            return Err(unsupported(&msg));
+        }
+
+        match self.version {
+            Some((major, minor)) if (major, minor) < CPYTHON_FREE_THREADED_OLDEST => {
+                let msg = format!(
+                    "The free-threaded 't' ABI flag requires Python {}.{} or newer, got {}.{}",
+                    CPYTHON_FREE_THREADED_OLDEST.0, CPYTHON_FREE_THREADED_OLDEST.1, major, minor
+                );
+                Err(unsupported(&msg))
+            }
+            // Free-threaded `python3t.dll` or `python3XYt.dll`
+            _ => Ok(()),
+        }
+    }
+
+    /// Returns the ABI flags of the generated import library,
+    /// taking the explicit Stable ABI selection into account.
+    fn effective_abiflags(&self) -> AbiFlags {
+        match self.stable_abi {
+            Some(stable_abi) => AbiFlags {
+                free_threaded: stable_abi.free_threaded,
+                debug: false,
+            },
+            None => self.abiflags,
+        }
+    }
+
+    /// Selects the embedded Python library definitions file.
+    ///
+    /// Returns the `python3.def` or `pythonXY.def` file name and contents.
+    fn embedded_def(&self) -> Result<(&'static str, &'static str)> {
+        match self.implementation {
+            PythonImplementation::CPython => cpython_def(self.version, self.effective_abiflags()),
+            PythonImplementation::PyPy => {
+                let pypy_def = PYPY_DEFS
+                    .iter()
+                    .find(|(version, _, _)| Some(*version) == self.version);
+
+                match pypy_def {
+                    Some(&(_, def_file, def_file_content)) => Ok((def_file, def_file_content)),
+                    None => match self.version {
+                        Some(version) => {
+                            let oldest = PYPY_DEFS[0].0;
+                            let latest = PYPY_DEFS[PYPY_DEFS.len() - 1].0;
+                            Err(unsupported_version("PyPy", version, oldest, latest))
+                        }
+                        None => Err(unsupported("Unsupported PyPy version, set the version")),
+                    },
+                }
+            }
+            PythonImplementation::Pyston => match self.version {
+                Some(version) if PYSTON_VERSIONS.contains(&version) => {
+                    cpython_def(Some(version), self.abiflags)
+                }
+                _ => Err(unsupported(
+                    "Unsupported Pyston version, only 3.8 is supported",
+                )),
+            },
+        }
+    }
+
+    /// Selects the definitions data for the Python configuration,
+    /// preferring the `override_def()` replacements to the embedded data.
+    ///
+    /// Returns the definitions file name and contents.
+    fn selected_def(&self) -> Result<(String, String)> {
+        let mut overrides = self
+            .def_overrides
+            .iter()
+            .rev()
+            .filter(|o| o.implementation == self.implementation && o.version == self.version)
+            .peekable();
+
+        if overrides.peek().is_none() {
+            let (def_file, def_file_content) = self.embedded_def()?;
+            return Ok((def_file.to_owned(), def_file_content.to_owned()));
+        }
+
+        let expected = self.default_dll_name();
+        let mut found = Vec::new();
+
+        for DefOverride { def, .. } in overrides {
+            match ModuleDef::parse(def)?.library() {
+                Some(library) if library.eq_ignore_ascii_case(&expected) => {
+                    return Ok((format!("{}.def", dll_stem(&expected)), def.clone()));
+                }
+                Some(library) => found.push(library.to_owned()),
+                None => found.push("<none>".to_owned()),
+            }
+        }
+
+        let msg = format!(
+            "the override definitions LIBRARY {} do not match {}",
+            found.join(", "),
+            expected
+        );
+        Err(Error::InvalidDef(msg))
+    }
+
+    /// Returns the Python DLL name the configuration links against
+    /// by default, e.g. `python313t.dll` or `libpypy3.10-c.dll`.
+    fn default_dll_name(&self) -> String {
+        let threading = if self.effective_abiflags().free_threaded {
+            "t"
+        } else {
+            ""
+        };
+
+        match (self.implementation, self.version) {
+            (PythonImplementation::PyPy, Some((3, minor))) if minor < 9 => {
+                "libpypy3-c.dll".to_owned()
+            }
+            (PythonImplementation::PyPy, Some((major, minor))) => {
+                format!("libpypy{}.{}-c.dll", major, minor)
+            }
+            (PythonImplementation::PyPy, None) => "libpypy3-c.dll".to_owned(),
+            (_, Some((major, minor))) => format!("python{}{}{}.dll", major, minor, threading),
+            (_, None) => format!("python3{}.dll", threading),
+        }
+    }
+
+    /// Returns the `dll_name()` override or the naming convention
+    /// DLL name, `None` for the default DLL name.
+    fn resolved_dll_name(&self) -> Option<String> {
+        if self.dll_name.is_some() {
+            return self.dll_name.clone();
+        }
+
+        let stable_abi = self.limited_api.is_some() || self.stable_abi.is_some();
+
+        match (self.naming, self.implementation, self.version) {
+            (NamingConvention::Msys2, PythonImplementation::CPython, Some((major, minor)))
+                if !stable_abi =>
+            {
+                // POSIX-style ABI flags suffix, e.g. `libpython3.13td.dll`.
+                Some(format!("libpython{}.{}{}.dll", major, minor, self.abiflags))
+            }
+            _ => None,
+        }
+    }
+
+    /// Builds the generated import library file name.
+    ///
+    /// The output file extension is passed in `libext`.
+    ///
+    /// Returns the full import library file path under `out_dir`.
+    fn implib_file_path(&self, out_dir: &Path, libext: &str) -> PathBuf {
+        let mut libpath = out_dir.to_owned();
+        libpath.push(format!("{}{}", self.link_lib_name(), libext));
+
+        libpath
+    }
+
+    /// Returns `true` if `generate()` runs an external import library tool
+    /// process, which then has to be installed.
+    ///
+    /// All the supported import library tools (`dlltool`, `llvm-dlltool`,
+    /// `lib.exe`, `zig dlltool` and `genlib`) are external programs,
+    /// so this only returns `false` in the `dry_run()` mode. Build scripts
+    /// can use it to decide whether to check for the tools up front.
+    /// Use `def_to_string()` to get the module-definitions data
+    /// without any tools.
+    pub fn requires_external_tool(&self) -> bool {
+        !self.dry_run
+    }
+
+    /// Returns the library name to link against, as passed to
+    /// the `cargo:rustc-link-lib=` build script instruction,
+    /// e.g. `python3`, `python39` or `python313t`.
+    ///
+    /// This is the generated import library file name without
+    /// the `.dll.a` or `.lib` extension, accounting for the Python version,
+    /// the ABI flags and the DLL name override. Both MinGW-w64 and MSVC
+    /// linkers find the import library by this name.
+    pub fn link_lib_name(&self) -> String {
+        let abiflags = self.effective_abiflags();
+        let threading = if abiflags.free_threaded { "t" } else { "" };
+        // The Stable ABI import libraries link against `python3.dll`.
+        let version = self
+            .version
+            .filter(|_| self.limited_api.is_none() && self.stable_abi.is_none());
+
+        match (self.resolved_dll_name(), version) {
+            (Some(dll_name), _) => dll_stem(&dll_name).to_owned(),
+            (None, Some((major, minor))) => {
+                format!("python{}{}{}", major, minor, threading)
+            }
+            (None, None) => format!("python3{}", threading),
+        }
+    }
+}
+
+/// Generates `python3.dll` import library directly from the embedded
+/// Python Stable ABI definitions data for the specified compile target.
+///
+/// The import library file named `python3.dll.a` or `python3.lib` is created
+/// in directory `out_dir`.
+///
+/// The compile target architecture name (as in `CARGO_CFG_TARGET_ARCH`)
+/// is passed in `arch`.
+///
+/// The compile target environment ABI name (as in `CARGO_CFG_TARGET_ENV`)
+/// is passed in `env`.
+///
+/// Keeps returning [`io::Result`] for the existing build scripts,
+/// the [`Error`] kinds are preserved by the conversion.
+pub fn generate_implib_for_target(out_dir: &Path, arch: &str, env: &str) -> io::Result<()> {
+    ImportLibraryGenerator::new(arch, env)
+        .generate(out_dir)
+        .map_err(io::Error::from)?;
+    Ok(())
+}
+
+/// Generates the Python DLL import library for the compile target
+/// with the generator options set by the `configure` callback.
+///
+/// This is the extensible form of [`generate_implib_for_target()`],
+/// which keeps its signature and is equivalent to passing a no-op callback.
+/// The callback receives the [`ImportLibraryGenerator`] created for
+/// `arch` and `env`, so all the builder options are available without
+/// changing this function signature when new options are added.
+/// Unlike the other shorthand functions, this one returns the generation report.
+///
+/// Migrating from `generate_implib_for_target()` only requires adding
+/// the callback, e.g. for the Python 3.10 Stable ABI level:
+///
+/// ```no_run
+/// let libdir = std::path::Path::new("target/python3-dll");
+///
+/// // Was: python3_dll_a::generate_implib_for_target(libdir, "x86_64", "msvc")
+/// let report = python3_dll_a::generate_implib_for_target_with(libdir, "x86_64", "msvc", |g| {
+///     g.limited_api(Some((3, 10)));
+/// })
+/// .expect("python3.dll import library generator failed");
+///
+/// report.emit_cargo();
+/// ```
+pub fn generate_implib_for_target_with(
+    out_dir: &Path,
+    arch: &str,
+    env: &str,
+    configure: impl FnOnce(&mut ImportLibraryGenerator),
+) -> Result<GenerationReport> {
+    let mut generator = ImportLibraryGenerator::new(arch, env);
+    configure(&mut generator);
+    generator.generate(out_dir)
+}
+
+/// Generates the Python DLL import library for the specified Python
+/// implementation, version and ABI flags for the compile target.
+///
+/// This is a shorthand for the equivalent [`ImportLibraryGenerator`]
+/// configuration, see `version()`, `implementation()` and `abiflags()`
+/// for the parameter descriptions.
+///
+/// ```no_run
+/// # use python3_dll_a::PythonImplementation;
+/// let libdir = std::path::Path::new("target/python3-dll");
+///
+/// // Generates `python313t.lib`
+/// python3_dll_a::generate_implib_for_target_versioned(
+///     libdir,
+///     "x86_64",
+///     "msvc",
+///     Some((3, 13)),
+///     PythonImplementation::CPython,
+///     Some("t"),
+/// )
+/// .expect("python313t.dll import library generator failed");
+/// ```
+pub fn generate_implib_for_target_versioned(
+    out_dir: &Path,
+    arch: &str,
+    env: &str,
+    version: Option<(u8, u8)>,
+    implementation: PythonImplementation,
+    abiflags: Option<&str>,
+) -> Result<()> {
+    ImportLibraryGenerator::new(arch, env)
+        .version(version)
+        .implementation(implementation)
+        .abiflags(abiflags)
+        .generate(out_dir)?;
+    Ok(())
+}
+
+/// Generates all the Python DLL import libraries supported by this crate
+/// for the specified compile target in `out_dir`.
+///
+/// This includes the CPython Stable ABI and version-specific libraries,
+/// their free-threaded variants and the PyPy libraries.
+///
+/// Does not stop on the first failure, the returned report lists
+/// both the generated import libraries and the failed configurations.
+pub fn generate_all(out_dir: &Path, arch: &str, env: &str) -> BatchReport {
+    generate_batch(&all_generators(arch, env), out_dir)
+}
+
+/// Generates the import libraries for all the generator configurations
+/// in `specs` in `out_dir`, e.g. for a CI build matrix.
+///
+/// Does not stop on the first failure, the returned report lists
+/// both the generated import libraries and the failed configurations.
+/// Use [`BatchReport::into_result()`] to fail if any of them have failed.
+pub fn generate_batch(specs: &[ImportLibraryGenerator], out_dir: &Path) -> BatchReport {
+    let mut report = BatchReport::default();
+
+    for generator in specs {
+        match generator.generate(out_dir) {
+            Ok(generated) => report.generated.push(generated),
+            Err(e) => report.failed.push((generator.clone(), e)),
+        }
+    }
+
+    report
+}
+
+/// Generates the import library configured by `generator` for each
+/// of the target architectures in `arches`, writing them into
+/// the `out_dir/<arch>` subdirectories, e.g. `out_dir/x86_64/python312.lib`.
+///
+/// This matches the common packaging layouts shipping the import libraries
+/// for several architectures in a single tree. The target architecture
+/// of `generator` is replaced with each one in turn, all the other settings
+/// including the target `env` are kept. The architecture subdirectories
+/// are named after the `CARGO_CFG_TARGET_ARCH` names, with the aliases
+/// such as `"amd64"` normalized.
+///
+/// All the architectures are checked before anything is written, so that
+/// an unsupported one (or one missing its import library tool) fails
+/// with its error, e.g. [`Error::UnsupportedArch`], without leaving
+/// the partial output behind.
+///
+/// Returns the generation reports keyed by the architecture name.
+pub fn generate_per_arch(
+    generator: &ImportLibraryGenerator,
+    arches: &[&str],
+    out_dir: &Path,
+) -> Result<BTreeMap<String, GenerationReport>> {
+    let generators: BTreeMap<_, _> = arches
+        .iter()
+        .map(|arch| {
+            let arch = canonical_arch(arch).to_owned();
+            let mut generator = generator.clone();
+            generator.arch.clone_from(&arch);
+            (arch, generator)
+        })
+        .collect();
+
+    for (arch, generator) in &generators {
+        // Only report the progress events of the actual generation.
+        let mut plan = generator.clone();
+        plan.on_event = None;
+        plan.dry_run(true).generate(&out_dir.join(arch))?;
+    }
+
+    generators
+        .into_iter()
+        .map(|(arch, generator)| {
+            let report = generator.generate(&out_dir.join(&arch))?;
+            Ok((arch, report))
+        })
+        .collect()
+}
+
+/// Lists the generators for all the supported Python configurations.
+fn all_generators(arch: &str, env: &str) -> Vec<ImportLibraryGenerator> {
+    let mut generators = Vec::new();
+
+    let mut generator = ImportLibraryGenerator::new(arch, env);
+    generators.push(generator.clone());
+    generators.push(
+        generator
+            .stable_abi(Some(StableAbi {
+                free_threaded: true,
+            }))
+            .clone(),
+    );
+
+    for minor in CPYTHON_OLDEST.1..=CPYTHON_LATEST.1 {
+        let mut generator = ImportLibraryGenerator::new(arch, env);
+        generators.push(generator.version(Some((3, minor))).clone());
+
+        if (3, minor) >= CPYTHON_FREE_THREADED_OLDEST {
+            generators.push(generator.abiflags(Some("t")).clone());
+        }
+    }
+
+    for (version, _) in supported_pypy_versions() {
+        let mut generator = ImportLibraryGenerator::new(arch, env);
+        generator
+            .version(Some(version))
+            .implementation(PythonImplementation::PyPy);
+        generators.push(generator);
+    }
+
+    for version in PYSTON_VERSIONS {
+        let mut generator = ImportLibraryGenerator::new(arch, env);
+        generator
+            .version(Some(version))
+            .implementation(PythonImplementation::Pyston);
+        generators.push(generator);
+    }
+
+    generators
+}
+
+/// Selects the embedded CPython library definitions file.
+///
+/// Returns the `python3.def` or `pythonXY.def` file name and contents.
+fn cpython_def(
+    version: Option<(u8, u8)>,
+    abiflags: AbiFlags,
+) -> Result<(&'static str, &'static str)> {
+    let def = match version {
+        None => match abiflags {
+            AbiFlags {
+                free_threaded: true,
+                debug: false,
+            } => ("python3t.def", include_str!("python3t.def")),
+            flags if flags.is_empty() => ("python3.def", include_str!("python3.def")),
+            flags => return Err(unsupported_abiflags(&flags.to_string())),
+        },
+        Some((3, 7)) => ("python37.def", include_str!("python37.def")),
+        Some((3, 8)) => ("python38.def", include_str!("python38.def")),
+        Some((3, 9)) => ("python39.def", include_str!("python39.def")),
+        Some((3, 10)) => ("python310.def", include_str!("python310.def")),
+        Some((3, 11)) => ("python311.def", include_str!("python311.def")),
+        Some((3, 12)) => ("python312.def", include_str!("python312.def")),
+        Some((3, 13)) => match abiflags {
+            AbiFlags {
+                free_threaded: true,
+                debug: false,
+            } => ("python313t.def", include_str!("python313t.def")),
+            flags if flags.is_empty() => ("python313.def", include_str!("python313.def")),
+            flags => return Err(unsupported_abiflags(&flags.to_string())),
+        },
+        Some(version) => {
+            return Err(unsupported_version(
+                "Python",
+                version,
+                CPYTHON_OLDEST,
+                CPYTHON_LATEST,
+            ))
+        }
+    };
+
+    Ok(def)
+}
+
+/// Restricts the Stable ABI definitions to the symbols available
+/// in the Python version `level` (as in `Py_LIMITED_API`).
+///
+/// The embedded Stable ABI data does not record when each symbol was added,
+/// so the symbol availability is derived from the exports
+/// of the matching `pythonXY.dll`. Levels newer than the newest embedded
+/// `pythonXY.def` keep the full Stable ABI symbol set.
+fn limited_api_subset(def: &str, level: (u8, u8)) -> Result<String> {
+    if level > CPYTHON_LATEST {
+        return Ok(def.to_owned());
+    } else if level < CPYTHON_OLDEST {
+        let msg = format!("Unsupported Stable ABI level {}.{}", level.0, level.1);
+        return Err(unsupported(&msg));
+    }
+
+    let (_, versioned_def) = cpython_def(Some(level), AbiFlags::default())?;
+    let available = def_exports(versioned_def)?;
+
+    let mut subset = ModuleDef::parse(def)?;
+    subset.retain_exports(|export| available.contains(&export.name));
+
+    Ok(subset.to_string())
+}
+
+/// Adds the `.dll` extension to the DLL name without an extension.
+fn dll_file_name(name: &str) -> String {
+    if name.contains('.') {
+        name.to_owned()
+    } else {
+        format!("{}.dll", name)
+    }
+}
+
+/// Strips the `.dll` extension from the DLL name.
+fn dll_stem(dll_name: &str) -> &str {
+    match dll_name.len().checked_sub(4) {
+        Some(i) if dll_name.is_char_boundary(i) && dll_name[i..].eq_ignore_ascii_case(".dll") => {
+            &dll_name[..i]
+        }
+        _ => dll_name,
+    }
+}
+
+/// Normalizes the module-definitions file text for the `dlltool` parsers.
+///
+/// Strips the UTF-8 byte order mark and converts the CRLF line endings to LF.
+/// Both can sneak into the embedded files, e.g. via Git `core.autocrlf`.
+fn normalize_def(def: &str) -> String {
+    let def = def.strip_prefix('\u{feff}').unwrap_or(def);
+
+    let mut normalized = String::with_capacity(def.len());
+
+    for line in def.lines() {
+        normalized.push_str(line);
+        normalized.push('\n');
+    }
+
+    normalized
+}
+
+/// Collects the exported symbol names in the module-definitions file.
+fn def_exports(def: &str) -> Result<HashSet<String>> {
+    let def = ModuleDef::parse(def)?;
+
+    Ok(def.exports().map(|export| export.name.clone()).collect())
+}
+
+/// Checks whether the Python DLL exports the symbol `name`
+/// for the specified target architecture, Python implementation,
+/// version and ABI flags.
+///
+/// The parameters have the same meaning as in
+/// [`generate_implib_for_target_versioned()`], `None` version selects
+/// the Stable ABI `python3(t).dll`. This lets the build scripts verify
+/// that the Python API symbols they use are available before the linker
+/// fails on them.
+///
+/// The answer matches the import library generated for `arch`,
+/// e.g. the 32-bit x86 only `PyOS_CheckStack` is not exported
+/// for `x86_64`.
+///
+/// Fails for the unsupported architectures and Python configurations.
+pub fn exports_symbol(
+    arch: &str,
+    implementation: PythonImplementation,
+    version: Option<(u8, u8)>,
+    abiflags: Option<&str>,
+    name: &str,
+) -> Result<bool> {
+    let mut generator = ImportLibraryGenerator::new(arch, "msvc");
+    generator
+        .version(version)
+        .implementation(implementation)
+        .abiflags(abiflags);
+
+    Machine::from_arch(&generator.arch, false)?;
+
+    let (_, def_file_content) = generator.def_file()?;
+    let def = ModuleDef::parse(&def_file_content)?;
+
+    let exported = def.exports().any(|export| export.name == name);
+    Ok(exported)
+}
+
+/// Lists the embedded CPython definitions exports by the minor version,
+/// up to `version`.
+fn export_history(version: (u8, u8)) -> Result<Vec<(u8, HashSet<String>)>> {
+    let mut history = Vec::new();
+
+    for minor in CPYTHON_OLDEST.1..=CPYTHON_LATEST.1 {
+        if (3, minor) > version {
+            break;
+        }
+
+        let (_, def) = cpython_def(Some((3, minor)), AbiFlags::default())?;
+        history.push((minor, def_exports(def)?));
+    }
+
+    Ok(history)
+}
+
+/// Finds the first CPython version up to `version` no longer exporting
+/// the symbol `name` exported by an older version in the export `history`.
+///
+/// Nothing is known about the versions newer than the embedded ones.
+fn removal_version(
+    name: &str,
+    version: (u8, u8),
+    history: &[(u8, HashSet<String>)],
+) -> Option<(u8, u8)> {
+    if version > CPYTHON_LATEST {
+        return None;
+    }
+
+    let last_exported = history
+        .iter()
+        .rev()
+        .find(|(_, exports)| exports.contains(name))
+        .map(|&(minor, _)| minor);
+
+    last_exported
+        .filter(|&minor| (3, minor) < version)
+        .map(|minor| (3, minor + 1))
+}
+
+/// Lists the PyPy versions supported by the embedded definitions data.
+///
+/// Returns the Python language versions paired with the matching
+/// `libpypy3*-c.def` module-definitions file names.
+/// Note that PyPy 3.7 and 3.8 share the same `libpypy3-c.dll` name.
+pub fn supported_pypy_versions() -> Vec<((u8, u8), &'static str)> {
+    PYPY_DEFS
+        .iter()
+        .map(|&(version, def_file, _)| (version, def_file))
+        .collect()
+}
+
+/// Supported Python configuration
+///
+/// Describes a Python implementation, version and ABI flags combination
+/// the embedded definitions data covers, see [`support_matrix()`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[non_exhaustive]
+pub struct SupportEntry {
+    /// Python implementation
+    pub implementation: PythonImplementation,
+    /// Python language version, `None` for the Stable ABI
+    pub version: Option<(u8, u8)>,
+    /// Python ABI flags
+    pub abiflags: AbiFlags,
+    /// The embedded module-definitions file name, e.g. `python313t.def`
+    pub def_file: &'static str,
+}
+
+impl SupportEntry {
+    /// Creates the import library generator for this configuration
+    /// and the specified compile target.
+    pub fn generator(&self, arch: &str, env: &str) -> ImportLibraryGenerator {
+        let mut generator = ImportLibraryGenerator::new(arch, env);
+        generator
+            .version(self.version)
+            .implementation(self.implementation)
+            .abiflags_typed(self.abiflags);
+        generator
+    }
+}
+
+/// Lists all the Python implementation, version and ABI flags combinations
+/// supported by the embedded definitions data, in the `generate_all()` order.
+///
+/// The list does not depend on the compile target, all the configurations
+/// are available for all the supported architectures and environments.
+pub fn support_matrix() -> Vec<SupportEntry> {
+    all_generators("x86_64", "msvc")
+        .iter()
+        .map(|generator| {
+            let (def_file, _) = generator
+                .embedded_def()
+                .expect("unsupported configuration in all_generators()");
+
+            SupportEntry {
+                implementation: generator.implementation,
+                version: generator.version,
+                abiflags: generator.effective_abiflags(),
+                def_file,
+            }
+        })
+        .collect()
+}
+
+/// `dlltool` utility command builder
+///
+/// Supports Visual Studio `lib.exe`, MinGW, LLVM and Zig `dlltool` flavors
+/// and the MSYS2 `genlib` tool.
+#[derive(Debug)]
+enum DllToolCommand {
+    /// MinGW `dlltool` program (with prefix)
+    Mingw { command: Command, delay_load: bool },
+    /// LLVM `llvm-dlltool` program (no prefix)
+    Llvm { command: Command, machine: String },
+    /// MSVC `lib.exe` program (no prefix)
+    LibExe { command: Command, machine: String },
+    /// `zig dlltool` wrapper (no prefix)
+    Zig { command: Command, machine: String },
+    /// MSYS2 `genlib` program (user-selected only)
+    Genlib { command: Command, machine: String },
+}
+
+impl DllToolCommand {
+    /// Attempts to find the best matching `dlltool` flavor for the target.
+    ///
+    /// Unknown `arch` names are passed through to the tool in `permissive` mode.
+    /// `zig dlltool` is only considered when `prefer_zig` is set.
+    /// MinGW `dlltool` is used for the MSVC targets lacking `llvm-dlltool`
+    /// when `mingw_for_msvc` is set.
+    fn find_for_target(
+        arch: &str,
+        env: &str,
+        permissive: bool,
+        prefer_zig: bool,
+        mingw_for_msvc: bool,
+    ) -> Result<DllToolCommand> {
+        Self::find_for_target_with(
+            arch,
+            env,
+            permissive,
+            prefer_zig,
+            mingw_for_msvc,
+            &is_available,
+        )
+    }
+
+    /// Same as `find_for_target()`, checking the tool programs
+    /// with `is_available` instead of looking them up in `PATH`.
+    fn find_for_target_with(
+        arch: &str,
+        env: &str,
+        permissive: bool,
+        prefer_zig: bool,
+        mingw_for_msvc: bool,
+        is_available: &dyn Fn(&Command) -> bool,
+    ) -> Result<DllToolCommand> {
+        let machine = Machine::from_arch(arch, permissive)?;
+
+        // The tools are looked up before committing to one of them,
+        // so that the missing ones are reported all at once.
+        let mut tried = Vec::new();
+        let mut env_vars = Vec::new();
+
+        // If `zig cc` is used as the linker, `zig dlltool` is the best choice.
+        if let Some(command) = find_zig().filter(|_| prefer_zig) {
+            if is_available(&command) {
+                if matches!(env, "gnu" | "cygwin") && env::var_os("PYO3_MINGW_DLLTOOL").is_some() {
+                    warn(
+                        "ZIG_COMMAND takes precedence, PYO3_MINGW_DLLTOOL is ignored; \
+                         set PYO3_NO_ZIG_DLLTOOL to use PYO3_MINGW_DLLTOOL instead",
+                    );
+                }
+
+                let machine = machine.as_llvm().to_owned();
+                return Ok(DllToolCommand::Zig { command, machine });
+            }
+
+            warn(&format!(
+                "ZIG_COMMAND program {} is not found, ignoring it",
+                command_line(&command)
+            ));
+            tried.push(command_line(&command));
+        }
+
+        match env {
+            // 64-bit and 32-bit MinGW-w64 (aka `{x86_64,i686}-pc-windows-gnu`)
+            "gnu" => {
+                let dlltool = get_mingw_dlltool(arch)?;
+                if is_available(dlltool.command()) {
+                    return Ok(dlltool);
+                }
+
+                tried.push(command_line(dlltool.command()));
+                env_vars.extend(["PYO3_MINGW_DLLTOOL", DLLTOOL_ENV]);
+            }
+
+            // Cygwin (aka `x86_64-pc-cygwin`), same import libraries as MinGW-w64
+            "cygwin" => {
+                let command = get_cygwin_dlltool(arch)?;
+                if is_available(&command) {
+                    return Ok(DllToolCommand::Mingw {
+                        command,
+                        delay_load: false,
+                    });
+                }
+
+                tried.push(command_line(&command));
+                env_vars.extend(["PYO3_MINGW_DLLTOOL", DLLTOOL_ENV]);
+            }
+
+            // MSVC ABI (multiarch)
+            "msvc" => {
+                let candidates = find_lib_exe(arch);
+
+                if !candidates.is_empty() {
+                    for command in candidates {
+                        let machine = machine.as_msvc().to_owned();
+                        let lib_exe = DllToolCommand::LibExe { command, machine };
+
+                        // The registry lookup might find a `lib.exe` unable to target
+                        // the requested machine in misconfigured MSVC installations.
+                        if lib_exe.probe() {
+                            return Ok(lib_exe);
+                        }
+
+                        tried.push(command_line(lib_exe.command()));
+                    }
+
+                    warn(&format!(
+                        "{} can not target '{}', falling back to {}",
+                        LIB_MSVC, arch, DLLTOOL_MSVC
+                    ));
+                }
+
+                let command = llvm_dlltool();
+                if is_available(&command) {
+                    let machine = machine.as_llvm().to_owned();
+                    return Ok(DllToolCommand::Llvm { command, machine });
+                }
+
+                tried.push(command_line(&command));
+                env_vars.push(DLLTOOL_ENV);
+
+                // MinGW `dlltool` also writes the COFF import libraries.
+                if mingw_for_msvc && env_command(DLLTOOL_ENV).is_none() {
+                    let dlltool = get_mingw_dlltool(arch)?;
+                    if is_available(dlltool.command()) {
+                        return Ok(dlltool);
+                    }
+
+                    tried.push(command_line(dlltool.command()));
+                    env_vars.push("PYO3_MINGW_DLLTOOL");
+                }
+            }
+            // LLVM MinGW-w64 toolchain (aka `*-pc-windows-gnullvm`)
+            "gnullvm" => {
+                let command = llvm_dlltool();
+                if is_available(&command) {
+                    let machine = machine.as_llvm().to_owned();
+                    return Ok(DllToolCommand::Llvm { command, machine });
+                }
+
+                tried.push(command_line(&command));
+                env_vars.push(DLLTOOL_ENV);
+            }
+            _ => return Err(Error::UnsupportedEnv(env.to_owned())),
+        }
+
+        if prefer_zig {
+            env_vars.push("ZIG_COMMAND");
+        }
+
+        Err(Error::ToolNotFound {
+            tried,
+            env_vars: env_vars.into_iter().map(str::to_owned).collect(),
+        })
+    }
+
+    /// Wraps the user-provided base tool command for the target.
+    fn from_user(user: UserCommand, arch: &str, permissive: bool) -> Result<DllToolCommand> {
+        let UserCommand { command, flavor } = user;
+        let machine = Machine::from_arch(arch, permissive)?;
+
+        Ok(match flavor {
+            DllToolFlavor::Mingw => DllToolCommand::Mingw {
+                command,
+                delay_load: false,
+            },
+            DllToolFlavor::Llvm => DllToolCommand::Llvm {
+                command,
+                machine: machine.as_llvm().to_owned(),
+            },
+            DllToolFlavor::LibExe => DllToolCommand::LibExe {
+                command,
+                machine: machine.as_msvc().to_owned(),
+            },
+            DllToolFlavor::Zig => DllToolCommand::Zig {
+                command,
+                machine: machine.as_llvm().to_owned(),
+            },
+            DllToolFlavor::Genlib => DllToolCommand::Genlib {
+                command,
+                machine: machine.as_genlib().to_owned(),
+            },
+        })
+    }
+
+    /// Checks if the tool can generate an import library for the target
+    /// machine by running it on a tiny module-definitions file.
+    fn probe(&self) -> bool {
+        let temp_dir = env::temp_dir();
+        let defpath = temp_file_path(&temp_dir.join("python3-dll-a-probe.def"));
+        let libpath = defpath.with_extension("lib");
+
+        let mut command = self.try_clone().build(&defpath, &libpath, &[]);
+        command.stdout(Stdio::null()).stderr(Stdio::null());
+
+        let success = write(&defpath, PROBE_DEF).is_ok()
+            && command.status().is_ok_and(|status| status.success())
+            && is_archive_file(&libpath).unwrap_or(false);
+
+        let _ = remove_file(&defpath);
+        let _ = remove_file(&libpath);
+        // `lib.exe` also leaves an exports file behind.
+        let _ = remove_file(libpath.with_extension("exp"));
+
+        success
+    }
+
+    /// Explains the tool failure caused by the unsupported target machine.
+    ///
+    /// Probes the tool only after it has failed, keeping the successful
+    /// runs cheap. The other errors are returned unchanged.
+    /// MinGW `dlltool` builds target a single machine implied by their name.
+    fn diagnose_failure(&self, err: Error) -> Error {
+        let machine = match self {
+            Self::Mingw { .. } => return err,
+            Self::Llvm { machine, .. }
+            | Self::LibExe { machine, .. }
+            | Self::Zig { machine, .. }
+            | Self::Genlib { machine, .. } => machine,
+        };
+
+        match err {
+            Error::ToolFailed { command, reason } if !self.probe() => Error::ToolFailed {
+                command,
+                reason: format!(
+                    "{}, the tool does not support the '{}' target machine",
+                    reason, machine
+                ),
+            },
+            err => err,
+        }
+    }
+
+    /// Duplicates the tool command, including its environment.
+    fn try_clone(&self) -> DllToolCommand {
+        match self {
+            Self::Mingw {
+                command,
+                delay_load,
+            } => Self::Mingw {
+                command: clone_command(command),
+                delay_load: *delay_load,
+            },
+            Self::Llvm { command, machine } => Self::Llvm {
+                command: clone_command(command),
+                machine: machine.clone(),
+            },
+            Self::LibExe { command, machine } => Self::LibExe {
+                command: clone_command(command),
+                machine: machine.clone(),
+            },
+            Self::Zig { command, machine } => Self::Zig {
+                command: clone_command(command),
+                machine: machine.clone(),
+            },
+            Self::Genlib { command, machine } => Self::Genlib {
+                command: clone_command(command),
+                machine: machine.clone(),
+            },
+        }
+    }
+
+    /// Returns the base tool command.
+    fn command(&self) -> &Command {
+        match self {
+            Self::Mingw { command, .. }
+            | Self::Llvm { command, .. }
+            | Self::LibExe { command, .. }
+            | Self::Zig { command, .. }
+            | Self::Genlib { command, .. } => command,
+        }
+    }
+
+    /// Returns the mutable base tool command.
+    fn command_mut(&mut self) -> &mut Command {
+        match self {
+            Self::Mingw { command, .. }
+            | Self::Llvm { command, .. }
+            | Self::LibExe { command, .. }
+            | Self::Zig { command, .. }
+            | Self::Genlib { command, .. } => command,
+        }
+    }
+
+    /// Returns the tool command line argument style.
+    fn flavor(&self) -> DllToolFlavor {
+        match self {
+            Self::Mingw { .. } => DllToolFlavor::Mingw,
+            Self::Llvm { .. } => DllToolFlavor::Llvm,
+            Self::LibExe { .. } => DllToolFlavor::LibExe,
+            Self::Zig { .. } => DllToolFlavor::Zig,
+            Self::Genlib { .. } => DllToolFlavor::Genlib,
+        }
+    }
+
+    /// Replaces the target machine name for the flavors using it.
+    fn set_machine(&mut self, name: &str) {
+        match self {
+            Self::Mingw { .. } => {}
+            Self::Llvm { machine, .. }
+            | Self::LibExe { machine, .. }
+            | Self::Zig { machine, .. }
+            | Self::Genlib { machine, .. } => {
+                *machine = name.to_owned();
+            }
+        }
+    }
+
+    /// Returns the verbose output option of the flavors supporting it.
+    ///
+    /// `llvm-dlltool` has no such option.
+    fn verbose_flag(&self) -> Option<&'static str> {
+        match self {
+            Self::Mingw { .. } => Some("-v"),
+            Self::LibExe { .. } => Some("/VERBOSE"),
+            Self::Llvm { .. } | Self::Zig { .. } | Self::Genlib { .. } => None,
+        }
+    }
+
+    /// Builds the archive tool command merging the `inputs` import libraries
+    /// into `output`.
+    ///
+    /// The archive tool is found next to the `dlltool` program. GNU-style
+    /// `ar` merges the archives via the returned MRI script fed to its stdin.
+    fn merge(&self, output: &Path, inputs: &[&Path]) -> Result<(Command, Option<String>)> {
+        match self {
+            Self::Mingw { command, .. } => {
+                // The MRI script commands take the unquoted file names.
+                let paths = inputs.iter().chain([&output]);
+                if let Some(path) = paths.map(|p| p.display().to_string()).find(|p| {
+                    p.contains(|c: char| c.is_whitespace() || matches!(c, ',' | ';' | '*'))
+                }) {
+                    let msg = format!("Can not merge the archives with the path {}", path);
+                    return Err(Error::UnsupportedFeature(msg));
+                }
+
+                let mut ar = Command::new(sibling_tool(command, "ar"));
+                ar.arg("-M");
+
+                let mut script = format!("CREATE {}\n", output.display());
+                for input in inputs {
+                    script.push_str(&format!("ADDLIB {}\n", input.display()));
+                }
+                script.push_str("SAVE\nEND\n");
+
+                Ok((ar, Some(script)))
+            }
+            Self::Llvm { command, .. }
+            | Self::LibExe { command, .. }
+            | Self::Zig { command, .. } => {
+                let mut lib = match self {
+                    Self::Llvm { .. } => Command::new(sibling_tool(command, "lib")),
+                    Self::LibExe { .. } => {
+                        let mut lib = clone_command(command);
+                        lib.arg("/NOLOGO");
+                        lib
+                    }
+                    // `zig lib` is the `llvm-lib` equivalent.
+                    _ => {
+                        let mut zig = clone_command(command);
+                        zig.arg("lib");
+                        zig
+                    }
+                };
+
+                lib.arg(format!("/OUT:{}", output.display())).args(inputs);
+
+                Ok((lib, None))
+            }
+            Self::Genlib { .. } => {
+                let msg = "MSYS2 genlib can not merge the import libraries";
+                Err(Error::UnsupportedFeature(msg.to_owned()))
+            }
+        }
+    }
+
+    /// Returns the `ranlib` command indexing the archives written
+    /// by the tool, `None` for `lib.exe` always writing the index.
+    ///
+    /// The `PYO3_RANLIB` and then the conventional `RANLIB` environment
+    /// variables override the program derived from the tool name,
+    /// e.g. `x86_64-w64-mingw32-ranlib` or `llvm-ranlib`.
+    fn ranlib(&self) -> Option<Command> {
+        let ranlib = match self {
+            Self::Mingw { command, .. }
+            | Self::Llvm { command, .. }
+            | Self::Genlib { command, .. } => Command::new(sibling_tool(command, "ranlib")),
+            Self::Zig { command, .. } => {
+                let mut zig = clone_command(command);
+                zig.arg("ranlib");
+                zig
+            }
+            Self::LibExe { .. } => return None,
+        };
+
+        Some(
+            env_command(RANLIB_ENV)
+                .or_else(|| env_command(RANLIB_ENV_FALLBACK))
+                .unwrap_or(ranlib),
+        )
+    }
+
+    /// Switches the tool to writing the delay-load import library.
+    ///
+    /// Only MinGW `dlltool` generates the delay-load import libraries,
+    /// the MSVC-style linkers delay-load the DLLs via `/DELAYLOAD` instead.
+    fn set_delay_load(&mut self) -> Result<()> {
+        match self {
+            Self::Mingw { delay_load, .. } => {
+                *delay_load = true;
+                Ok(())
+            }
+            _ => {
+                let msg = "Only MinGW dlltool can generate the delay-load import library, \
+                           link the regular import library with `/DELAYLOAD:<dll>` instead";
+                Err(Error::UnsupportedFeature(msg.to_owned()))
+            }
+        }
+    }
+
+    /// Generates the complete `dlltool` executable invocation command.
+    ///
+    /// The user-provided `extra_args` are inserted before the output file argument.
+    fn build(self, defpath: &Path, libpath: &Path, extra_args: &[String]) -> Command {
+        match self {
+            Self::Mingw {
+                mut command,
+                delay_load,
+            } => {
+                let output = if delay_load {
+                    "--output-delaylib"
+                } else {
+                    "--output-lib"
+                };
+
+                command
+                    .arg("--input-def")
+                    .arg(defpath)
+                    .args(extra_args)
+                    .arg(output)
+                    .arg(libpath);
+
+                command
+            }
+            Self::Llvm {
+                mut command,
+                machine,
+            } => {
+                command
+                    .arg("-m")
+                    .arg(machine)
+                    .arg("-d")
+                    .arg(defpath)
+                    .args(extra_args)
+                    .arg("-l")
+                    .arg(libpath);
+
+                command
+            }
+            Self::LibExe {
+                mut command,
+                machine,
+            } => {
+                command
+                    .arg(format!("/MACHINE:{}", machine))
+                    .arg(format!("/DEF:{}", defpath.display()))
+                    .args(extra_args)
+                    .arg(format!("/OUT:{}", libpath.display()));
+
+                command
+            }
+            Self::Zig {
+                mut command,
+                machine,
+            } => {
+                // Same as `llvm-dlltool`, but invoked as `zig dlltool`.
+                command
+                    .arg("dlltool")
+                    .arg("-m")
+                    .arg(machine)
+                    .arg("-d")
+                    .arg(defpath)
+                    .args(extra_args)
+                    .arg("-l")
+                    .arg(libpath);
+
+                command
+            }
+            Self::Genlib {
+                mut command,
+                machine,
+            } => {
+                command
+                    .arg("-a")
+                    .arg(machine)
+                    .args(extra_args)
+                    .arg("-o")
+                    .arg(libpath)
+                    .arg(defpath);
+
+                command
+            }
+        }
+    }
+}
+
+/// Import library target machine type
+///
+/// Each tool flavor uses its own machine names for the same PE machine type.
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum Machine {
+    /// 32-bit x86
+    X86,
+    /// 64-bit x86
+    X64,
+    /// 32-bit ARM (Thumb-2)
+    Arm,
+    /// 64-bit ARM
+    Arm64,
+    /// 64-bit ARM with the x64 emulation compatible ABI
+    Arm64Ec,
+    /// Unknown architecture name passed to the tools as-is
+    Other(String),
+}
+
+impl Machine {
+    /// Maps the target architecture name (as in `CARGO_CFG_TARGET_ARCH`)
+    /// to the machine type.
+    ///
+    /// Unknown `arch` names are passed through as-is in `permissive` mode.
+    fn from_arch(arch: &str, permissive: bool) -> Result<Self> {
+        match arch {
+            "x86_64" => Ok(Machine::X64),
+            "x86" => Ok(Machine::X86),
+            "aarch64" => Ok(Machine::Arm64),
+            "arm" => Ok(Machine::Arm),
+            "arm64ec" => Ok(Machine::Arm64Ec),
+            arch if permissive => Ok(Machine::Other(arch.to_owned())),
+            // Neither LLVM nor MSVC tools know the LoongArch PE machine type yet,
+            // `loongarch64` is only passed through in the permissive mode.
+            _ => Err(Error::UnsupportedArch(arch.to_owned())),
+        }
+    }
+
+    /// Returns the `llvm-dlltool` and `zig dlltool` machine name.
+    ///
+    /// MinGW `dlltool` uses the same names for the x86 machines.
+    fn as_llvm(&self) -> &str {
+        match self {
+            Machine::X86 => "i386",
+            Machine::X64 => "i386:x86-64",
+            Machine::Arm => "arm",
+            Machine::Arm64 => "arm64",
+            Machine::Arm64Ec => "arm64ec",
+            Machine::Other(name) => name,
+        }
+    }
+
+    /// Returns the MSYS2 `genlib` architecture name.
+    fn as_genlib(&self) -> &str {
+        match self {
+            Machine::X86 => "i386",
+            Machine::X64 => "x86_64",
+            Machine::Arm => "arm32",
+            Machine::Arm64 => "arm64",
+            Machine::Arm64Ec => "arm64ec",
+            Machine::Other(name) => name,
+        }
+    }
+
+    /// Returns the MSVC `lib.exe` machine name.
+    fn as_msvc(&self) -> &str {
+        match self {
+            Machine::X86 => "X86",
+            Machine::X64 => "X64",
+            Machine::Arm => "ARM",
+            Machine::Arm64 => "ARM64",
+            Machine::Arm64Ec => "ARM64EC",
+            Machine::Other(name) => name,
+        }
+    }
+}
+
+/// Chooses the appropriate MinGW-w64 `dlltool` executable
+/// for the target architecture.
+///
+/// Examines the user-provided `PYO3_MINGW_DLLTOOL` environment variable first,
+/// then the conventional `DLLTOOL` environment variable,
+/// and falls back to the default MinGW-w64 arch prefixes.
+///
+/// The UCRT MinGW-w64 toolchains use the `mingw32ucrt` prefix,
+/// e.g. `x86_64-w64-mingw32ucrt-dlltool`, see `select_mingw_dlltool()`.
+///
+/// On Windows hosts, the unprefixed native MinGW-w64 `dlltool` is used
+/// when the prefixed one is not found in `PATH`.
+///
+/// For the ARM64 target, `llvm-dlltool` is used when the LLVM MinGW-w64
+/// toolchain `aarch64-w64-mingw32-dlltool` is not found in `PATH`.
+fn get_mingw_dlltool(arch: &str) -> Result<DllToolCommand> {
+    let mingw = |command| DllToolCommand::Mingw {
+        command,
+        delay_load: false,
+    };
+
+    if let Ok(user_dlltool) = env::var("PYO3_MINGW_DLLTOOL") {
+        Ok(mingw(Command::new(user_dlltool)))
+    } else if let Some(command) = env_command(DLLTOOL_ENV) {
+        Ok(mingw(command))
+    } else {
+        let ucrt = match env::var(MINGW_UCRT_ENV).as_deref() {
+            Ok("1") => Some(true),
+            Ok("0") => Some(false),
+            _ => None,
+        };
+        let is_found = |name: &str| find_program(name).is_some();
+
+        let prefix_dlltool = match arch {
+            // 64-bit MinGW-w64 (aka `x86_64-pc-windows-gnu`)
+            "x86_64" => Ok(select_mingw_dlltool(
+                DLLTOOL_GNU,
+                DLLTOOL_GNU_UCRT,
+                ucrt,
+                is_found,
+            )),
+            // 32-bit MinGW-w64 (aka `i686-pc-windows-gnu`)
+            "x86" => Ok(select_mingw_dlltool(
+                DLLTOOL_GNU_32,
+                DLLTOOL_GNU_32_UCRT,
+                ucrt,
+                is_found,
+            )),
+            // ARM64 MinGW-w64 (aka `aarch64-pc-windows-gnullvm`)
+            "aarch64" if find_program(DLLTOOL_GNU_ARM64).is_some() => Ok(DLLTOOL_GNU_ARM64),
+            "aarch64" => {
+                // GNU binutils lack ARM64 PE support, `llvm-dlltool`
+                // writes the same GNU-style import libraries.
+                return Ok(DllToolCommand::Llvm {
+                    command: Command::new(DLLTOOL_MSVC),
+                    machine: Machine::Arm64.as_llvm().to_owned(),
+                });
+            }
+            _ => Err(Error::UnsupportedArch(arch.to_owned())),
+        }?;
+
+        // Native MSYS2 shells only provide the unprefixed `dlltool`.
+        if cfg!(windows)
+            && find_program(prefix_dlltool).is_none()
+            && find_program(DLLTOOL_GNU_NATIVE).is_some()
+        {
+            let mut command = Command::new(DLLTOOL_GNU_NATIVE);
+            command
+                .arg("-m")
+                .arg(Machine::from_arch(arch, false)?.as_llvm());
+
+            return Ok(mingw(command));
+        }
+
+        Ok(mingw(Command::new(prefix_dlltool)))
+    }
+}
+
+/// Selects between the classic MSVCRT and the UCRT MinGW-w64 `dlltool`
+/// program names.
+///
+/// The import libraries are the same, only the toolchain layout differs.
+/// `PYO3_MINGW_UCRT=1` (`ucrt` is `Some(true)`) prefers the UCRT `dlltool`
+/// when it is installed, `PYO3_MINGW_UCRT=0` selects the classic one.
+/// Otherwise, the UCRT `dlltool` is only used when the classic one
+/// is not installed.
+fn select_mingw_dlltool(
+    classic: &'static str,
+    ucrt_dlltool: &'static str,
+    ucrt: Option<bool>,
+    is_found: impl Fn(&str) -> bool,
+) -> &'static str {
+    match ucrt {
+        Some(true) if is_found(ucrt_dlltool) => ucrt_dlltool,
+        None if !is_found(classic) && is_found(ucrt_dlltool) => ucrt_dlltool,
+        _ => classic,
+    }
+}
+
+/// Finds the Cygwin `dlltool` program for the target `arch`.
+///
+/// The same `PYO3_MINGW_DLLTOOL` and `DLLTOOL` overrides apply as for
+/// the MinGW-w64 targets. The native Cygwin shells only provide
+/// the unprefixed `dlltool`, which is used when the cross one is not found.
+fn get_cygwin_dlltool(arch: &str) -> Result<Command> {
+    if let Ok(user_dlltool) = env::var("PYO3_MINGW_DLLTOOL") {
+        Ok(Command::new(user_dlltool))
+    } else if let Some(command) = env_command(DLLTOOL_ENV) {
+        Ok(command)
+    } else if arch != "x86_64" {
+        // The 32-bit Cygwin is discontinued.
+        Err(Error::UnsupportedArch(arch.to_owned()))
+    } else if cfg!(any(windows, target_os = "cygwin"))
+        && find_program(DLLTOOL_CYGWIN).is_none()
+        && find_program(DLLTOOL_GNU_NATIVE).is_some()
+    {
+        Ok(Command::new(DLLTOOL_GNU_NATIVE))
+    } else {
+        Ok(Command::new(DLLTOOL_CYGWIN))
+    }
+}
+
+/// Parses the Python version string such as `"3.12"`, `"3.13t"` or `"312"`.
+fn parse_version(s: &str) -> Result<((u8, u8), AbiFlags)> {
+    let invalid = || unsupported(&format!("Invalid Python version '{}'", s));
+
+    let digits_end = s
+        .find(|c: char| !c.is_ascii_digit() && c != '.')
+        .unwrap_or(s.len());
+    let (number, flags) = s.split_at(digits_end);
+
+    let (major, minor) = match number.split_once('.') {
+        Some((major, minor)) => (major, minor),
+        // The `sys.version_info` based `"XY"` form, e.g. `"312"`
+        None if number.len() >= 2 => number.split_at(1),
+        None => return Err(invalid()),
+    };
+
+    let parse = |n: &str| {
+        // Reject the signs and the empty numbers `u8::from_str()` lets through.
+        n.bytes()
+            .all(|b| b.is_ascii_digit())
+            .then(|| n.parse().ok())
+            .flatten()
+    };
+
+    let (Some(major), Some(minor)) = (parse(major), parse(minor)) else {
+        return Err(invalid());
+    };
+
+    let flags = flags.parse().map_err(|_| invalid())?;
+
+    Ok(((major, minor), flags))
+}
+
+/// Validates the user-provided import library file extension.
+fn check_implib_extension(ext: &str) -> Result<&str> {
+    let valid = ext.len() > 1
+        && ext.starts_with('.')
+        && !ext.contains(|c| std::path::is_separator(c) || c == '\0');
+
+    if valid {
+        Ok(ext)
+    } else {
+        let msg = format!(
+            "Invalid import library file extension '{}', expected e.g. '.lib'",
+            ext
+        );
+        Err(Error::UnsupportedFeature(msg))
+    }
+}
+
+/// Chooses the `llvm-dlltool` executable.
+///
+/// The conventional `DLLTOOL` environment variable overrides
+/// the default `llvm-dlltool` command name.
+fn llvm_dlltool() -> Command {
+    env_command(DLLTOOL_ENV).unwrap_or_else(|| Command::new(DLLTOOL_MSVC))
+}
+
+/// Creates the command for the program named in the environment variable `var`.
+///
+/// Unset and empty variables are ignored.
+fn env_command(var: &str) -> Option<Command> {
+    env::var_os(var)
+        .filter(|program| !program.is_empty())
+        .map(Command::new)
+}
+
+/// Returns the import library file extension used by the target `env`.
+///
+/// MinGW-w64 targets use `.dll.a`, MSVC targets use `.lib`.
+fn implib_file_ext(env: &str) -> Result<&'static str> {
+    match env {
+        "gnu" | "gnullvm" | "cygwin" => Ok(IMPLIB_EXT_GNU),
+        "msvc" => Ok(IMPLIB_EXT_MSVC),
+        _ => Err(Error::UnsupportedEnv(env.to_owned())),
+    }
+}
+
+/// Searches for the executable program `name` in `PATH`.
+fn find_program(name: &str) -> Option<PathBuf> {
+    let paths = env::var_os("PATH")?;
+
+    env::split_paths(&paths).find_map(|dir| {
+        let path = dir.join(name);
+        if path.is_file() {
+            return Some(path);
+        }
+
+        // Windows executables are usually run without the extension.
+        let path = dir.join(format!("{}.exe", name));
+        (cfg!(windows) && path.is_file()).then_some(path)
+    })
+}
+
+/// Runs the `dlltool` command and validates its output files.
+fn run_dlltool(
+    command: &mut Command,
+    implib_file: &Path,
+    export_file: Option<&Path>,
+    retries: u32,
+) -> Result<()> {
+    // Run the selected `dlltool` executable to generate the import library.
+    let status = spawn_tool(command, retries)?
+        .wait()
+        .map_err(|source| Error::ToolSpawn {
+            command: command_line(command),
+            source,
+        })?;
+
+    if !status.success() {
+        return Err(Error::ToolFailed {
+            command: command_line(command),
+            reason: status.to_string(),
+        });
+    }
+
+    // Some broken `dlltool` builds exit successfully without writing anything.
+    if !is_archive_file(implib_file)? {
+        return Err(Error::ToolFailed {
+            command: command_line(command),
+            reason: format!("no valid import library in {}", implib_file.display()),
+        });
+    }
+
+    if let Some(export_file) = export_file {
+        if !export_file.is_file() {
+            return Err(Error::ToolFailed {
+                command: command_line(command),
+                reason: format!("no exports file in {}", export_file.display()),
+            });
+        }
+    }
+
+    Ok(())
+}
+
+/// Runs the `ranlib` command adding the symbol index to `archive`.
+fn run_ranlib(command: &mut Command, archive: &Path, retries: u32) -> Result<()> {
+    let status = spawn_tool(command, retries)?
+        .wait()
+        .map_err(|source| Error::ToolSpawn {
+            command: command_line(command),
+            source,
+        })?;
+
+    if !status.success() {
+        return Err(Error::ToolFailed {
+            command: command_line(command),
+            reason: status.to_string(),
+        });
+    }
+
+    if !has_symbol_index(archive)? {
+        return Err(Error::ToolFailed {
+            command: command_line(command),
+            reason: format!("no symbol index in {}", archive.display()),
+        });
+    }
+
+    Ok(())
+}
+
+/// Runs the archive merge tool, feeding it the MRI `script` if any.
+fn run_merge(
+    command: &mut Command,
+    script: Option<&str>,
+    output: &Path,
+    retries: u32,
+) -> Result<()> {
+    let spawn_error = |command: &Command, source| Error::ToolSpawn {
+        command: command_line(command),
+        source,
+    };
+
+    if script.is_some() {
+        command.stdin(Stdio::piped());
+    }
+
+    let mut child = spawn_tool(command, retries)?;
+
+    // Closing stdin ends the script, the tool exit status is more relevant
+    // than the write errors.
+    let written = match (script, child.stdin.take()) {
+        (Some(script), Some(mut stdin)) => stdin.write_all(script.as_bytes()),
+        _ => Ok(()),
+    };
+
+    let status = child.wait().map_err(|e| spawn_error(command, e))?;
+
+    if !status.success() {
+        return Err(Error::ToolFailed {
+            command: command_line(command),
+            reason: status.to_string(),
+        });
+    }
+
+    written?;
+
+    if !is_archive_file(output)? {
+        return Err(Error::ToolFailed {
+            command: command_line(command),
+            reason: format!("no valid merged archive in {}", output.display()),
+        });
+    }
+
+    Ok(())
+}
+
+/// Starts the tool `command`, retrying up to `retries` times
+/// on the transient spawn failures.
+fn spawn_tool(command: &mut Command, retries: u32) -> Result<Child> {
+    let mut delay = SPAWN_RETRY_DELAY;
+    let mut attempt = 0;
+
+    loop {
+        match command.spawn() {
+            Ok(child) => return Ok(child),
+            Err(e) if attempt < retries && is_transient_spawn_error(&e) => {
+                warn(&format!(
+                    "{} failed to start with {}, retrying in {} ms",
+                    command_line(command),
+                    e,
+                    delay.as_millis()
+                ));
+                sleep(delay);
+                delay *= 2;
+                attempt += 1;
+            }
+            Err(source) => {
+                return Err(Error::ToolSpawn {
+                    command: command_line(command),
+                    source,
+                })
+            }
+        }
+    }
+}
+
+/// Checks if the process spawn error is likely caused by a temporary
+/// file lock, e.g. held by an antivirus scanner.
+fn is_transient_spawn_error(e: &io::Error) -> bool {
+    // `ERROR_SHARING_VIOLATION`, `ERROR_LOCK_VIOLATION`; `ERROR_ACCESS_DENIED`
+    // usually means a permanent permission problem.
+    #[cfg(windows)]
+    const TRANSIENT_OS_ERRORS: &[i32] = &[32, 33];
+    // `ETXTBSY`
+    #[cfg(not(windows))]
+    const TRANSIENT_OS_ERRORS: &[i32] = &[26];
+
+    match e.kind() {
+        io::ErrorKind::Interrupted | io::ErrorKind::WouldBlock => true,
+        _ => e
+            .raw_os_error()
+            .is_some_and(|code| TRANSIENT_OS_ERRORS.contains(&code)),
+    }
+}
+
+/// Copies the program, arguments, environment and working directory
+/// of `command` into a new `Command`.
+fn clone_command(command: &Command) -> Command {
+    let mut clone = Command::new(command.get_program());
+    clone.args(command.get_args());
+
+    for (key, value) in command.get_envs() {
+        match value {
+            Some(value) => clone.env(key, value),
+            None => clone.env_remove(key),
+        };
+    }
+
+    if let Some(dir) = command.get_current_dir() {
+        clone.current_dir(dir);
+    }
+
+    clone
+}
+
+/// Reports a non-fatal problem to the user.
+///
+/// Uses `cargo:warning` when running from a build script.
+fn warn(msg: &str) {
+    if env::var_os("OUT_DIR").is_some() && env::var_os("TARGET").is_some() {
+        println!("cargo:warning={}", msg);
+    } else {
+        eprintln!("warning: {}", msg);
+    }
+}
+
+/// Renders the command as a shell command line for copy-pasting.
+///
+/// The arguments are quoted for the POSIX shell, or following
+/// the Windows command line parsing rules on Windows hosts.
+fn command_line(command: &Command) -> String {
+    let program = command.get_program().to_string_lossy();
+    let mut line = shell_quote(&program).into_owned();
+
+    for arg in command.get_args() {
+        line.push(' ');
+        line.push_str(&shell_quote(&arg.to_string_lossy()));
+    }
+
+    line
+}
+
+/// Lists the command program followed by its arguments.
+fn command_argv(command: &Command) -> Vec<OsString> {
+    let mut argv = vec![command.get_program().to_owned()];
+    argv.extend(command.get_args().map(OsStr::to_owned));
+    argv
+}
+
+/// Quotes a single command line argument for the POSIX shell.
+#[cfg(not(windows))]
+fn shell_quote(arg: &str) -> Cow<'_, str> {
+    let is_safe = |c: char| c.is_ascii_alphanumeric() || "_-+=./:,@%".contains(c);
+
+    if !arg.is_empty() && arg.chars().all(is_safe) {
+        Cow::Borrowed(arg)
+    } else {
+        Cow::Owned(format!("'{}'", arg.replace('\'', r"'\''")))
+    }
+}
+
+/// Quotes a single command line argument following
+/// the `CommandLineToArgvW` parsing rules.
+#[cfg(windows)]
+fn shell_quote(arg: &str) -> Cow<'_, str> {
+    if !arg.is_empty() && !arg.contains([' ', '\t', '"']) {
+        return Cow::Borrowed(arg);
+    }
+
+    let mut quoted = String::from('"');
+    let mut backslashes = 0;
+
+    for c in arg.chars() {
+        match c {
+            '\\' => backslashes += 1,
+            // Escape the preceding backslashes and the quote itself.
+            '"' => {
+                quoted.push_str(&"\\".repeat(backslashes + 1));
+                backslashes = 0;
+            }
+            _ => backslashes = 0,
+        }
+        quoted.push(c);
+    }
+
+    // Escape the trailing backslashes before the closing quote.
+    quoted.push_str(&"\\".repeat(backslashes));
+    quoted.push('"');
+
+    Cow::Owned(quoted)
+}
+
+/// Creates the output directory and checks that it is writable,
+/// so that the failures name the directory instead of the individual files.
+///
+/// The newly created directories get the Unix permission bits `mode` if set.
+fn prepare_out_dir(out_dir: &Path, mode: Option<u32>) -> Result<()> {
+    let probe = temp_file_path(&out_dir.join("python3-dll-a-write-check"));
+
+    let result = create_out_dir(out_dir, mode).and_then(|()| File::create(&probe).map(drop));
+
+    if let Err(e) = result {
+        let msg = format!(
+            "output directory {} is not writable: {}",
+            out_dir.display(),
+            e
+        );
+        return Err(io::Error::new(e.kind(), msg).into());
+    }
+
+    let _ = remove_file(&probe);
+
+    Ok(())
+}
+
+/// Creates the output directory with its missing parents,
+/// setting the Unix permission bits `mode` on the created directories.
+fn create_out_dir(out_dir: &Path, mode: Option<u32>) -> io::Result<()> {
+    #[cfg(unix)]
+    if let Some(mode) = mode {
+        return create_dir_all_mode(out_dir, mode);
+    }
+
+    #[cfg(not(unix))]
+    let _ = mode;
+
+    create_dir_all(out_dir)
+}
+
+/// Creates the directory `dir` with its missing parents,
+/// setting the permission bits `mode` on the directories created here.
+///
+/// The directories found existing, including the ones created concurrently
+/// by another process, keep their permissions.
+#[cfg(unix)]
+fn create_dir_all_mode(dir: &Path, mode: u32) -> io::Result<()> {
+    use std::fs::{set_permissions, DirBuilder, Permissions};
+    use std::os::unix::fs::{DirBuilderExt, PermissionsExt};
+
+    let create = || DirBuilder::new().mode(mode).create(dir);
+
+    let result = match create() {
+        Err(e) if e.kind() == io::ErrorKind::NotFound => match dir.parent() {
+            Some(parent) if !parent.as_os_str().is_empty() => {
+                create_dir_all_mode(parent, mode)?;
+                create()
+            }
+            _ => Err(e),
+        },
+        result => result,
+    };
+
+    match result {
+        // `DirBuilderExt::mode()` is still subject to the umask.
+        Ok(()) => set_permissions(dir, Permissions::from_mode(mode)),
+        Err(e) if e.kind() == io::ErrorKind::AlreadyExists && dir.is_dir() => Ok(()),
+        Err(e) => Err(e),
+    }
+}
+
+/// Builds a unique temporary file path in the same directory as `path`.
+fn temp_file_path(path: &Path) -> PathBuf {
+    static COUNTER: AtomicUsize = AtomicUsize::new(0);
+
+    let mut file_name = path.file_name().unwrap_or_default().to_owned();
+    let count = COUNTER.fetch_add(1, Ordering::Relaxed);
+    file_name.push(format!(".{}.{}.tmp", process::id(), count));
+
+    path.with_file_name(file_name)
+}
+
+/// Normalizes the common architecture name aliases
+/// to the `CARGO_CFG_TARGET_ARCH` names.
+fn canonical_arch(arch: &str) -> &str {
+    match arch {
+        "amd64" | "x64" => "x86_64",
+        "arm64" => "aarch64",
+        "i386" | "i686" => "x86",
+        arch => arch,
+    }
+}
+
+/// Normalizes the environment ABI names meaning the default
+/// Windows ABI to `"msvc"`.
+fn canonical_env(env: &str) -> &str {
+    match env {
+        "" | "sdk" | "none" => "msvc",
+        env => env,
+    }
+}
+
+/// Checks that `path` is a non-empty file starting with the archive signature.
+fn is_archive_file(path: &Path) -> Result<bool> {
+    let mut magic = [0; ARCHIVE_MAGIC.len()];
+
+    let file = match File::open(path) {
+        Ok(file) => file,
+        Err(e) if e.kind() == io::ErrorKind::NotFound => return Ok(false),
+        Err(e) => return Err(e.into()),
+    };
+
+    // `read_exact()` fails on truncated files.
+    Ok(file.take(magic.len() as u64).read_exact(&mut magic).is_ok() && magic == ARCHIVE_MAGIC)
+}
+
+/// Checks that the archive `path` starts with the symbol index member,
+/// either the GNU and COFF `/` or `/SYM64/` or the BSD `__.SYMDEF` one.
+fn has_symbol_index(path: &Path) -> Result<bool> {
+    let mut header = [0; ARCHIVE_MAGIC.len() + 16];
+
+    // `read_exact()` fails on the archives without members.
+    if File::open(path)?.read_exact(&mut header).is_err() {
+        return Ok(false);
+    }
+
+    let name = &header[ARCHIVE_MAGIC.len()..];
+
+    Ok(name.starts_with(b"/ ") || name.starts_with(b"/SYM64/") || name.starts_with(b"__.SYMDEF"))
+}
+
+/// Constructs the unsupported Python configuration error.
+fn unsupported(msg: &str) -> Error {
+    Error::UnsupportedPython(msg.to_owned())
+}
+
+/// Constructs the unsupported Python ABI flags error.
+fn unsupported_abiflags(flags: &str) -> Error {
+    Error::UnsupportedPython(format!("Unsupported Python ABI flags '{}'", flags))
+}
+
+/// Constructs the unsupported Python `version` error for the `name`
+/// implementation, suggesting the supported `oldest` to `latest` range.
+///
+/// The versions other than Python 3 are likely typos or the Python 2 leftovers
+/// and get a dedicated message.
+fn unsupported_version(name: &str, version: (u8, u8), oldest: (u8, u8), latest: (u8, u8)) -> Error {
+    let (major, minor) = version;
+
+    let msg = if major != 3 {
+        format!("Only Python 3 is supported, got {}.{}", major, minor)
+    } else {
+        format!(
+            "Unsupported {} version {}.{}, the supported versions are {}.{} to {}.{}",
+            name, major, minor, oldest.0, oldest.1, latest.0, latest.1
+        )
+    };
+
+    Error::UnsupportedPython(msg)
+}
+
+/// Finds the `zig` executable (when built by `maturin --zig`).
+///
+/// Examines the `ZIG_COMMAND` environment variable
+/// to find out if `zig cc` is being used as the linker.
+fn find_zig() -> Option<Command> {
+    // `ZIG_COMMAND` may contain simply `zig` or `/usr/bin/zig`,
+    // or a more complex construct like `python3 -m ziglang`.
+    let zig_command = env::var("ZIG_COMMAND").ok()?;
+
+    // Try to emulate `sh -c ${ZIG_COMMAND}`.
+    let mut zig_cmdlet = zig_command.split_ascii_whitespace();
+
+    // Extract the main program component (e.g. `zig` or `python3`).
+    let mut zig = Command::new(zig_cmdlet.next()?);
+
+    // Append the rest of the commandlet.
+    zig.args(zig_cmdlet);
+
+    Some(zig)
+}
+
+/// Derives the sibling tool program name from the `dlltool` program,
+/// e.g. `x86_64-w64-mingw32-ar` from `x86_64-w64-mingw32-dlltool`
+/// or `llvm-lib-14` from `llvm-dlltool-14`.
+///
+/// Returns the bare `name` when the program is not named `dlltool`.
+fn sibling_tool(command: &Command, name: &str) -> PathBuf {
+    let program = Path::new(command.get_program());
+
+    match program
+        .file_name()
+        .and_then(|f| f.to_str())
+        .and_then(|f| f.rsplit_once("dlltool"))
+    {
+        Some((prefix, suffix)) => program.with_file_name(format!("{}{}{}", prefix, name, suffix)),
+        None => PathBuf::from(name),
+    }
+}
+
+/// Checks whether the tool program exists without running it.
+///
+/// The bare program names are looked up in `PATH`,
+/// the paths are checked as they are.
+fn is_available(command: &Command) -> bool {
+    let program = Path::new(command.get_program());
+
+    if program.components().count() > 1 {
+        // Windows executables are usually run without the extension.
+        return program.is_file() || (cfg!(windows) && program.with_extension("exe").is_file());
+    }
+
+    program.to_str().and_then(find_program).is_some()
+}
+
+/// Finds the Visual Studio `lib.exe` candidates when running on Windows,
+/// in the order of preference.
+///
+/// The cross toolset `lib.exe` for the target architecture comes first,
+/// e.g. `Hostx64\arm64\lib.exe` for `aarch64` on x64 hosts.
+/// The host native `lib.exe` is the second choice: it is able to write
+/// the import libraries for the other machines via `/MACHINE` as well,
+/// but only when the matching toolset is installed.
+///
+/// Requires the `lib-exe` crate feature, always returns no candidates otherwise.
+#[cfg(all(windows, feature = "lib-exe"))]
+fn find_lib_exe(arch: &str) -> Vec<Command> {
+    let target = match arch {
+        "x86_64" => "x86_64-pc-windows-msvc",
+        "x86" => "i686-pc-windows-msvc",
+        "aarch64" => "aarch64-pc-windows-msvc",
+        _ => return Vec::new(),
+    };
+
+    let host = if cfg!(target_arch = "aarch64") {
+        "aarch64-pc-windows-msvc"
+    } else {
+        "x86_64-pc-windows-msvc"
+    };
+
+    let mut candidates: Vec<Command> = cc::windows_registry::find(target, LIB_MSVC)
+        .into_iter()
+        .collect();
+
+    if target != host {
+        let cross = candidates.first().map(|c| c.get_program().to_owned());
+
+        candidates.extend(
+            cc::windows_registry::find(host, LIB_MSVC)
+                .filter(|native| Some(native.get_program()) != cross.as_deref()),
+        );
+    }
+
+    candidates
+}
+
+#[cfg(not(all(windows, feature = "lib-exe")))]
+fn find_lib_exe(_arch: &str) -> Vec<Command> {
+    Vec::new()
+}
+
+#[cfg(test)]
+mod tests {
+    use std::path::PathBuf;
+
+    use super::*;
+
+    #[cfg(unix)]
+    #[test]
+    fn generate() {
+        // FIXME: Use "target/<arch>" dirs for temporary files.
+        let mut dir = PathBuf::from(env!("CARGO_MANIFEST_DIR"));
+        dir.push("target");
+        dir.push("x86_64-pc-windows-gnu");
+        dir.push("python3-dll");
+
+        ImportLibraryGenerator::new("x86_64", "gnu")
+            .generate(&dir)
+            .unwrap();
+
+        // Free-threaded Stable ABI
+        ImportLibraryGenerator::new("x86_64", "gnu")
+            .abiflags(Some("t"))
+            .generate(&dir)
+            .unwrap();
+
+        for minor in 7..=13 {
+            ImportLibraryGenerator::new("x86_64", "gnu")
+                .version(Some((3, minor)))
+                .generate(&dir)
+                .unwrap();
+        }
+
+        // Free-threaded CPython v3.13+
+        for minor in 13..=13 {
+            ImportLibraryGenerator::new("x86_64", "gnu")
+                .version(Some((3, minor)))
+                .abiflags(Some("t"))
+                .generate(&dir)
+                .unwrap();
+        }
+
+        // PyPy
+        for minor in 7..=10 {
+            ImportLibraryGenerator::new("x86_64", "gnu")
+                .version(Some((3, minor)))
+                .implementation(PythonImplementation::PyPy)
+                .generate(&dir)
+                .unwrap();
+        }
+
+        // Pyston
+        ImportLibraryGenerator::new("x86_64", "gnu")
+            .version(Some((3, 8)))
+            .implementation(PythonImplementation::Pyston)
+            .generate(&dir)
+            .unwrap();
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn generate_gnu_arm64() {
+        let mut dir = PathBuf::from(env!("CARGO_MANIFEST_DIR"));
+        dir.push("target");
+        dir.push("aarch64-pc-windows-gnullvm");
+        dir.push("python3-dll");
+
+        let report = ImportLibraryGenerator::new("aarch64", "gnu")
+            .generate(&dir)
+            .unwrap();
+        assert!(is_archive_file(&report.implib_path).unwrap());
+        assert_eq!(report.implib_path, dir.join("python3.dll.a"));
+        assert!(report.tool_time > Duration::ZERO);
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn mingw_arm64_fallback() {
+        use std::os::unix::fs::PermissionsExt;
+
+        let Some(llvm_dlltool) = find_program(DLLTOOL_MSVC) else {
+            return;
+        };
+
+        if find_program(DLLTOOL_GNU_ARM64).is_some() {
+            return;
+        }
+
+        // The fallback uses the `llvm-dlltool` arguments,
+        // not the MinGW-only ones.
+        let command = get_mingw_dlltool("aarch64").unwrap();
+        assert!(matches!(command, DllToolCommand::Llvm { ref machine, .. } if machine == "arm64"));
+        assert_eq!(command.verbose_flag(), None);
+
+        let mut dir = PathBuf::from(env!("CARGO_MANIFEST_DIR"));
+        dir.push("target");
+        dir.push("aarch64-pc-windows-gnu");
+        dir.push("python3-fallback");
+
+        // The stub rejects the MinGW-only arguments `llvm-dlltool` ignores.
+        let bin_dir = dir.join("bin");
+        create_dir_all(&bin_dir).unwrap();
+        let stub = bin_dir.join("strict-llvm-dlltool");
+        let script = format!(
+            "#!/bin/sh\n\
+             for arg in \"$@\"; do\n\
+             case \"$arg\" in -v|--*) echo \"unsupported $arg\" >&2; exit 1;; esac\n\
+             done\n\
+             exec {} \"$@\"\n",
+            llvm_dlltool.display()
+        );
+        write(&stub, script).unwrap();
+        std::fs::set_permissions(&stub, std::fs::Permissions::from_mode(0o755)).unwrap();
+
+        let mut generator = ImportLibraryGenerator::new("aarch64", "gnu");
+        generator
+            .dlltool_command(Command::new(&stub), DllToolFlavor::Llvm)
+            .verbose(true);
+
+        let report = generator.generate(&dir).unwrap();
+        assert_eq!(report.implib_path, dir.join("python3.dll.a"));
+        assert_eq!(report.tool_argv[1..3], ["-m", "arm64"]);
+        assert!(is_archive_file(&report.implib_path).unwrap());
+
+        let err = generator
+            .deterministic_archive(Some(true))
+            .generate(&dir)
+            .unwrap_err();
+        assert!(matches!(err, Error::UnsupportedFeature(_)));
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn generate_gnullvm() {
+        let mut dir = PathBuf::from(env!("CARGO_MANIFEST_DIR"));
+        dir.push("target");
+        dir.push("x86_64-pc-windows-gnullvm");
+        dir.push("python3-dll");
+
+        let report = ImportLibraryGenerator::new("x86_64", "gnullvm")
+            .generate(&dir)
+            .unwrap();
+        assert!(is_archive_file(&report.implib_path).unwrap());
+        assert_eq!(report.implib_path, dir.join("python3.dll.a"));
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn user_dlltool_command() {
+        let mut dir = PathBuf::from(env!("CARGO_MANIFEST_DIR"));
+        dir.push("target");
+        dir.push("x86_64-pc-windows-msvc");
+        dir.push("python3-user");
+
+        // Run the tool through a wrapper with a custom environment.
+        let mut command = Command::new("env");
+        command.arg(DLLTOOL_MSVC).env("PYO3_TEST_VAR", "1");
+
+        let report = ImportLibraryGenerator::new("x86_64", "msvc")
+            .dlltool_command(command, DllToolFlavor::Llvm)
+            .generate(&dir)
+            .unwrap();
+        assert!(is_archive_file(&report.implib_path).unwrap());
+        assert_eq!(report.tool, "env");
+
+        let err = ImportLibraryGenerator::new("x86_64", "msvc")
+            .dlltool_command(Command::new("env"), DllToolFlavor::Mingw)
+            .generate(&dir)
+            .unwrap_err();
+        assert!(matches!(err, Error::ToolFailed { .. }));
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn progress_events() {
+        use std::sync::{Arc, Mutex};
+
+        let mut dir = PathBuf::from(env!("CARGO_MANIFEST_DIR"));
+        dir.push("target");
+        dir.push("x86_64-pc-windows-msvc");
+        dir.push("python3-events");
+
+        let events = Arc::new(Mutex::new(Vec::new()));
+        let log = Arc::clone(&events);
+
+        let mut generator = ImportLibraryGenerator::new("x86_64", "msvc");
+        generator
+            .version(Some((3, 12)))
+            .prefer_zig(false)
+            .on_event(move |event| {
+                let entry = match event {
+                    Event::DiscoveryStarted { version, .. } => format!("start {:?}", version),
+                    Event::ToolSelected { tool, flavor } => format!("tool {} {:?}", tool, flavor),
+                    Event::DefWritten { path } => format!("def {}", path.display()),
+                    Event::Generated { report } => format!("lib {}", report.implib_path.display()),
+                    Event::DeprecatedSymbol { name, .. } => format!("deprecated {}", name),
+                };
+                log.lock().unwrap().push(entry);
+            });
+
+        let report = generator.generate(&dir).unwrap();
+
+        assert_eq!(
+            *events.lock().unwrap(),
+            [
+                "start Some((3, 12))".to_owned(),
+                "tool llvm-dlltool Llvm".to_owned(),
+                format!("def {}", report.def_path.display()),
+                format!("lib {}", report.implib_path.display()),
+            ]
+        );
+
+        // The definitions are written even if the tool fails afterwards.
+        events.lock().unwrap().clear();
+        remove_file(&report.def_path).unwrap();
+
+        let err = generator
+            .dlltool_command(Command::new("false"), DllToolFlavor::Llvm)
+            .generate(&dir)
+            .unwrap_err();
+        assert!(matches!(err, Error::ToolFailed { .. }));
+
+        assert_eq!(
+            *events.lock().unwrap(),
+            [
+                "start Some((3, 12))".to_owned(),
+                "tool false Llvm".to_owned(),
+                format!("def {}", report.def_path.display()),
+            ]
+        );
+        assert!(report.def_path.is_file());
+    }
+
+    #[test]
+    fn deprecated_symbols() {
+        use std::sync::{Arc, Mutex};
+
+        let dir = PathBuf::from(env!("CARGO_MANIFEST_DIR"))
+            .join("target")
+            .join("python3-deprecated");
+
+        let warnings_of = |generator: &mut ImportLibraryGenerator| {
+            let warnings = Arc::new(Mutex::new(Vec::new()));
+            let log = Arc::clone(&warnings);
+
+            generator
+                .dry_run(true)
+                .on_event(move |event| {
+                    if let Event::DeprecatedSymbol {
+                        name,
+                        deprecated_in,
+                        removed_in,
+                    } = event
+                    {
+                        log.lock()
+                            .unwrap()
+                            .push((name.to_owned(), deprecated_in, removed_in));
+                    }
+                })
+                .generate(&dir)
+                .unwrap();
+
+            let warnings = warnings.lock().unwrap().clone();
+            warnings
+        };
+
+        let warnings_for = |version, names: &[&str]| {
+            let mut generator = ImportLibraryGenerator::new("x86_64", "msvc");
+            generator.version(version).data_symbols(names);
+            warnings_of(&mut generator)
+        };
+
+        let names = [
+            "Py_SetProgramName",
+            "PyUnicode_AsUnicode",
+            "PyExc_TypeError",
+        ];
+
+        assert_eq!(warnings_for(Some((3, 10)), &names), []);
+        assert_eq!(
+            warnings_for(Some((3, 12)), &names),
+            [
+                ("Py_SetProgramName".to_owned(), Some((3, 11)), None),
+                ("PyUnicode_AsUnicode".to_owned(), None, Some((3, 12))),
+            ]
+        );
+
+        // Neither deprecated nor ever exported
+        assert_eq!(warnings_for(None, &["Py_NoSuchSymbol"]), []);
+
+        // The caller-provided definitions exports are checked too.
+        let def = "EXPORTS\nPy_SetPythonHome\nPyUnicode_AsUnicode\nPy_NoSuchSymbol\n";
+        let mut generator = ImportLibraryGenerator::new("x86_64", "msvc");
+        generator
+            .version(Some((3, 12)))
+            .custom_def(def, "mypython.dll");
+        assert_eq!(
+            warnings_of(&mut generator),
+            [
+                ("Py_SetPythonHome".to_owned(), Some((3, 11)), None),
+                ("PyUnicode_AsUnicode".to_owned(), None, Some((3, 12))),
+            ]
+        );
+
+        let def = "LIBRARY python314.dll\nEXPORTS\nPy_Initialize\nPy_GetPath\n";
+        let mut generator = ImportLibraryGenerator::new("x86_64", "msvc");
+        generator.version(Some((3, 14))).override_def(
+            PythonImplementation::CPython,
+            Some((3, 14)),
+            def,
+        );
+        assert_eq!(
+            warnings_of(&mut generator),
+            [("Py_GetPath".to_owned(), Some((3, 13)), None)]
+        );
+    }
+
+    #[test]
+    fn unwritable_out_dir() {
+        let mut dir = PathBuf::from(env!("CARGO_MANIFEST_DIR"));
+        dir.push("target");
+        dir.push("python3-unwritable");
+        create_dir_all(&dir).unwrap();
+
+        // A regular file in place of the output directory
+        let file = dir.join("not-a-dir");
+        write(&file, "").unwrap();
+
+        let err = ImportLibraryGenerator::new("x86_64", "msvc")
+            .generate(&file)
+            .unwrap_err();
+        assert!(matches!(err, Error::Io(_)));
+        assert!(err.to_string().contains(&file.display().to_string()));
+    }
+
+    #[test]
+    fn implib_file_extension() {
+        assert_eq!(implib_file_ext("gnu").unwrap(), ".dll.a");
+        assert_eq!(implib_file_ext("gnullvm").unwrap(), ".dll.a");
+        assert_eq!(implib_file_ext("msvc").unwrap(), ".lib");
+        assert!(matches!(
+            implib_file_ext("musl"),
+            Err(Error::UnsupportedEnv(_))
+        ));
+
+        assert_eq!(check_implib_extension(".a").unwrap(), ".a");
+        for ext in ["", ".", "lib", "./lib"] {
+            assert!(check_implib_extension(ext).is_err(), "{}", ext);
+        }
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn implib_extension_override() {
+        let mut dir = PathBuf::from(env!("CARGO_MANIFEST_DIR"));
+        dir.push("target");
+        dir.push("x86_64-pc-windows-gnu");
+        dir.push("python3-extension");
+
+        let report = ImportLibraryGenerator::new("x86_64", "gnu")
+            .implib_extension_override(".lib")
+            .generate(&dir)
+            .unwrap();
+        assert_eq!(report.implib_path, dir.join("python3.lib"));
+        assert!(report.tool.contains("dlltool"));
+
+        let report = ImportLibraryGenerator::new("x86_64", "msvc")
+            .version(Some((3, 12)))
+            .implib_extension_override(".dll.a")
+            .generate(&dir)
+            .unwrap();
+        assert_eq!(report.implib_path, dir.join("python312.dll.a"));
+
+        let err = ImportLibraryGenerator::new("x86_64", "msvc")
+            .implib_extension_override("lib")
+            .generate(&dir)
+            .unwrap_err();
+        assert!(matches!(err, Error::UnsupportedFeature(_)));
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn generate_gnu32() {
+        let mut dir = PathBuf::from(env!("CARGO_MANIFEST_DIR"));
+        dir.push("target");
+        dir.push("i686-pc-windows-gnu");
+        dir.push("python3-dll");
+
+        generate_implib_for_target(&dir, "x86", "gnu").unwrap();
+    }
+
+    #[test]
+    fn empty_implib_file() {
+        let mut dir = PathBuf::from(env!("CARGO_MANIFEST_DIR"));
+        dir.push("target");
+        dir.push("empty-implib");
+        create_dir_all(&dir).unwrap();
+
+        let path = dir.join("missing.lib");
+        assert!(!is_archive_file(&path).unwrap());
+
+        let path = dir.join("empty.lib");
+        write(&path, b"").unwrap();
+        assert!(!is_archive_file(&path).unwrap());
+
+        let path = dir.join("archive.lib");
+        write(&path, ARCHIVE_MAGIC).unwrap();
+        assert!(is_archive_file(&path).unwrap());
+    }
+
+    #[test]
+    fn parse_abiflags() {
+        assert!("".parse::<AbiFlags>().unwrap().is_empty());
+
+        let flags: AbiFlags = "dt".parse().unwrap();
+        assert!(flags.free_threaded && flags.debug);
+        assert_eq!(flags.to_string(), "td");
+
+        assert!("x".parse::<AbiFlags>().is_err());
+        assert!("tt".parse::<AbiFlags>().is_err());
+
+        // Debug builds are not supported yet.
+        let mut generator = ImportLibraryGenerator::new("x86_64", "msvc");
+        generator.version(Some((3, 13))).abiflags(Some("dt"));
+        let err = generator.generate(Path::new("target")).unwrap_err();
+        assert!(matches!(err, Error::UnsupportedPython(_)));
+        assert!(err.to_string().contains("debug"), "{}", err);
+
+        let err = generator.abiflags(Some("x")).generate(Path::new("target"));
+        assert!(matches!(err, Err(Error::UnsupportedPython(_))));
+    }
+
+    #[test]
+    fn stable_abi_selection() {
+        let dir = Path::new("");
+
+        let mut generator = ImportLibraryGenerator::new("x86_64", "msvc");
+        generator.stable_abi(Some(StableAbi::default()));
+        let path = generator.implib_file_path(dir, IMPLIB_EXT_MSVC);
+        assert_eq!(path, Path::new("python3.lib"));
+        let stable_def = generator.def_to_string().unwrap();
+        assert!(stable_def.contains("LIBRARY python3.dll"));
+
+        generator.stable_abi(Some(StableAbi {
+            free_threaded: true,
+        }));
+        let path = generator.implib_file_path(dir, IMPLIB_EXT_MSVC);
+        assert_eq!(path, Path::new("python3t.lib"));
+        assert!(generator
+            .def_to_string()
+            .unwrap()
+            .contains("LIBRARY python3t.dll"));
+
+        // The version restricts the Stable ABI symbols only.
+        generator.version(Some((3, 13)));
+        let path = generator.implib_file_path(dir, IMPLIB_EXT_MSVC);
+        assert_eq!(path, Path::new("python3t.lib"));
+        assert!(generator.def_to_string().is_ok());
+
+        let mut generator = ImportLibraryGenerator::new("x86_64", "msvc");
+        generator
+            .stable_abi(Some(StableAbi::default()))
+            .version(Some((3, 9)));
+        let def = generator.def_to_string().unwrap();
+        assert!(def.contains("LIBRARY python3.dll"));
+        assert!(!def.contains("Py_NewRef"));
+        assert!(stable_def.contains("Py_NewRef"));
+
+        // ABI flags are for the versioned import libraries.
+        let err = generator.abiflags(Some("t")).def_to_string().unwrap_err();
+        assert!(err.to_string().contains("StableAbi"), "{}", err);
+
+        let err = ImportLibraryGenerator::new("x86_64", "gnu")
+            .stable_abi(Some(StableAbi::default()))
+            .implementation(PythonImplementation::PyPy)
+            .version(Some((3, 10)))
+            .def_to_string()
+            .unwrap_err();
+        assert!(matches!(err, Error::UnsupportedPython(_)));
+    }
+
+    #[test]
+    fn version_string() {
+        let no_flags = AbiFlags::default();
+        let free_threaded = AbiFlags {
+            free_threaded: true,
+            debug: false,
+        };
+
+        assert_eq!(parse_version("3.12").unwrap(), ((3, 12), no_flags));
+        assert_eq!(parse_version("3.13t").unwrap(), ((3, 13), free_threaded));
+        assert_eq!(parse_version("312").unwrap(), ((3, 12), no_flags));
+        assert_eq!(parse_version("37").unwrap(), ((3, 7), no_flags));
+        assert_eq!(parse_version("313t").unwrap(), ((3, 13), free_threaded));
+
+        for version in [
+            "", "3", "3.", ".12", "3.12.1", "3.+1", "3.13x", "t", "3.1000",
+        ] {
+            let err = parse_version(version).unwrap_err();
+            assert!(
+                err.to_string().contains("Invalid Python version"),
+                "{}",
+                version
+            );
+        }
+
+        let mut generator = ImportLibraryGenerator::new("x86_64", "msvc");
+        generator.version_str("3.13t").unwrap();
+        let path = generator.implib_file_path(Path::new(""), IMPLIB_EXT_MSVC);
+        assert_eq!(path, Path::new("python313t.lib"));
+
+        generator.version_str("3.12").unwrap();
+        let path = generator.implib_file_path(Path::new(""), IMPLIB_EXT_MSVC);
+        assert_eq!(path, Path::new("python312.lib"));
+
+        assert!(generator.version_str("three").is_err());
+    }
+
+    #[test]
+    fn link_lib_name() {
+        let mut generator = ImportLibraryGenerator::new("x86_64", "gnu");
+        assert_eq!(generator.link_lib_name(), "python3");
+
+        generator.version(Some((3, 9)));
+        assert_eq!(generator.link_lib_name(), "python39");
+
+        generator.version(Some((3, 13))).abiflags(Some("t"));
+        assert_eq!(generator.link_lib_name(), "python313t");
+
+        generator.dll_name(Some("libpython3.13.dll"));
+        assert_eq!(generator.link_lib_name(), "libpython3.13");
+
+        let mut generator = ImportLibraryGenerator::new("x86_64", "msvc");
+        generator
+            .version(Some((3, 12)))
+            .stable_abi(Some(StableAbi::default()));
+        assert_eq!(generator.link_lib_name(), "python3");
+
+        let path = generator.implib_file_path(Path::new(""), IMPLIB_EXT_MSVC);
+        assert_eq!(path, Path::new("python3.lib"));
+    }
+
+    #[test]
+    fn abi_tag() {
+        let (implementation, version, flags) = PythonImplementation::from_abi_tag("cp312").unwrap();
+        assert!(matches!(implementation, PythonImplementation::CPython));
+        assert_eq!((version, flags), ((3, 12), None));
+
+        let (implementation, version, flags) =
+            PythonImplementation::from_abi_tag("cp313t").unwrap();
+        assert!(matches!(implementation, PythonImplementation::CPython));
+        assert_eq!((version, flags.as_deref()), ((3, 13), Some("t")));
+
+        let (implementation, version, flags) = PythonImplementation::from_abi_tag("pp310").unwrap();
+        assert!(matches!(implementation, PythonImplementation::PyPy));
+        assert_eq!((version, flags), ((3, 10), None));
+
+        let err = PythonImplementation::from_abi_tag("graalpy311").unwrap_err();
+        assert!(err.to_string().contains("prefix 'graalpy'"));
+
+        for tag in ["", "cp", "cp3.12", "cp3", "cp312x", "312"] {
+            let err = PythonImplementation::from_abi_tag(tag).unwrap_err();
+            assert!(matches!(err, Error::UnsupportedPython(_)), "{}", tag);
+        }
+    }
+
+    #[test]
+    fn pyo3_config_file() {
+        let mut dir = PathBuf::from(env!("CARGO_MANIFEST_DIR"));
+        dir.push("target");
+        dir.push("python3-pyo3-config");
+        create_dir_all(&dir).unwrap();
+
+        let config = dir.join("pyo3-build-config.txt");
+        let generator_for = |contents: &str| {
+            write(&config, contents).unwrap();
+            ImportLibraryGenerator::from_pyo3_config(&config, "x86_64", "msvc").unwrap()
+        };
+
+        let generator =
+            generator_for("implementation=CPython\nversion=3.13\nbuild_flags=Py_GIL_DISABLED\n");
+        let path = generator.implib_file_path(Path::new(""), IMPLIB_EXT_MSVC);
+        assert_eq!(path, Path::new("python313t.lib"));
+
+        let generator = generator_for("implementation=CPython\nversion=3.9\nabi3=true\n");
+        let path = generator.implib_file_path(Path::new(""), IMPLIB_EXT_MSVC);
+        assert_eq!(path, Path::new("python3.lib"));
+        assert!(!generator.def_to_string().unwrap().contains("Py_NewRef"));
+
+        let generator = generator_for("implementation=PyPy\nversion=3.10\nabi3=true\n");
+        assert!(generator
+            .def_to_string()
+            .unwrap()
+            .contains("libpypy3.10-c.dll"));
+
+        write(
+            &config,
+            "implementation=CPython\nversion=3.12\nshared=false\n",
+        )
+        .unwrap();
+        let err = ImportLibraryGenerator::from_pyo3_config(&config, "x86_64", "msvc").unwrap_err();
+        assert!(matches!(err, Error::UnsupportedPython(_)));
+        assert!(err.to_string().contains("statically"));
+
+        let err =
+            ImportLibraryGenerator::from_pyo3_config(&dir.join("missing.txt"), "x86_64", "msvc")
+                .unwrap_err();
+        assert!(matches!(err, Error::Io(_)));
+    }
+
+    #[test]
+    fn embedded_def_export_counts() {
+        assert_eq!(
+            def_export_count("LIBRARY x.dll\r\n; EXPORTS\r\nEXPORTS\r\n  a\r\n\r\n; b\r\nc DATA"),
+            2
+        );
+        assert_eq!(def_export_count(""), 0);
+
+        let defs = [
+            include_str!("python3.def"),
+            include_str!("python3t.def"),
+            include_str!("python37.def"),
+            include_str!("python313t.def"),
+            include_str!("libpypy3-c.def"),
+            include_str!("libpypy3.10-c.def"),
+        ];
+
+        for def in defs {
+            let parsed = ModuleDef::parse(def).unwrap();
+            assert_eq!(def_export_count(def), parsed.exports().count());
+        }
+    }
+
+    #[test]
+    fn ordinal_imports() {
+        let err = ImportLibraryGenerator::new("x86_64", "msvc")
+            .version(Some((3, 12)))
+            .ordinal_imports(true)
+            .def_to_string()
+            .unwrap_err();
+        assert!(matches!(err, Error::UnsupportedFeature(_)));
+        assert!(err.to_string().contains("python312.def"));
+    }
+
+    #[test]
+    fn custom_def() {
+        let def = "LIBRARY embedded.dll\nEXPORTS\nPy_Initialize\nPyExc_TypeError DATA\n";
+
+        let mut generator = ImportLibraryGenerator::new("x86_64", "gnu");
+        generator
+            .implementation(PythonImplementation::PyPy)
+            .version(Some((3, 10)))
+            .custom_def(def, "mypython.dll");
+
+        assert_eq!(generator.link_lib_name(), "mypython");
+        assert_eq!(
+            generator.def_to_string().unwrap(),
+            "LIBRARY mypython.dll\nEXPORTS\nPy_Initialize\nPyExc_TypeError DATA\n"
+        );
+
+        // The extension is added the same way as by `dll_name()`.
+        generator.custom_def(def, "mypython");
+        assert_eq!(generator.link_lib_name(), "mypython");
+        assert!(generator
+            .def_to_string()
+            .unwrap()
+            .starts_with("LIBRARY mypython.dll\n"));
+
+        let err = generator
+            .custom_def("EXPORTS\nPy_Initialize @x\n", "mypython.dll")
+            .def_to_string()
+            .unwrap_err();
+        assert!(matches!(err, Error::InvalidDef(_)));
+    }
+
+    #[test]
+    fn override_def() {
+        let python314 = "LIBRARY \"python314.dll\"\nEXPORTS\nPy_Initialize\nPy_None DATA\n";
+        let python314t = "LIBRARY python314t.dll\nEXPORTS\nPy_Initialize\n";
+
+        let mut generator = ImportLibraryGenerator::new("x86_64", "msvc");
+        generator
+            .override_def(PythonImplementation::CPython, Some((3, 14)), python314)
+            .override_def(PythonImplementation::CPython, Some((3, 14)), python314t);
+
+        // The other versions keep the embedded definitions.
+        let def = generator.version(Some((3, 12))).def_to_string().unwrap();
+        assert!(def.contains("\nLIBRARY \"python312.dll\"\n"));
+
+        let def = generator.version(Some((3, 14))).def_to_string().unwrap();
+        assert_eq!(def, python314);
+        assert_eq!(generator.link_lib_name(), "python314");
+
+        let def = generator.abiflags(Some("t")).def_to_string().unwrap();
+        assert_eq!(def, python314t);
+
+        let mut generator = ImportLibraryGenerator::new("x86_64", "msvc");
+        let err = generator
+            .version(Some((3, 13)))
+            .override_def(PythonImplementation::CPython, Some((3, 13)), python314)
+            .def_to_string()
+            .unwrap_err();
+        assert!(matches!(err, Error::InvalidDef(_)));
+        assert!(err.to_string().contains("python313.dll"), "{}", err);
+    }
+
+    #[test]
+    fn from_dll() {
+        let mut dir = PathBuf::from(env!("CARGO_MANIFEST_DIR"));
+        dir.push("target");
+        dir.push("python3-from-dll");
+        create_dir_all(&dir).unwrap();
+
+        let image = pe::test_dll(
+            0x14c,
+            "python313t.dll",
+            &[
+                ("PyExc_TypeError", Ok(false)),
+                ("Py_IsInitialized", Ok(true)),
+                ("Py_Initialize", Ok(true)),
+                ("PyObject_Call", Err("python3.PyObject_Call")),
+            ],
+        );
+        let dll = dir.join("python313t.dll");
+        write(&dll, image).unwrap();
+
+        let mut generator = ImportLibraryGenerator::from_dll(&dll, "gnu").unwrap();
+        assert_eq!(generator.arch, "x86");
+        assert_eq!(generator.version, Some((3, 13)));
+        assert!(generator.abiflags.free_threaded);
+        assert_eq!(generator.link_lib_name(), "python313t");
+
+        let def = generator
+            .data_symbols(&["Py_Initialize"])
+            .def_to_string()
+            .unwrap();
+        assert_eq!(
+            def,
+            "LIBRARY \"python313t.dll\"\n\
+             EXPORTS\n\
+             PyExc_TypeError @1 DATA\n\
+             Py_IsInitialized @2\n\
+             Py_Initialize @3 DATA\n\
+             PyObject_Call @4\n"
+        );
+
+        let not_dll = dir.join("python3.def");
+        write(&not_dll, "EXPORTS\n").unwrap();
+        let err = ImportLibraryGenerator::from_dll(&not_dll, "gnu").unwrap_err();
+        assert!(matches!(err, Error::InvalidDll(_)));
+    }
+
+    #[test]
+    fn data_symbol_override() {
+        let mut generator = ImportLibraryGenerator::new("x86_64", "msvc");
+        generator.version(Some((3, 12)));
+        let def = generator.def_to_string().unwrap();
+        assert!(def.lines().any(|line| line == "Py_Initialize"));
+
+        let def = generator
+            .data_symbols(&["Py_Initialize", "Py_NotExported"])
+            .def_to_string()
+            .unwrap();
+        assert!(def.lines().any(|line| line == "Py_Initialize DATA"));
+        assert!(!def.contains("Py_NotExported"));
+    }
+
+    #[test]
+    fn unsupported_versions() {
+        let expect_err = |implementation, version, msg: &str| {
+            let err = ImportLibraryGenerator::new("x86_64", "msvc")
+                .implementation(implementation)
+                .version(Some(version))
+                .def_to_string()
+                .unwrap_err();
+            assert!(matches!(err, Error::UnsupportedPython(_)));
+            assert_eq!(err.to_string(), msg);
+        };
+
+        let cpython = PythonImplementation::CPython;
+        expect_err(cpython, (4, 0), "Only Python 3 is supported, got 4.0");
+        expect_err(cpython, (2, 7), "Only Python 3 is supported, got 2.7");
+        expect_err(
+            cpython,
+            (3, 6),
+            "Unsupported Python version 3.6, the supported versions are 3.7 to 3.13",
+        );
+        expect_err(
+            PythonImplementation::PyPy,
+            (3, 11),
+            "Unsupported PyPy version 3.11, the supported versions are 3.7 to 3.10",
+        );
+        expect_err(
+            PythonImplementation::PyPy,
+            (2, 7),
+            "Only Python 3 is supported, got 2.7",
+        );
+    }
+
+    #[test]
+    fn invalid_abiflags_combinations() {
+        let expect_err = |generator: &ImportLibraryGenerator, reason: &str| {
+            let err = generator.def_to_string().unwrap_err();
+            assert!(matches!(err, Error::UnsupportedPython(_)));
+            assert!(err.to_string().contains(reason), "{}", err);
+        };
+
+        // No debug builds, versioned or not.
+        let mut generator = ImportLibraryGenerator::new("x86_64", "msvc");
+        expect_err(generator.abiflags(Some("d")), "debug");
+        expect_err(generator.version(Some((3, 12))), "debug");
+
+        // The free-threaded builds appeared in 3.13.
+        let mut generator = ImportLibraryGenerator::new("x86_64", "msvc");
+        generator.abiflags(Some("t"));
+        assert!(generator.def_to_string().is_ok());
+        expect_err(generator.version(Some((3, 12))), "requires Python 3.13");
+        assert!(generator.version(Some((3, 13))).def_to_string().is_ok());
+
+        // CPython only
+        let mut generator = ImportLibraryGenerator::new("x86_64", "gnu");
+        generator.version(Some((3, 8))).abiflags(Some("t"));
+        expect_err(
+            generator.implementation(PythonImplementation::Pyston),
+            "CPython only",
+        );
+        expect_err(
+            generator.implementation(PythonImplementation::PyPy),
+            "CPython only",
+        );
+    }
+
+    #[test]
+    fn pypy_versions() {
+        let versions = supported_pypy_versions();
+        assert!(versions.contains(&((3, 7), "libpypy3-c.def")));
+        assert!(versions.contains(&((3, 8), "libpypy3-c.def")));
+        assert!(versions.contains(&((3, 10), "libpypy3.10-c.def")));
+
+        // PyPy has no free-threaded or debug builds.
+        for flags in ["t", "d"] {
+            let err = ImportLibraryGenerator::new("x86_64", "gnu")
+                .version(Some((3, 10)))
+                .implementation(PythonImplementation::PyPy)
+                .abiflags(Some(flags))
+                .def_to_string()
+                .unwrap_err();
+            assert!(matches!(err, Error::UnsupportedPython(_)));
+        }
+    }
+
+    #[test]
+    fn tool_not_found() {
+        let dir = PathBuf::from(env!("CARGO_MANIFEST_DIR")).join("target");
+        let mut command = Command::new("python3-dll-a-missing-dlltool");
+
+        let err = run_dlltool(&mut command, &dir.join("missing.lib"), None, 0).unwrap_err();
+        let source = std::error::Error::source(&err).unwrap();
+        let source = source.downcast_ref::<io::Error>().unwrap();
+        assert_eq!(source.kind(), io::ErrorKind::NotFound);
+
+        let err = io::Error::from(err);
+        assert_eq!(err.kind(), io::ErrorKind::NotFound);
+    }
+
+    #[test]
+    fn spawn_retries() {
+        assert!(is_transient_spawn_error(&io::ErrorKind::Interrupted.into()));
+        assert!(!is_transient_spawn_error(&io::ErrorKind::NotFound.into()));
+
+        #[cfg(windows)]
+        let transient = io::Error::from_raw_os_error(32);
+        #[cfg(not(windows))]
+        let transient = io::Error::from_raw_os_error(26);
+        assert!(is_transient_spawn_error(&transient));
+
+        // `ERROR_ACCESS_DENIED` on Windows, `ENOENT` elsewhere
+        let permanent = io::Error::from_raw_os_error(if cfg!(windows) { 5 } else { 2 });
+        assert!(!is_transient_spawn_error(&permanent));
+
+        // The missing tools fail immediately.
+        let mut command = Command::new("python3-dll-a-missing-dlltool");
+        let start = std::time::Instant::now();
+        let err = spawn_tool(&mut command, 3).unwrap_err();
+        assert!(matches!(err, Error::ToolSpawn { .. }));
+        assert!(start.elapsed() < SPAWN_RETRY_DELAY);
+    }
+
+    #[test]
+    fn arch_specific_exports() {
+        for free_threaded in [false, true] {
+            let stable_abi = Some(StableAbi { free_threaded });
+
+            let (_, def) = ImportLibraryGenerator::new("x86", "msvc")
+                .stable_abi(stable_abi)
+                .def_file()
+                .unwrap();
+            let exports = def_exports(&def).unwrap();
+            assert!(exports.contains("PyOS_CheckStack"));
+
+            // Not exported by the Windows DLLs at all.
+            assert!(!exports.contains("PyOS_BeforeFork"));
+            assert!(!exports.contains("_Py_NegativeRefcount"));
+
+            for arch in ["x86_64", "aarch64"] {
+                let (_, def) = ImportLibraryGenerator::new(arch, "gnu")
+                    .stable_abi(stable_abi)
+                    .def_file()
+                    .unwrap();
+                let exports = def_exports(&def).unwrap();
+                assert!(!exports.contains("PyOS_CheckStack"));
+                assert!(exports.contains("Py_Initialize"));
+            }
+        }
+    }
+
+    #[test]
+    fn presets() {
+        let generator = ImportLibraryGenerator::preset_abi3_py37("x86_64", "msvc");
+        assert_eq!(generator.link_lib_name(), "python3");
+        let def = generator.def_to_string().unwrap();
+        assert!(def.starts_with("LIBRARY python3.dll\nEXPORTS\n"));
+        assert!(!def_exports(&def).unwrap().contains("Py_NewRef"));
+
+        let generator = ImportLibraryGenerator::preset_abi3("x86_64", "msvc", (3, 10));
+        assert_eq!(generator.link_lib_name(), "python3");
+        let def = generator.def_to_string().unwrap();
+        assert!(def_exports(&def).unwrap().contains("Py_NewRef"));
+
+        let generator = ImportLibraryGenerator::stable_abi_latest("x86_64", "gnu");
+        assert_eq!(generator.limited_api, Some(CPYTHON_LATEST));
+        assert_eq!(generator.link_lib_name(), "python3");
+        let def = generator.def_to_string().unwrap();
+        assert!(def.starts_with("LIBRARY python3.dll\nEXPORTS\n"));
+        assert!(def_exports(&def).unwrap().contains("Py_NewRef"));
+
+        let generator = ImportLibraryGenerator::preset_abi3t("x86_64", "gnu");
+        assert_eq!(generator.link_lib_name(), "python3t");
+        let (def_file, _) = generator.def_file().unwrap();
+        assert_eq!(def_file, "python3t.def");
+
+        let generator = ImportLibraryGenerator::preset_freethreaded("x86_64", "msvc", (3, 13));
+        assert_eq!(generator.link_lib_name(), "python313t");
+        let (def_file, _) = generator.def_file().unwrap();
+        assert_eq!(def_file, "python313t.def");
+
+        let generator = ImportLibraryGenerator::preset_freethreaded("x86_64", "msvc", (3, 12));
+        assert!(generator.def_file().is_err());
+    }
+
+    #[test]
+    fn limited_api_level() {
+        let dir = Path::new("python3-limited-api");
+
+        let mut generator = ImportLibraryGenerator::new("x86_64", "msvc");
+
+        let (_, def) = generator.def_file().unwrap();
+        assert!(def.starts_with("LIBRARY python3.dll\nEXPORTS\n"));
+        assert!(def_exports(&def).unwrap().contains("Py_NewRef"));
+
+        // `Py_NewRef` was added in Python 3.10
+        let (_, def) = generator.limited_api(Some((3, 9))).def_file().unwrap();
+        assert!(def.starts_with("LIBRARY python3.dll\nEXPORTS\n"));
+        assert!(def_exports(&def).unwrap().contains("PyArg_Parse"));
+        assert!(!def_exports(&def).unwrap().contains("Py_NewRef"));
+
+        let (_, def) = generator.limited_api(Some((3, 10))).def_file().unwrap();
+        assert!(def_exports(&def).unwrap().contains("Py_NewRef"));
+
+        assert!(generator.limited_api(Some((3, 6))).def_file().is_err());
+
+        // Stable ABI linkage for the Python 3.9 interpreter
+        generator.limited_api(Some((3, 10))).version(Some((3, 9)));
+        let (def_file, def) = generator.def_file().unwrap();
+        assert_eq!(def_file, "python3.def");
+        assert!(def.starts_with("LIBRARY python3.dll\nEXPORTS\n"));
+        assert!(!def_exports(&def).unwrap().contains("Py_NewRef"));
+        let path = generator.implib_file_path(dir, IMPLIB_EXT_MSVC);
+        assert_eq!(path, dir.join("python3.lib"));
+
+        // Direct linkage against the versioned DLL with the Stable ABI symbols
+        generator.dll_name(Some("python39.dll"));
+        let (def_file, def) = generator.def_file().unwrap();
+        assert_eq!(def_file, "python39.def");
+        assert!(def.starts_with("LIBRARY python39.dll\nEXPORTS\n"));
+        assert!(!def_exports(&def).unwrap().contains("PyRun_SimpleString"));
+        let path = generator.implib_file_path(dir, IMPLIB_EXT_GNU);
+        assert_eq!(path, dir.join("python39.dll.a"));
+
+        let err = generator
+            .implementation(PythonImplementation::PyPy)
+            .def_file();
+        assert!(matches!(err, Err(Error::UnsupportedPython(_))));
+    }
+
+    #[test]
+    fn versioned_python3_alias() {
+        let mut generator = ImportLibraryGenerator::new("x86_64", "msvc");
+        generator.version(Some((3, 12))).dll_name(Some("python3"));
+
+        let (def_file, def) = generator.def_file().unwrap();
+        assert_eq!(def_file, "python3.def");
+        assert_eq!(generator.link_lib_name(), "python3");
+
+        let library: Vec<_> = def.lines().filter(|l| l.starts_with("LIBRARY")).collect();
+        assert_eq!(library, ["LIBRARY \"python3.dll\""]);
+
+        // The full `python312.dll` symbol set, not just the Stable ABI.
+        let (_, python312) = cpython_def(Some((3, 12)), AbiFlags::default()).unwrap();
+        let (_, python3) = cpython_def(None, AbiFlags::default()).unwrap();
+        let exports = def_exports(&def).unwrap();
+        assert_eq!(exports, def_exports(python312).unwrap());
+        assert!(!exports.is_subset(&def_exports(python3).unwrap()));
+        assert!(exports.contains("PyRun_SimpleString"));
+    }
+
+    #[test]
+    fn naming_conventions() {
+        fn names(generator: &ImportLibraryGenerator) -> (String, String, String) {
+            let (def_file, def) = generator.def_file().unwrap();
+            let library = ModuleDef::parse(&def)
+                .unwrap()
+                .library()
+                .unwrap()
+                .to_owned();
+            (def_file, library, generator.link_lib_name())
+        }
+
+        let mut generator = ImportLibraryGenerator::new("x86_64", "gnu");
+        generator.version(Some((3, 12)));
+
+        for naming in [NamingConvention::Standard, NamingConvention::Conda] {
+            generator.naming_convention(naming);
+            assert_eq!(
+                names(&generator),
+                (
+                    "python312.def".to_owned(),
+                    "python312.dll".to_owned(),
+                    "python312".to_owned()
+                )
+            );
+        }
+
+        generator.naming_convention(NamingConvention::Msys2);
+        assert_eq!(
+            names(&generator),
+            (
+                "libpython3.12.def".to_owned(),
+                "libpython3.12.dll".to_owned(),
+                "libpython3.12".to_owned()
+            )
+        );
+        let path = generator.implib_file_path(Path::new(""), IMPLIB_EXT_GNU);
+        assert_eq!(path, Path::new("libpython3.12.dll.a"));
+
+        generator.version(Some((3, 13))).abiflags(Some("t"));
+        assert_eq!(generator.link_lib_name(), "libpython3.13t");
+
+        // The Stable ABI and the explicit DLL name are not affected.
+        generator.stable_abi(Some(StableAbi::default()));
+        assert_eq!(generator.link_lib_name(), "python3");
+
+        generator.stable_abi(None).dll_name(Some("python313t.dll"));
+        assert_eq!(generator.link_lib_name(), "python313t");
+    }
+
+    #[test]
+    fn def_to_string() {
+        let mut generator = ImportLibraryGenerator::new("x86_64", "gnu");
+
+        let def = generator.def_to_string().unwrap();
+        assert!(def.starts_with("LIBRARY python3.dll\n"));
+        assert!(def_exports(&def).unwrap().contains("Py_NewRef"));
+
+        let def = generator.limited_api(Some((3, 9))).def_to_string().unwrap();
+        assert!(def.starts_with("LIBRARY python3.dll\n"));
+        assert!(!def_exports(&def).unwrap().contains("Py_NewRef"));
+
+        let def = generator
+            .limited_api(None)
+            .version(Some((3, 13)))
+            .abiflags(Some("t"))
+            .def_to_string()
+            .unwrap();
+        assert!(def.contains("LIBRARY \"python313t.dll\"\n"));
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn normalize_def_file() {
+        let def = "\u{feff}LIBRARY python3.dll\r\nEXPORTS\r\nPy_Initialize\r\nPy_None DATA\r\n";
+        let def = normalize_def(def);
+        assert_eq!(
+            def,
+            "LIBRARY python3.dll\nEXPORTS\nPy_Initialize\nPy_None DATA\n"
+        );
+
+        let mut dir = PathBuf::from(env!("CARGO_MANIFEST_DIR"));
+        dir.push("target");
+        dir.push("python3-normalized");
+        create_dir_all(&dir).unwrap();
+
+        let defpath = dir.join("python3.def");
+        write(&defpath, def).unwrap();
+
+        let flavors = [
+            get_mingw_dlltool("x86_64").unwrap(),
+            DllToolCommand::Llvm {
+                command: Command::new(DLLTOOL_MSVC),
+                machine: "i386:x86-64".to_owned(),
+            },
+        ];
+
+        for (i, flavor) in flavors.into_iter().enumerate() {
+            let libpath = dir.join(format!("python3-{}.lib", i));
+            let mut command = flavor.build(&defpath, &libpath, &[]);
+            run_dlltool(&mut command, &libpath, None, 0).unwrap();
+        }
+    }
+
+    #[test]
+    fn support_matrix_entries() {
+        let matrix = support_matrix();
+        assert_eq!(matrix.len(), all_generators("x86_64", "gnu").len());
+
+        let stable_abi = &matrix[0];
+        assert_eq!(stable_abi.implementation, PythonImplementation::CPython);
+        assert_eq!(stable_abi.version, None);
+        assert_eq!(stable_abi.def_file, "python3.def");
+
+        let free_threaded = SupportEntry {
+            implementation: PythonImplementation::CPython,
+            version: Some((3, 13)),
+            abiflags: AbiFlags {
+                free_threaded: true,
+                debug: false,
+            },
+            def_file: "python313t.def",
+        };
+        assert!(matrix.contains(&free_threaded));
+
+        let pypy37 = matrix
+            .iter()
+            .find(|entry| entry.implementation == PythonImplementation::PyPy)
+            .unwrap();
+        assert_eq!(pypy37.version, Some((3, 7)));
+        assert_eq!(pypy37.def_file, "libpypy3-c.def");
+
+        for entry in &matrix {
+            let generator = entry.generator("x86_64", "msvc");
+            let (def_file, _) = generator.def_file().unwrap();
+            assert_eq!(def_file, entry.def_file);
+            assert!(Path::new(env!("CARGO_MANIFEST_DIR"))
+                .join("src")
+                .join(entry.def_file)
+                .is_file());
+        }
+    }
+
+    #[test]
+    fn exported_symbols() {
+        let cpython = PythonImplementation::CPython;
+
+        assert!(exports_symbol("x86_64", cpython, None, None, "Py_IsInitialized").unwrap());
+        assert!(!exports_symbol("x86_64", cpython, None, None, "_PyObject_GC_Malloc").unwrap());
+        assert!(
+            exports_symbol("x86_64", cpython, Some((3, 9)), None, "_PyObject_GC_Malloc").unwrap()
+        );
+        assert!(exports_symbol(
+            "aarch64",
+            cpython,
+            Some((3, 13)),
+            Some("t"),
+            "Py_IsInitialized"
+        )
+        .unwrap());
+        assert!(
+            !exports_symbol("x86_64", cpython, Some((3, 13)), None, "Py_NoSuchSymbol").unwrap()
+        );
+
+        // The 32-bit x86 only export.
+        assert!(exports_symbol("x86", cpython, None, None, "PyOS_CheckStack").unwrap());
+        assert!(exports_symbol("i686", cpython, None, None, "PyOS_CheckStack").unwrap());
+        assert!(!exports_symbol("x86_64", cpython, None, None, "PyOS_CheckStack").unwrap());
+        assert!(!exports_symbol("aarch64", cpython, None, None, "PyOS_CheckStack").unwrap());
+
+        let pypy = PythonImplementation::PyPy;
+        assert!(exports_symbol("x86_64", pypy, Some((3, 10)), None, "PyPy_IsInitialized").unwrap());
+        assert!(!exports_symbol("x86_64", pypy, Some((3, 10)), None, "Py_IsInitialized").unwrap());
+
+        assert!(exports_symbol(
+            "x86_64",
+            cpython,
+            Some((3, 12)),
+            Some("t"),
+            "Py_IsInitialized"
+        )
+        .is_err());
+        assert!(exports_symbol("x86_64", pypy, Some((3, 6)), None, "PyPy_IsInitialized").is_err());
+        assert!(exports_symbol("mips", cpython, None, None, "Py_IsInitialized").is_err());
+    }
+
+    #[test]
+    fn pyston_versions() {
+        let mut generator = ImportLibraryGenerator::new("x86_64", "msvc");
+        generator.implementation(PythonImplementation::Pyston);
+
+        let def = generator.version(Some((3, 8))).def_to_string().unwrap();
+        assert!(def.contains("LIBRARY \"python38.dll\"\n"));
+
+        for version in [None, Some((3, 7)), Some((3, 13))] {
+            let err = generator.version(version).def_to_string().unwrap_err();
+            assert!(matches!(err, Error::UnsupportedPython(_)));
+        }
+    }
+
+    #[test]
+    fn quoted_command_line() {
+        let mut command = Command::new("llvm-dlltool");
+        command.args([
+            "-m",
+            "arm64",
+            "-d",
+            "my dir/python3.def",
+            "-l",
+            "it's.lib",
+            "",
+        ]);
+
+        let expected = if cfg!(windows) {
+            r#"llvm-dlltool -m arm64 -d "my dir/python3.def" -l it's.lib """#
+        } else {
+            r#"llvm-dlltool -m arm64 -d 'my dir/python3.def' -l 'it'\''s.lib' ''"#
+        };
+        assert_eq!(command_line(&command), expected);
+
+        #[cfg(windows)]
+        assert_eq!(shell_quote(r#"C:\a b\"c\"#), r#""C:\a b\\\"c\\""#);
+    }
+
+    #[test]
+    fn generate_msvc() {
+        let mut dir = PathBuf::from(env!("CARGO_MANIFEST_DIR"));
+        dir.push("target");
+        dir.push("x86_64-pc-windows-msvc");
+        dir.push("python3-dll");
+
+        ImportLibraryGenerator::new("x86_64", "msvc")
+            .generate(&dir)
+            .unwrap();
+
+        // Free-threaded Stable ABI
+        let report = ImportLibraryGenerator::new("x86_64", "msvc")
+            .abiflags(Some("t"))
+            .generate(&dir)
+            .unwrap();
+        assert_eq!(report.implib_path, dir.join("python3t.lib"));
+
+        for minor in 7..=13 {
+            ImportLibraryGenerator::new("x86_64", "msvc")
+                .version(Some((3, minor)))
+                .generate(&dir)
+                .unwrap();
+        }
+
+        // The patch level does not affect the import library name
+        let report = ImportLibraryGenerator::new("x86_64", "msvc")
+            .version(Some((3, 12)))
+            .patch_level(Some(5))
+            .generate(&dir)
+            .unwrap();
+        assert_eq!(report.implib_path, dir.join("python312.lib"));
+
+        // Free-threaded CPython v3.13+
+        for minor in 13..=13 {
+            ImportLibraryGenerator::new("x86_64", "msvc")
+                .version(Some((3, minor)))
+                .abiflags(Some("t"))
+                .generate(&dir)
+                .unwrap();
+        }
+
+        // PyPy
+        for minor in 7..=10 {
+            ImportLibraryGenerator::new("x86_64", "msvc")
+                .version(Some((3, minor)))
+                .implementation(PythonImplementation::PyPy)
+                .generate(&dir)
+                .unwrap();
+        }
+
+        // Pyston
+        ImportLibraryGenerator::new("x86_64", "msvc")
+            .version(Some((3, 8)))
+            .implementation(PythonImplementation::Pyston)
+            .generate(&dir)
+            .unwrap();
+    }
+
+    #[cfg(windows)]
+    #[test]
+    fn generate_msvc_arm64_lib_exe() {
+        // Requires the ARM64 MSVC toolset, `zig dlltool` takes precedence.
+        if find_lib_exe("aarch64").is_empty() || find_zig().is_some() {
+            return;
+        }
+
+        let mut dir = PathBuf::from(env!("CARGO_MANIFEST_DIR"));
+        dir.push("target");
+        dir.push("aarch64-pc-windows-msvc");
+        dir.push("python3-lib-exe");
+
+        let report = ImportLibraryGenerator::new("aarch64", "msvc")
+            .generate(&dir)
+            .unwrap();
+        assert_eq!(report.implib_path, dir.join("python3.lib"));
+
+        let tool = Path::new(&report.tool).file_name().unwrap();
+        assert!(tool.eq_ignore_ascii_case(LIB_MSVC), "{}", report.tool);
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn generate_msvc_export_file() {
+        let mut dir = PathBuf::from(env!("CARGO_MANIFEST_DIR"));
+        dir.push("target");
+        dir.push("x86_64-pc-windows-msvc");
+        dir.push("python3-exp");
+
+        let report = ImportLibraryGenerator::new("x86_64", "msvc")
+            .generate(&dir)
+            .unwrap();
+        assert_eq!(report.implib_path, dir.join("python3.lib"));
+        assert_eq!(report.def_path, dir.join("python3.def"));
+        assert_eq!(report.export_path, None);
+
+        // Neither `llvm-dlltool` nor `zig dlltool` produce `.exp` files.
+        let err = ImportLibraryGenerator::new("x86_64", "msvc")
+            .export_file(true)
+            .generate(&dir)
+            .unwrap_err();
+        assert!(matches!(err, Error::UnsupportedFeature(_)));
+    }
+
+    #[test]
+    fn dry_run() {
+        let mut dir = PathBuf::from(env!("CARGO_MANIFEST_DIR"));
+        dir.push("target");
+        dir.push("x86_64-pc-windows-msvc");
+        dir.push("python3-dry-run");
+
+        let report = ImportLibraryGenerator::new("x86_64", "msvc")
+            .version(Some((3, 12)))
+            .dlltool_command(
+                Command::new("python3-dll-a-missing-dlltool"),
+                DllToolFlavor::Llvm,
+            )
+            .dry_run(true)
+            .generate(&dir)
+            .unwrap();
+        assert_eq!(report.implib_path, dir.join("python312.lib"));
+        assert_eq!(report.def_path, dir.join("python312.def"));
+        assert_eq!(report.export_path, None);
+        assert_eq!(report.tool, "python3-dll-a-missing-dlltool");
+        assert_eq!(report.tool_time, Duration::ZERO);
+
+        // Nothing has been written.
+        assert!(!dir.exists());
+
+        let mut generator = ImportLibraryGenerator::new("x86_64", "msvc");
+        assert!(generator.requires_external_tool());
+        assert!(!generator.dry_run(true).requires_external_tool());
+
+        // The plan resolution errors are still reported.
+        let err = ImportLibraryGenerator::new("x86_64", "gnu")
+            .export_file(true)
+            .dry_run(true)
+            .generate(&dir)
+            .unwrap_err();
+        assert!(matches!(err, Error::UnsupportedFeature(_)));
+    }
+
+    #[test]
+    fn tool_argv() {
+        let mut dir = PathBuf::from(env!("CARGO_MANIFEST_DIR"));
+        dir.push("target");
+        dir.push("python3-argv");
+
+        let mut generator = ImportLibraryGenerator::new("x86_64", "msvc");
+        generator
+            .version(Some((3, 12)))
+            .dlltool_command(Command::new("llvm-dlltool"), DllToolFlavor::Llvm);
+
+        let def_path = dir.join("python312.def");
+        let implib_path = dir.join("python312.lib");
+        let expected: Vec<OsString> = vec![
+            "llvm-dlltool".into(),
+            "-m".into(),
+            "i386:x86-64".into(),
+            "-d".into(),
+            def_path.into(),
+            "-l".into(),
+            implib_path.into(),
+        ];
+        assert_eq!(generator.tool_argv(&dir).unwrap(), expected);
+
+        // Nothing has been written.
+        assert!(!dir.exists());
+
+        let mut generator = ImportLibraryGenerator::new("x86_64", "gnu");
+        generator
+            .dlltool_command(Command::new("dlltool"), DllToolFlavor::Mingw)
+            .dll_name(Some("python3t"));
+
+        let def_path = dir.join("python3t.def");
+        let implib_path = dir.join("python3t.dll.a");
+        let expected: Vec<OsString> = vec![
+            "dlltool".into(),
+            "--input-def".into(),
+            def_path.into(),
+            "--dllname".into(),
+            "python3t.dll".into(),
+            "--output-lib".into(),
+            implib_path.into(),
+        ];
+        assert_eq!(generator.tool_argv(&dir).unwrap(), expected);
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn tool_env() {
+        let mut dir = PathBuf::from(env!("CARGO_MANIFEST_DIR"));
+        dir.push("target");
+        dir.push("x86_64-pc-windows-msvc");
+        dir.push("python3-tool-env");
+
+        // Fails unless the variable is set for the tool process.
+        let mut command = Command::new("sh");
+        command.args([
+            "-c",
+            "test \"$PYO3_TEST_TOOL_ENV\" = 1 && exec llvm-dlltool \"$@\"",
+            "sh",
+        ]);
+
+        let mut generator = ImportLibraryGenerator::new("x86_64", "msvc");
+        generator.dlltool_command(command, DllToolFlavor::Llvm);
+
+        let err = generator.generate(&dir).unwrap_err();
+        assert!(matches!(err, Error::ToolFailed { .. }), "{}", err);
+
+        let report = generator
+            .tool_env("PYO3_TEST_TOOL_ENV", "1")
+            .generate(&dir)
+            .unwrap();
+        assert!(is_archive_file(&report.implib_path).unwrap());
+        assert!(env::var_os("PYO3_TEST_TOOL_ENV").is_none());
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn extra_tool_args() {
+        let command = DllToolCommand::Llvm {
+            command: Command::new(DLLTOOL_MSVC),
+            machine: "arm64".to_owned(),
+        };
+        let command = command.build(
+            Path::new("python3.def"),
+            Path::new("python3.lib"),
+            &["-k".to_owned()],
+        );
+        let args: Vec<_> = command.get_args().collect();
+        assert_eq!(
+            args,
+            [
+                "-m",
+                "arm64",
+                "-d",
+                "python3.def",
+                "-k",
+                "-l",
+                "python3.lib"
+            ]
+        );
+
+        let mut dir = PathBuf::from(env!("CARGO_MANIFEST_DIR"));
+        dir.push("target");
+        dir.push("aarch64-pc-windows-msvc");
+        dir.push("python3-args");
+
+        ImportLibraryGenerator::new("aarch64", "msvc")
+            .extra_tool_args(&["-k"])
+            .generate(&dir)
+            .unwrap();
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn deterministic_archive() {
+        let mut dir = PathBuf::from(env!("CARGO_MANIFEST_DIR"));
+        dir.push("target");
+        dir.push("x86_64-pc-windows-gnu");
+        dir.push("python3-deterministic");
+
+        // The failed command line shows the passed arguments.
+        let err = ImportLibraryGenerator::new("x86_64", "gnu")
+            .dlltool_command(Command::new("false"), DllToolFlavor::Mingw)
+            .deterministic_archive(Some(true))
+            .generate(&dir)
+            .unwrap_err();
+        match err {
+            Error::ToolFailed { command, .. } => {
+                assert!(
+                    command.contains(" --deterministic-libraries "),
+                    "{}",
+                    command
+                );
+            }
+            err => panic!("unexpected error: {}", err),
+        }
+
+        let err = ImportLibraryGenerator::new("x86_64", "msvc")
+            .deterministic_archive(Some(false))
+            .generate(&dir)
+            .unwrap_err();
+        assert!(matches!(err, Error::UnsupportedFeature(_)));
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn delay_load() {
+        let mut dir = PathBuf::from(env!("CARGO_MANIFEST_DIR"));
+        dir.push("target");
+        dir.push("x86_64-pc-windows-gnu");
+        dir.push("python3-delay-load");
+
+        // The failed command line shows the passed arguments.
+        let err = ImportLibraryGenerator::new("x86_64", "gnu")
+            .dlltool_command(Command::new("false"), DllToolFlavor::Mingw)
+            .delay_load(true)
+            .generate(&dir)
+            .unwrap_err();
+        match err {
+            Error::ToolFailed { command, .. } => {
+                assert!(command.contains(" --output-delaylib "), "{}", command);
+                assert!(!command.contains(" --output-lib "), "{}", command);
+            }
+            err => panic!("unexpected error: {}", err),
+        }
+
+        for flavor in [DllToolFlavor::Llvm, DllToolFlavor::LibExe] {
+            let err = ImportLibraryGenerator::new("x86_64", "msvc")
+                .dlltool_command(Command::new("false"), flavor)
+                .delay_load(true)
+                .generate(&dir)
+                .unwrap_err();
+            assert!(err.to_string().contains("/DELAYLOAD"), "{}", err);
+        }
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn verbose() {
+        let mut dir = PathBuf::from(env!("CARGO_MANIFEST_DIR"));
+        dir.push("target");
+        dir.push("x86_64-pc-windows-msvc");
+        dir.push("python3-verbose");
+
+        // The failed command lines show the passed arguments.
+        for (flavor, flag) in [
+            (DllToolFlavor::Mingw, " -v "),
+            (DllToolFlavor::LibExe, " /VERBOSE "),
+        ] {
+            let err = ImportLibraryGenerator::new("x86_64", "msvc")
+                .dlltool_command(Command::new("false"), flavor)
+                .verbose(true)
+                .generate(&dir)
+                .unwrap_err();
+            match err {
+                Error::ToolFailed { command, .. } => {
+                    assert!(command.contains(flag), "{}", command);
+                }
+                err => panic!("unexpected error: {}", err),
+            }
+        }
+
+        // `llvm-dlltool` gets no verbose option.
+        let report = ImportLibraryGenerator::new("x86_64", "msvc")
+            .dlltool_command(Command::new(DLLTOOL_MSVC), DllToolFlavor::Llvm)
+            .verbose(true)
+            .generate(&dir)
+            .unwrap();
+        assert!(is_archive_file(&report.implib_path).unwrap());
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn per_arch() {
+        let mut dir = PathBuf::from(env!("CARGO_MANIFEST_DIR"));
+        dir.push("target");
+        dir.push("python3-per-arch");
+        let _ = std::fs::remove_dir_all(&dir);
+
+        let mut generator = ImportLibraryGenerator::new("x86_64", "msvc");
+        generator.version(Some((3, 12)));
+
+        let err = generate_per_arch(&generator, &["x86_64", "loongarch64"], &dir).unwrap_err();
+        assert!(matches!(err, Error::UnsupportedArch(arch) if arch == "loongarch64"));
+        assert!(!dir.exists());
+
+        let reports = generate_per_arch(&generator, &["amd64", "x86", "aarch64"], &dir).unwrap();
+        assert_eq!(
+            reports.keys().collect::<Vec<_>>(),
+            ["aarch64", "x86", "x86_64"]
+        );
+
+        for (arch, report) in &reports {
+            assert_eq!(report.implib_path, dir.join(arch).join("python312.lib"));
+            assert!(is_archive_file(&report.implib_path).unwrap());
+        }
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn def_temp_dir() {
+        let mut dir = PathBuf::from(env!("CARGO_MANIFEST_DIR"));
+        dir.push("target");
+        dir.push("x86_64-pc-windows-msvc");
+        let out_dir = dir.join("python3-def-dir");
+        let def_dir = dir.join("python3-def-dir-scratch");
+
+        let report = ImportLibraryGenerator::new("x86_64", "msvc")
+            .def_temp_dir(&def_dir)
+            .generate(&out_dir)
+            .unwrap();
+
+        assert_eq!(report.implib_path, out_dir.join("python3.lib"));
+        assert_eq!(report.def_path, def_dir.join("python3.def"));
+        assert!(is_archive_file(&report.implib_path).unwrap());
+        assert!(report.def_path.is_file());
+        assert!(!out_dir.join("python3.def").exists());
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn out_dir_mode() {
+        use std::os::unix::fs::PermissionsExt;
+
+        let mut dir = PathBuf::from(env!("CARGO_MANIFEST_DIR"));
+        dir.push("target");
+        dir.push("x86_64-pc-windows-msvc");
+        dir.push("python3-out-dir-mode");
+        let _ = std::fs::remove_dir_all(&dir);
+        create_dir_all(&dir).unwrap();
+
+        let mode = |dir: &Path| std::fs::metadata(dir).unwrap().permissions().mode() & 0o7777;
+        let existing_mode = mode(&dir);
+
+        let out_dir = dir.join("shared").join("python3-dll");
+        let report = ImportLibraryGenerator::new("x86_64", "msvc")
+            .out_dir_mode(Some(0o770))
+            .generate(&out_dir)
+            .unwrap();
+
+        assert!(report.implib_path.is_file());
+        assert_eq!(mode(&out_dir), 0o770);
+        assert_eq!(mode(&dir.join("shared")), 0o770);
+        assert_eq!(mode(&dir), existing_mode);
+
+        // The existing output directory is left alone.
+        std::fs::set_permissions(&out_dir, std::fs::Permissions::from_mode(0o750)).unwrap();
+        ImportLibraryGenerator::new("x86_64", "msvc")
+            .out_dir_mode(Some(0o770))
+            .generate(&out_dir)
+            .unwrap();
+        assert_eq!(mode(&out_dir), 0o750);
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn append_to() {
+        let llvm_dlltool = || {
+            let mut command = Command::new(DLLTOOL_MSVC);
+            command.arg("-m").arg("i386:x86-64");
+            command
         };
 
-        let mut libpath = out_dir.to_owned();
-        libpath.push(libname);
+        for (env, flavor) in [("msvc", DllToolFlavor::Llvm), ("gnu", DllToolFlavor::Mingw)] {
+            let mut dir = PathBuf::from(env!("CARGO_MANIFEST_DIR"));
+            dir.push("target");
+            dir.push(format!("x86_64-pc-windows-{}", env));
+            dir.push("python3-append");
 
-        libpath
+            let existing = ImportLibraryGenerator::new("x86_64", env)
+                .version(Some((3, 12)))
+                .dlltool_command(llvm_dlltool(), flavor)
+                .generate(&dir)
+                .unwrap();
+            let combined = dir.join("combined.lib");
+            std::fs::copy(&existing.implib_path, &combined).unwrap();
+
+            let report = ImportLibraryGenerator::new("x86_64", env)
+                .dlltool_command(llvm_dlltool(), flavor)
+                .append_to(&combined)
+                .generate(&dir)
+                .unwrap();
+            assert!(is_archive_file(&report.implib_path).unwrap());
+
+            // The combined archive has the imports of both DLLs.
+            let combined = std::fs::read(&combined).unwrap();
+            let contains = |symbol: &[u8]| combined.windows(symbol.len()).any(|w| w == symbol);
+            assert!(contains(b"__IMPORT_DESCRIPTOR_python312\0"), "{}", env);
+            assert!(contains(b"__IMPORT_DESCRIPTOR_python3\0"), "{}", env);
+        }
+
+        let dir = PathBuf::from(env!("CARGO_MANIFEST_DIR")).join("target");
+
+        let err = ImportLibraryGenerator::new("x86_64", "msvc")
+            .append_to(&dir.join("python3-dll-a-missing.lib"))
+            .generate(&dir)
+            .unwrap_err();
+        assert!(matches!(err, Error::Io(e) if e.kind() == io::ErrorKind::NotFound));
+
+        let err = ImportLibraryGenerator::new("x86_64", "gnu")
+            .dlltool_command(Command::new("genlib"), DllToolFlavor::Genlib)
+            .append_to(Path::new("Cargo.toml"))
+            .generate(&dir)
+            .unwrap_err();
+        assert!(matches!(err, Error::UnsupportedFeature(_)));
+
+        let sibling = |program: &str, name| sibling_tool(&Command::new(program), name);
+        assert_eq!(
+            sibling(DLLTOOL_GNU, "ar"),
+            Path::new("x86_64-w64-mingw32-ar")
+        );
+        assert_eq!(
+            sibling("/usr/bin/llvm-dlltool-14", "lib"),
+            Path::new("/usr/bin/llvm-lib-14")
+        );
+        assert_eq!(sibling("zig", "lib"), Path::new("lib"));
     }
-}
 
-/// Generates `python3.dll` import library directly from the embedded
-/// Python Stable ABI definitions data for the specified compile target.
-///
-/// The import library file named `python3.dll.a` or `python3.lib` is created
-/// in directory `out_dir`.
-///
-/// The compile target architecture name (as in `CARGO_CFG_TARGET_ARCH`)
-/// is passed in `arch`.
-///
-/// The compile target environment ABI name (as in `CARGO_CFG_TARGET_ENV`)
-/// is passed in `env`.
-pub fn generate_implib_for_target(out_dir: &Path, arch: &str, env: &str) -> Result<()> {
-    ImportLibraryGenerator::new(arch, env).generate(out_dir)
-}
+    #[cfg(unix)]
+    #[test]
+    fn generate_temp() {
+        let (temp_dir, report) = ImportLibraryGenerator::new("x86_64", "msvc")
+            .generate_temp()
+            .unwrap();
+        assert_eq!(report.implib_path, temp_dir.join("python3.lib"));
+        assert!(report.implib_path.is_file());
+
+        let dir = temp_dir.to_path_buf();
+        drop(temp_dir);
+        assert!(!dir.exists());
+
+        let (temp_dir, report) = ImportLibraryGenerator::new("x86_64", "gnu")
+            .generate_temp()
+            .unwrap();
+        assert!(report.def_path.is_file());
+
+        let dir = temp_dir.keep();
+        assert!(report.implib_path.is_file());
+        std::fs::remove_dir_all(dir).unwrap();
+
+        // Nothing is left behind on failures.
+        assert!(ImportLibraryGenerator::new("loongarch64", "msvc")
+            .generate_temp()
+            .is_err());
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn mingw_dll_name() {
+        let mut dir = PathBuf::from(env!("CARGO_MANIFEST_DIR"));
+        dir.push("target");
+        dir.push("x86_64-pc-windows-gnu");
+        dir.push("python3-dllname");
+
+        let err = ImportLibraryGenerator::new("x86_64", "gnu")
+            .dlltool_command(Command::new("false"), DllToolFlavor::Mingw)
+            .dll_name(Some("python3-packaged.dll"))
+            .generate(&dir)
+            .unwrap_err();
+        match err {
+            Error::ToolFailed { command, .. } => {
+                assert!(
+                    command.contains(" --dllname python3-packaged.dll "),
+                    "{}",
+                    command
+                );
+            }
+            err => panic!("unexpected error: {}", err),
+        }
+
+        let report = ImportLibraryGenerator::new("x86_64", "gnu")
+            .dll_name(Some("python3-packaged.dll"))
+            .generate(&dir)
+            .unwrap();
+        assert_eq!(report.implib_path, dir.join("python3-packaged.dll.a"));
+
+        let implib = std::fs::read(&report.implib_path).unwrap();
+        assert!(implib
+            .windows(b"python3-packaged.dll".len())
+            .any(|name| name == b"python3-packaged.dll"));
+
+        for dll_name in ["lib/python3.dll", "lib\\python3.dll"] {
+            let err = ImportLibraryGenerator::new("x86_64", "gnu")
+                .dll_name(Some(dll_name))
+                .generate(&dir)
+                .unwrap_err();
+            assert!(matches!(err, Error::UnsupportedFeature(_)), "{}", err);
+        }
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn generate_atomic() {
+        let mut dir = PathBuf::from(env!("CARGO_MANIFEST_DIR"));
+        dir.push("target");
+        dir.push("aarch64-pc-windows-msvc");
+        dir.push("python3-atomic");
+
+        let report = ImportLibraryGenerator::new("aarch64", "msvc")
+            .generate(&dir)
+            .unwrap();
+        assert!(is_archive_file(&report.implib_path).unwrap());
+
+        // The tool rejects the bogus machine, the old library must survive.
+        ImportLibraryGenerator::new("aarch64", "msvc")
+            .extra_tool_args(&["-m", "bogus"])
+            .generate(&dir)
+            .unwrap_err();
+        assert!(is_archive_file(&report.implib_path).unwrap());
+
+        for entry in std::fs::read_dir(&dir).unwrap() {
+            let path = entry.unwrap().path();
+            assert_ne!(path.extension().unwrap(), "tmp");
+        }
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn generate_concurrent() {
+        let mut dir = PathBuf::from(env!("CARGO_MANIFEST_DIR"));
+        dir.push("target");
+        dir.push("x86_64-pc-windows-msvc");
+        dir.push("python3-concurrent");
+
+        // Run every configuration several times at once, including
+        // the PyPy versions sharing the module-definitions file name.
+        let generators: Vec<_> = (0..4)
+            .flat_map(|_| all_generators("x86_64", "msvc"))
+            .collect();
+
+        let reports: Vec<_> = std::thread::scope(|scope| {
+            let handles: Vec<_> = generators
+                .iter()
+                .map(|generator| scope.spawn(|| generator.generate(&dir)))
+                .collect();
+
+            handles
+                .into_iter()
+                .map(|handle| handle.join().unwrap().unwrap())
+                .collect()
+        });
+
+        for (generator, report) in generators.iter().zip(reports) {
+            assert!(is_archive_file(&report.implib_path).unwrap());
+
+            let def = std::fs::read_to_string(&report.def_path).unwrap();
+            assert_eq!(def, generator.def_to_string().unwrap());
+        }
+
+        for entry in std::fs::read_dir(&dir).unwrap() {
+            let path = entry.unwrap().path();
+            assert_ne!(path.extension().unwrap(), "tmp");
+        }
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn probe() {
+        let llvm = DllToolCommand::Llvm {
+            command: Command::new(DLLTOOL_MSVC),
+            machine: "arm64".to_owned(),
+        };
+        assert!(llvm.probe());
+
+        let llvm = DllToolCommand::Llvm {
+            command: Command::new(DLLTOOL_MSVC),
+            machine: "bogus".to_owned(),
+        };
+        assert!(!llvm.probe());
+
+        let missing = DllToolCommand::LibExe {
+            command: Command::new("python3-dll-a-missing-lib.exe"),
+            machine: "X64".to_owned(),
+        };
+        assert!(!missing.probe());
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn unsupported_machine() {
+        let mut dir = PathBuf::from(env!("CARGO_MANIFEST_DIR"));
+        dir.push("target");
+        dir.push("x86_64-pc-windows-msvc");
+        dir.push("python3-machine");
+
+        let err = ImportLibraryGenerator::new("x86_64", "msvc")
+            .dlltool_command(Command::new(DLLTOOL_MSVC), DllToolFlavor::Llvm)
+            .machine_override("bogus")
+            .generate(&dir)
+            .unwrap_err();
+        assert!(matches!(err, Error::ToolFailed { .. }));
+        assert!(
+            err.to_string()
+                .contains("the tool does not support the 'bogus' target machine"),
+            "{}",
+            err
+        );
+
+        // Unrelated failures are not blamed on the machine.
+        let llvm = DllToolCommand::Llvm {
+            command: Command::new(DLLTOOL_MSVC),
+            machine: "i386".to_owned(),
+        };
+        let err = llvm.diagnose_failure(Error::ToolFailed {
+            command: "llvm-dlltool".to_owned(),
+            reason: "exit status: 1".to_owned(),
+        });
+        assert_eq!(err.to_string(), "llvm-dlltool failed with exit status: 1");
+    }
+
+    /// Writes all the embedded module-definitions files into `out_dir`
+    /// under their canonical names, e.g. `python313t.def`.
+    ///
+    /// Uses the same definitions selection as the import library generators.
+    fn write_embedded_defs(out_dir: &Path) -> Result<Vec<PathBuf>> {
+        create_dir_all(out_dir)?;
+
+        let mut paths = Vec::new();
+
+        // No architecture-specific exports are dropped for x86.
+        for generator in all_generators("x86", "msvc") {
+            let (def_file, def_file_content) = generator.def_file()?;
+            let path = out_dir.join(def_file);
+
+            // Some Python versions share the same definitions.
+            if !paths.contains(&path) {
+                write(&path, def_file_content)?;
+                paths.push(path);
+            }
+        }
+
+        Ok(paths)
+    }
+
+    /// Dumps the embedded definitions for diffing them against
+    /// the symbols freshly extracted from the Python DLLs.
+    #[test]
+    fn dump_embedded_defs() {
+        let mut dir = PathBuf::from(env!("CARGO_MANIFEST_DIR"));
+        dir.push("target");
+        dir.push("embedded-defs");
+
+        let paths = write_embedded_defs(&dir).unwrap();
+
+        // Every embedded file is written, unmodified but for the line endings.
+        let src_dir = PathBuf::from(concat!(env!("CARGO_MANIFEST_DIR"), "/src"));
+        let mut src_defs = 0;
+
+        for dir_entry in std::fs::read_dir(&src_dir).unwrap() {
+            let path = dir_entry.unwrap().path();
+            if path.extension().is_some_and(|ext| ext == "def") {
+                let name = path.file_name().unwrap();
+                let dumped = read_to_string(dir.join(name)).unwrap();
+                let embedded = read_to_string(&path).unwrap();
+                assert_eq!(dumped, normalize_def(&embedded), "{:?}", name);
+                src_defs += 1;
+            }
+        }
+
+        assert_eq!(paths.len(), src_defs);
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn generate_all_msvc() {
+        let mut dir = PathBuf::from(env!("CARGO_MANIFEST_DIR"));
+        dir.push("target");
+        dir.push("aarch64-pc-windows-msvc");
+        dir.push("python3-all");
+
+        let report = generate_all(&dir, "aarch64", "msvc");
+        assert!(report.failed.is_empty(), "{:?}", report.failed);
+        assert!(report
+            .generated
+            .iter()
+            .any(|generated| generated.implib_path == dir.join("python313t.lib")));
+
+        let report = generate_all(&dir, "loongarch64", "msvc");
+        assert!(report.generated.is_empty());
+        assert_eq!(report.failed.len(), all_generators("x86_64", "msvc").len());
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn generate_batch_result() {
+        let mut dir = PathBuf::from(env!("CARGO_MANIFEST_DIR"));
+        dir.push("target");
+        dir.push("x86_64-pc-windows-msvc");
+        dir.push("python3-batch");
+
+        let mut versioned = ImportLibraryGenerator::new("x86_64", "msvc");
+        versioned.version(Some((3, 12)));
+        let mut unsupported = ImportLibraryGenerator::new("x86_64", "msvc");
+        unsupported.version(Some((3, 6)));
+
+        let report = generate_batch(&[versioned.clone(), unsupported.clone()], &dir);
+        assert!(!report.is_success());
+        assert_eq!(report.generated.len(), 1);
+        assert_eq!(report.failed.len(), 1);
+        assert_eq!(report.failed[0].0.version, Some((3, 6)));
+
+        let err = report.into_result().unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            "Import library batch generation failed: \
+             Unsupported Python version 3.6, the supported versions are 3.7 to 3.13"
+        );
+        assert!(matches!(err, Error::BatchFailed { failed } if failed.len() == 1));
+
+        let report = generate_batch(&[versioned], &dir);
+        assert!(report.is_success());
+        let generated = report.into_result().unwrap();
+        assert_eq!(generated[0].implib_path, dir.join("python312.lib"));
+    }
+
+    #[test]
+    fn generate_versioned_shorthand() {
+        let mut dir = PathBuf::from(env!("CARGO_MANIFEST_DIR"));
+        dir.push("target");
+        dir.push("i686-pc-windows-msvc");
+        dir.push("python3-versioned");
+
+        generate_implib_for_target_versioned(
+            &dir,
+            "x86",
+            "msvc",
+            Some((3, 10)),
+            PythonImplementation::PyPy,
+            None,
+        )
+        .unwrap();
+        assert!(is_archive_file(&dir.join("python310.lib")).unwrap());
+
+        let err = generate_implib_for_target_versioned(
+            &dir,
+            "x86",
+            "msvc",
+            Some((3, 12)),
+            PythonImplementation::CPython,
+            Some("x"),
+        )
+        .unwrap_err();
+        assert!(matches!(err, Error::UnsupportedPython(_)));
+    }
+
+    #[test]
+    fn generate_msvc32() {
+        let mut dir = PathBuf::from(env!("CARGO_MANIFEST_DIR"));
+        dir.push("target");
+        dir.push("i686-pc-windows-msvc");
+        dir.push("python3-dll");
+
+        generate_implib_for_target(&dir, "x86", "msvc").unwrap();
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn x86_symbol_decoration() {
+        for (env, triple, implib) in [
+            ("gnu", "i686-pc-windows-gnu", "python3.dll.a"),
+            ("msvc", "i686-pc-windows-msvc", "python3.lib"),
+        ] {
+            let mut dir = PathBuf::from(env!("CARGO_MANIFEST_DIR"));
+            dir.push("target");
+            dir.push(triple);
+            dir.push("python3-decoration");
 
-/// `dlltool` utility command builder
-///
-/// Supports Visual Studio `lib.exe`, MinGW, LLVM and Zig `dlltool` flavors.
-#[derive(Debug)]
-enum DllToolCommand {
-    /// MinGW `dlltool` program (with prefix)
-    Mingw { command: Command },
-    /// LLVM `llvm-dlltool` program (no prefix)
-    Llvm { command: Command, machine: String },
-    /// MSVC `lib.exe` program (no prefix)
-    LibExe { command: Command, machine: String },
-    /// `zig dlltool` wrapper (no prefix)
-    Zig { command: Command, machine: String },
-}
+            generate_implib_for_target(&dir, "x86", env).unwrap();
 
-impl DllToolCommand {
-    /// Attempts to find the best matching `dlltool` flavor for the target.
-    fn find_for_target(arch: &str, env: &str) -> Result<DllToolCommand> {
-        // LLVM tools use their own target architecture names...
-        let machine = match arch {
-            "x86_64" => "i386:x86-64",
-            "x86" => "i386",
-            "aarch64" => "arm64",
-            arch => arch,
-        }
-        .to_owned();
+            // Exactly one leading underscore is added to the C names.
+            let implib = std::fs::read(dir.join(implib)).unwrap();
+            let contains = |symbol: &[u8]| implib.windows(symbol.len()).any(|w| w == symbol);
 
-        // If `zig cc` is used as the linker, `zig dlltool` is the best choice.
-        if let Some(command) = find_zig() {
-            return Ok(DllToolCommand::Zig { command, machine });
+            assert!(contains(b"__imp__Py_Initialize\0"), "{}", env);
+            assert!(contains(b"__imp___Py_Dealloc\0"), "{}", env);
+            assert!(!contains(b"__imp___Py_Initialize\0"), "{}", env);
         }
+    }
 
-        match env {
-            // 64-bit and 32-bit MinGW-w64 (aka `{x86_64,i686}-pc-windows-gnu`)
-            "gnu" => Ok(DllToolCommand::Mingw {
-                command: get_mingw_dlltool(arch)?,
-            }),
+    #[test]
+    fn shorthand_signatures() {
+        // The existing build scripts depend on the exact signature.
+        let _: fn(&Path, &str, &str) -> io::Result<()> = generate_implib_for_target;
 
-            // MSVC ABI (multiarch)
-            "msvc" => {
-                if let Some(command) = find_lib_exe(arch) {
-                    // MSVC tools use their own target architecture names...
-                    let machine = match arch {
-                        "x86_64" => "X64",
-                        "x86" => "X86",
-                        "aarch64" => "ARM64",
-                        arch => arch,
-                    }
-                    .to_owned();
+        let err = generate_implib_for_target(Path::new(""), "x86_64", "unknown").unwrap_err();
+        assert_eq!(err.kind(), io::ErrorKind::Other);
+        assert!(matches!(
+            err.into_inner().unwrap().downcast::<Error>().as_deref(),
+            Ok(Error::UnsupportedEnv(_))
+        ));
 
-                    Ok(DllToolCommand::LibExe { command, machine })
-                } else {
-                    let command = Command::new(DLLTOOL_MSVC);
+        let dir = PathBuf::from(env!("CARGO_MANIFEST_DIR"))
+            .join("target")
+            .join("python3-shorthand");
 
-                    Ok(DllToolCommand::Llvm { command, machine })
-                }
-            }
-            _ => {
-                let msg = format!("Unsupported target env ABI '{}'", env);
-                Err(Error::new(ErrorKind::Other, msg))
-            }
-        }
+        let report = generate_implib_for_target_with(&dir, "x86_64", "msvc", |generator| {
+            generator.version(Some((3, 12))).dry_run(true);
+        })
+        .unwrap();
+        assert_eq!(report.implib_path, dir.join("python312.lib"));
     }
 
-    /// Returns the import library file extension used by
-    /// this `dlltool` flavor.
-    fn implib_file_ext(&self) -> &'static str {
-        if let DllToolCommand::Mingw { .. } = self {
-            IMPLIB_EXT_GNU
-        } else {
-            IMPLIB_EXT_MSVC
+    #[test]
+    fn generate_loongarch64() {
+        let mut dir = PathBuf::from(env!("CARGO_MANIFEST_DIR"));
+        dir.push("target");
+        dir.push("loongarch64-pc-windows-msvc");
+        dir.push("python3-dll");
+
+        for env in ["gnu", "msvc"] {
+            let err = ImportLibraryGenerator::new("loongarch64", env)
+                .generate(&dir)
+                .unwrap_err();
+            assert!(matches!(err, Error::UnsupportedArch(arch) if arch == "loongarch64"));
         }
+
+        // The machine name override bypasses the architecture validation.
+        let report = ImportLibraryGenerator::new("loongarch64", "msvc")
+            .machine_override("loongarch64")
+            .dry_run(true)
+            .generate(&dir)
+            .unwrap();
+        assert!(report
+            .tool_argv
+            .iter()
+            .any(|arg| arg.to_string_lossy().ends_with("loongarch64")));
     }
 
-    /// Generates the complete `dlltool` executable invocation command.
-    fn build(self, defpath: &Path, libpath: &Path) -> Command {
-        match self {
-            Self::Mingw { mut command } => {
-                command
-                    .arg("--input-def")
-                    .arg(defpath)
-                    .arg("--output-lib")
-                    .arg(libpath);
+    #[cfg(unix)]
+    #[test]
+    fn generate_unknown_arch() {
+        let mut dir = PathBuf::from(env!("CARGO_MANIFEST_DIR"));
+        dir.push("target");
+        dir.push("x86-64-pc-windows-msvc");
+        dir.push("python3-dll");
 
-                command
-            }
-            Self::Llvm {
-                mut command,
-                machine,
-            } => {
-                command
-                    .arg("-m")
-                    .arg(machine)
-                    .arg("-d")
-                    .arg(defpath)
-                    .arg("-l")
-                    .arg(libpath);
+        let err = ImportLibraryGenerator::new("x86-64", "msvc")
+            .generate(&dir)
+            .unwrap_err();
+        assert!(matches!(err, Error::UnsupportedArch(arch) if arch == "x86-64"));
 
-                command
-            }
-            Self::LibExe {
-                mut command,
-                machine,
-            } => {
-                command
-                    .arg(format!("/MACHINE:{}", machine))
-                    .arg(format!("/DEF:{}", defpath.display()))
-                    .arg(format!("/OUT:{}", libpath.display()));
+        // The typo is passed to the tool as `-m x86-64` now.
+        let err = ImportLibraryGenerator::new("x86-64", "msvc")
+            .permissive(true)
+            .generate(&dir)
+            .unwrap_err();
+        assert!(matches!(err, Error::ToolFailed { .. }));
+    }
 
-                command
-            }
-            Self::Zig {
-                mut command,
-                machine,
-            } => {
-                // Same as `llvm-dlltool`, but invoked as `zig dlltool`.
-                command
-                    .arg("dlltool")
-                    .arg("-m")
-                    .arg(machine)
-                    .arg("-d")
-                    .arg(defpath)
-                    .arg("-l")
-                    .arg(libpath);
+    #[test]
+    fn machine_names() {
+        let machine = Machine::from_arch("aarch64", false).unwrap();
+        assert_eq!(machine.as_llvm(), "arm64");
+        assert_eq!(machine.as_msvc(), "ARM64");
 
-                command
-            }
-        }
-    }
-}
+        let machine = Machine::from_arch("arm64ec", false).unwrap();
+        assert_eq!(machine.as_llvm(), "arm64ec");
+        assert_eq!(machine.as_msvc(), "ARM64EC");
 
-/// Chooses the appropriate MinGW-w64 `dlltool` executable
-/// for the target architecture.
-///
-/// Examines the user-provided `PYO3_MINGW_DLLTOOL` environment variable first
-/// and falls back to the default MinGW-w64 arch prefixes.
-fn get_mingw_dlltool(arch: &str) -> Result<Command> {
-    if let Ok(user_dlltool) = env::var("PYO3_MINGW_DLLTOOL") {
-        Ok(Command::new(user_dlltool))
-    } else {
-        let prefix_dlltool = match arch {
-            // 64-bit MinGW-w64 (aka `x86_64-pc-windows-gnu`)
-            "x86_64" => Ok(DLLTOOL_GNU),
-            // 32-bit MinGW-w64 (aka `i686-pc-windows-gnu`)
-            "x86" => Ok(DLLTOOL_GNU_32),
-            // AArch64?
-            _ => {
-                let msg = format!("Unsupported MinGW target arch '{}'", arch);
-                Err(Error::new(ErrorKind::Other, msg))
-            }
-        }?;
+        assert!(Machine::from_arch("loongarch64", false).is_err());
+        let machine = Machine::from_arch("loongarch64", true).unwrap();
+        assert_eq!(machine, Machine::Other("loongarch64".to_owned()));
+        assert!(Machine::from_arch("riscv64", false).is_err());
 
-        Ok(Command::new(prefix_dlltool))
+        let machine = Machine::from_arch("riscv64", true).unwrap();
+        assert_eq!(machine, Machine::Other("riscv64".to_owned()));
+        assert_eq!(machine.as_msvc(), "riscv64");
     }
-}
 
-/// Finds the `zig` executable (when built by `maturin --zig`).
-///
-/// Examines the `ZIG_COMMAND` environment variable
-/// to find out if `zig cc` is being used as the linker.
-fn find_zig() -> Option<Command> {
-    // `ZIG_COMMAND` may contain simply `zig` or `/usr/bin/zig`,
-    // or a more complex construct like `python3 -m ziglang`.
-    let zig_command = env::var("ZIG_COMMAND").ok()?;
+    #[test]
+    fn ucrt_dlltool() {
+        let select = |ucrt, found: &'static [&str]| {
+            select_mingw_dlltool(DLLTOOL_GNU, DLLTOOL_GNU_UCRT, ucrt, |name: &str| {
+                found.contains(&name)
+            })
+        };
 
-    // Try to emulate `sh -c ${ZIG_COMMAND}`.
-    let mut zig_cmdlet = zig_command.split_ascii_whitespace();
+        let both = &[DLLTOOL_GNU, DLLTOOL_GNU_UCRT];
+        assert_eq!(select(None, both), DLLTOOL_GNU);
+        assert_eq!(select(Some(true), both), DLLTOOL_GNU_UCRT);
+        assert_eq!(select(Some(false), both), DLLTOOL_GNU);
 
-    // Extract the main program component (e.g. `zig` or `python3`).
-    let mut zig = Command::new(zig_cmdlet.next()?);
+        let ucrt_only = &[DLLTOOL_GNU_UCRT];
+        assert_eq!(select(None, ucrt_only), DLLTOOL_GNU_UCRT);
+        assert_eq!(select(Some(false), ucrt_only), DLLTOOL_GNU);
 
-    // Append the rest of the commandlet.
-    zig.args(zig_cmdlet);
+        let classic_only = &[DLLTOOL_GNU];
+        assert_eq!(select(Some(true), classic_only), DLLTOOL_GNU);
 
-    Some(zig)
-}
+        // The default name is reported as missing.
+        assert_eq!(select(None, &[]), DLLTOOL_GNU);
+        assert_eq!(select(Some(true), &[]), DLLTOOL_GNU);
+    }
 
-/// Finds Visual Studio `lib.exe` when running on Windows.
-#[cfg(windows)]
-fn find_lib_exe(arch: &str) -> Option<Command> {
-    let target = match arch {
-        "x86_64" => "x86_64-pc-windows-msvc",
-        "x86" => "i686-pc-windows-msvc",
-        "aarch64" => "aarch64-pc-windows-msvc",
-        _ => return None,
-    };
+    #[test]
+    fn arch_aliases() {
+        assert_eq!(ImportLibraryGenerator::new("amd64", "msvc").arch, "x86_64");
+        assert_eq!(ImportLibraryGenerator::new("x64", "msvc").arch, "x86_64");
+        assert_eq!(ImportLibraryGenerator::new("arm64", "msvc").arch, "aarch64");
+        assert_eq!(ImportLibraryGenerator::new("i386", "gnu").arch, "x86");
+        assert_eq!(ImportLibraryGenerator::new("i686", "gnu").arch, "x86");
+        assert_eq!(
+            ImportLibraryGenerator::new("arm64ec", "msvc").arch,
+            "arm64ec"
+        );
+    }
 
-    cc::windows_registry::find(target, LIB_MSVC)
-}
+    #[test]
+    fn env_aliases() {
+        assert_eq!(ImportLibraryGenerator::new("x86_64", "").env, "msvc");
+        assert_eq!(ImportLibraryGenerator::new("x86_64", "sdk").env, "msvc");
+        assert_eq!(ImportLibraryGenerator::new("x86_64", "none").env, "msvc");
+        assert_eq!(ImportLibraryGenerator::new("x86_64", "gnu").env, "gnu");
 
-#[cfg(not(windows))]
-fn find_lib_exe(_arch: &str) -> Option<Command> {
-    None
-}
+        let dir = PathBuf::from(env!("CARGO_MANIFEST_DIR"))
+            .join("target")
+            .join("python3-env-aliases");
 
-#[cfg(test)]
-mod tests {
-    use std::path::PathBuf;
+        for env in ["", "sdk"] {
+            let report = ImportLibraryGenerator::new("x86_64", env)
+                .dry_run(true)
+                .generate(&dir)
+                .unwrap();
+            assert_eq!(report.implib_path, dir.join("python3.lib"));
+        }
+    }
 
-    use super::*;
+    #[test]
+    fn genlib_command() {
+        let user = UserCommand {
+            command: Command::new("genlib"),
+            flavor: DllToolFlavor::Genlib,
+        };
+        let command = DllToolCommand::from_user(user, "x86", false).unwrap();
+        assert_eq!(command.flavor(), DllToolFlavor::Genlib);
+
+        let extra_args = ["-v".to_owned()];
+        let command = command.build(
+            Path::new("python3.def"),
+            Path::new("python3.dll.a"),
+            &extra_args,
+        );
+        assert_eq!(
+            command.get_args().collect::<Vec<_>>(),
+            ["-a", "i386", "-v", "-o", "python3.dll.a", "python3.def"]
+        );
+    }
 
     #[cfg(unix)]
     #[test]
-    fn generate() {
-        // FIXME: Use "target/<arch>" dirs for temporary files.
+    fn machine_override() {
+        let mut command =
+            DllToolCommand::find_for_target("x86_64", "msvc", false, true, false).unwrap();
+        command.set_machine("arm64");
+        let command = command.build(Path::new("python3.def"), Path::new("python3.lib"), &[]);
+        assert!(command.get_args().any(|arg| arg == "arm64"));
+
         let mut dir = PathBuf::from(env!("CARGO_MANIFEST_DIR"));
         dir.push("target");
-        dir.push("x86_64-pc-windows-gnu");
+        dir.push("arm64ec-pc-windows-msvc");
         dir.push("python3-dll");
 
-        ImportLibraryGenerator::new("x86_64", "gnu")
+        // Not a known architecture, but the tool can handle it.
+        ImportLibraryGenerator::new("arm64ec", "msvc")
+            .machine_override("arm64")
             .generate(&dir)
             .unwrap();
+    }
 
-        for minor in 7..=13 {
-            ImportLibraryGenerator::new("x86_64", "gnu")
-                .version(Some((3, minor)))
-                .generate(&dir)
-                .unwrap();
-        }
-
-        // Free-threaded CPython v3.13+
-        for minor in 13..=13 {
-            ImportLibraryGenerator::new("x86_64", "gnu")
-                .version(Some((3, minor)))
-                .abiflags(Some("t"))
-                .generate(&dir)
-                .unwrap();
-        }
+    #[cfg(unix)]
+    #[test]
+    fn no_zig_preference() {
+        let command =
+            DllToolCommand::find_for_target("x86_64", "msvc", false, false, false).unwrap();
+        assert!(matches!(command, DllToolCommand::Llvm { .. }));
 
-        // PyPy
-        for minor in 7..=10 {
-            ImportLibraryGenerator::new("x86_64", "gnu")
-                .version(Some((3, minor)))
-                .implementation(PythonImplementation::PyPy)
-                .generate(&dir)
-                .unwrap();
-        }
+        let command =
+            DllToolCommand::find_for_target("x86_64", "gnu", false, false, false).unwrap();
+        assert!(matches!(command, DllToolCommand::Mingw { .. }));
     }
 
     #[cfg(unix)]
     #[test]
-    fn generate_gnu32() {
+    fn cygwin() {
+        let command = get_cygwin_dlltool("x86_64").unwrap();
+        assert_eq!(command.get_program(), DLLTOOL_CYGWIN);
+        assert!(get_cygwin_dlltool("x86").is_err());
+
+        match DllToolCommand::find_for_target("x86_64", "cygwin", false, false, false) {
+            Ok(command) => assert!(matches!(command, DllToolCommand::Mingw { .. })),
+            Err(Error::ToolNotFound { tried, env_vars }) => {
+                assert_eq!(tried, [DLLTOOL_CYGWIN]);
+                assert_eq!(env_vars, ["PYO3_MINGW_DLLTOOL", DLLTOOL_ENV]);
+            }
+            Err(e) => panic!("unexpected error: {}", e),
+        }
+
         let mut dir = PathBuf::from(env!("CARGO_MANIFEST_DIR"));
         dir.push("target");
-        dir.push("i686-pc-windows-gnu");
+        dir.push("x86_64-pc-cygwin");
         dir.push("python3-dll");
 
-        generate_implib_for_target(&dir, "x86", "gnu").unwrap();
+        // Cygwin uses the MinGW-w64 style import libraries.
+        let report = ImportLibraryGenerator::new("x86_64", "cygwin")
+            .version(Some((3, 12)))
+            .dll_name(Some("libpython3.12.dll"))
+            .dlltool_command(Command::new(DLLTOOL_GNU), DllToolFlavor::Mingw)
+            .generate(&dir)
+            .unwrap();
+        assert_eq!(report.implib_path, dir.join("libpython3.12.dll.a"));
+        assert!(is_archive_file(&report.implib_path).unwrap());
+        assert!(has_symbol_index(&report.implib_path).unwrap());
     }
 
     #[test]
-    fn generate_msvc() {
+    fn index_archive() {
         let mut dir = PathBuf::from(env!("CARGO_MANIFEST_DIR"));
         dir.push("target");
-        dir.push("x86_64-pc-windows-msvc");
-        dir.push("python3-dll");
+        dir.push("x86_64-pc-windows-gnu");
+        dir.push("python3-ranlib");
 
-        ImportLibraryGenerator::new("x86_64", "msvc")
-            .generate(&dir)
+        let mut generator = ImportLibraryGenerator::new("x86_64", "gnu");
+        generator.dlltool_command(Command::new("llvm-dlltool"), DllToolFlavor::Llvm);
+
+        let report = generator.generate(&dir).unwrap();
+        assert!(has_symbol_index(&report.implib_path).unwrap());
+
+        // Drop the leading symbol index member, keeping the others.
+        let archive = read(&report.implib_path).unwrap();
+        let (magic, members) = archive.split_at(ARCHIVE_MAGIC.len());
+        let size: usize = std::str::from_utf8(&members[48..58])
+            .unwrap()
+            .trim()
+            .parse()
             .unwrap();
+        let next = 60 + size + size % 2;
+        write(&report.implib_path, [magic, &members[next..]].concat()).unwrap();
+        assert!(!has_symbol_index(&report.implib_path).unwrap());
 
-        for minor in 7..=13 {
-            ImportLibraryGenerator::new("x86_64", "msvc")
-                .version(Some((3, minor)))
-                .generate(&dir)
-                .unwrap();
-        }
+        let user = UserCommand {
+            command: Command::new("llvm-dlltool"),
+            flavor: DllToolFlavor::Llvm,
+        };
+        let tool = DllToolCommand::from_user(user, "x86_64", false).unwrap();
+        generator.index_archive(&tool, &report.implib_path).unwrap();
+        assert!(has_symbol_index(&report.implib_path).unwrap());
 
-        // Free-threaded CPython v3.13+
-        for minor in 13..=13 {
-            ImportLibraryGenerator::new("x86_64", "msvc")
-                .version(Some((3, minor)))
-                .abiflags(Some("t"))
-                .generate(&dir)
-                .unwrap();
-        }
+        let user = UserCommand {
+            command: Command::new("x86_64-w64-mingw32-dlltool"),
+            flavor: DllToolFlavor::Mingw,
+        };
+        let tool = DllToolCommand::from_user(user, "x86_64", false).unwrap();
+        let ranlib = tool.ranlib().unwrap();
+        assert_eq!(ranlib.get_program(), "x86_64-w64-mingw32-ranlib");
 
-        // PyPy
-        for minor in 7..=10 {
-            ImportLibraryGenerator::new("x86_64", "msvc")
-                .version(Some((3, minor)))
-                .implementation(PythonImplementation::PyPy)
-                .generate(&dir)
-                .unwrap();
-        }
+        // `lib.exe` always writes the index.
+        let user = UserCommand {
+            command: Command::new("lib.exe"),
+            flavor: DllToolFlavor::LibExe,
+        };
+        let tool = DllToolCommand::from_user(user, "x86_64", false).unwrap();
+        assert!(tool.ranlib().is_none());
     }
 
     #[test]
-    fn generate_msvc32() {
+    fn dlltool_env_command() {
+        let var = "PYO3_DLL_A_TEST_DLLTOOL";
+
+        assert!(env_command(var).is_none());
+
+        env::set_var(var, "");
+        assert!(env_command(var).is_none());
+
+        env::set_var(var, "x86_64-w64-mingw32-dlltool-13");
+        let command = env_command(var).unwrap();
+        assert_eq!(command.get_program(), "x86_64-w64-mingw32-dlltool-13");
+
+        env::remove_var(var);
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn mingw_for_msvc() {
+        // `llvm-dlltool` is still preferred when available.
+        let command =
+            DllToolCommand::find_for_target("x86_64", "msvc", false, false, true).unwrap();
+        assert!(matches!(command, DllToolCommand::Llvm { .. }));
+
         let mut dir = PathBuf::from(env!("CARGO_MANIFEST_DIR"));
         dir.push("target");
-        dir.push("i686-pc-windows-msvc");
-        dir.push("python3-dll");
+        dir.push("x86_64-pc-windows-msvc");
+        dir.push("python3-mingw");
 
-        generate_implib_for_target(&dir, "x86", "msvc").unwrap();
+        // Neither `lib.exe` nor `llvm-dlltool` are available.
+        let mingw = get_mingw_dlltool("x86_64").unwrap();
+        let mingw = command_line(mingw.command());
+        let without_llvm = |command: &Command| command.get_program() != DLLTOOL_MSVC;
+        let command = DllToolCommand::find_for_target_with(
+            "x86_64",
+            "msvc",
+            false,
+            false,
+            true,
+            &without_llvm,
+        )
+        .unwrap();
+        assert!(matches!(command, DllToolCommand::Mingw { .. }));
+        assert_eq!(command_line(command.command()), mingw);
+
+        // The fallback is opt-in.
+        let err = DllToolCommand::find_for_target_with(
+            "x86_64",
+            "msvc",
+            false,
+            false,
+            false,
+            &without_llvm,
+        )
+        .unwrap_err();
+        match err {
+            Error::ToolNotFound { tried, env_vars } => {
+                assert_eq!(tried, [DLLTOOL_MSVC]);
+                assert_eq!(env_vars, [DLLTOOL_ENV]);
+            }
+            err => panic!("unexpected error {:?}", err),
+        }
+
+        // Nothing is available at all.
+        let err =
+            DllToolCommand::find_for_target_with("x86_64", "msvc", false, false, true, &|_| false)
+                .unwrap_err();
+        match err {
+            Error::ToolNotFound { tried, env_vars } => {
+                assert_eq!(tried, [DLLTOOL_MSVC, &mingw]);
+                assert_eq!(env_vars, [DLLTOOL_ENV, "PYO3_MINGW_DLLTOOL"]);
+            }
+            err => panic!("unexpected error {:?}", err),
+        }
+
+        let report = ImportLibraryGenerator::new("x86_64", "msvc")
+            .dlltool_command(Command::new(DLLTOOL_GNU), DllToolFlavor::Mingw)
+            .generate(&dir)
+            .unwrap();
+        assert_eq!(report.implib_path, dir.join("python3.lib"));
+        assert!(is_archive_file(&report.implib_path).unwrap());
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn tool_availability() {
+        assert!(is_available(&Command::new(DLLTOOL_MSVC)));
+        assert!(is_available(&Command::new("/bin/sh")));
+        assert!(!is_available(&Command::new(
+            "python3-dll-a-missing-dlltool"
+        )));
+        assert!(!is_available(&Command::new(
+            "/python3-dll-a/missing-dlltool"
+        )));
+
+        let err = Error::ToolNotFound {
+            tried: vec!["lib.exe".to_owned(), DLLTOOL_MSVC.to_owned()],
+            env_vars: vec![DLLTOOL_ENV.to_owned(), "ZIG_COMMAND".to_owned()],
+        };
+        assert_eq!(
+            err.to_string(),
+            "No import library tool found, tried lib.exe, llvm-dlltool; \
+             set DLLTOOL or ZIG_COMMAND to select one"
+        );
+        assert_eq!(io::Error::from(err).kind(), io::ErrorKind::NotFound);
     }
 
     #[test]
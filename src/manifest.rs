@@ -0,0 +1,284 @@
+//! Generated import library manifest writer
+//!
+//! The manifest is a JSON document listing the generated import libraries
+//! with their target and Python configurations, the tool used
+//! and the SHA-256 content hash.
+
+use std::fmt::Write as _;
+use std::fs::{create_dir_all, read, remove_file, rename, write};
+use std::path::{Path, PathBuf};
+
+use crate::{temp_file_path, GenerationReport, ImportLibraryGenerator, Result};
+
+/// The manifest file name in the output directory
+const MANIFEST_FILE: &str = "python3-dll-a-manifest.json";
+
+/// Generates the import libraries for all the generator configurations
+/// in `specs` in `out_dir` and writes the JSON manifest listing them.
+///
+/// Each manifest entry records the import library file name (relative
+/// to `out_dir`), the target `arch` and `env`, the Python implementation,
+/// version and ABI flags, the tool program used and the SHA-256 hash
+/// of the import library contents.
+///
+/// Stops on the first generator failure without writing the manifest.
+/// Returns the path of the written `python3-dll-a-manifest.json` file.
+///
+/// Requires the `manifest` crate feature.
+pub fn generate_manifest(specs: &[ImportLibraryGenerator], out_dir: &Path) -> Result<PathBuf> {
+    create_dir_all(out_dir)?;
+
+    let mut entries = Vec::with_capacity(specs.len());
+
+    for spec in specs {
+        let report = spec.generate(out_dir)?;
+        entries.push(manifest_entry(spec, &report)?);
+    }
+
+    let mut json = String::from("{\n  \"artifacts\": [");
+
+    for (i, entry) in entries.iter().enumerate() {
+        json.push_str(if i == 0 { "\n" } else { ",\n" });
+        json.push_str(entry);
+    }
+
+    json.push_str(if entries.is_empty() {
+        "]\n}\n"
+    } else {
+        "\n  ]\n}\n"
+    });
+
+    // Publish the complete manifest only, same as the import libraries.
+    let manifest_path = out_dir.join(MANIFEST_FILE);
+    let temp_path = temp_file_path(&manifest_path);
+
+    let result = write(&temp_path, json).and_then(|()| rename(&temp_path, &manifest_path));
+
+    if result.is_err() {
+        let _ = remove_file(&temp_path);
+    }
+
+    result?;
+
+    Ok(manifest_path)
+}
+
+/// Formats the manifest JSON object describing the generated import library.
+fn manifest_entry(spec: &ImportLibraryGenerator, report: &GenerationReport) -> Result<String> {
+    let contents = read(&report.implib_path)?;
+
+    let file_name = report
+        .implib_path
+        .file_name()
+        .unwrap_or_default()
+        .to_string_lossy();
+
+    let version = match spec.version {
+        Some((major, minor)) => json_string(&format!("{}.{}", major, minor)),
+        None => "null".to_owned(),
+    };
+
+    let fields = [
+        ("path", json_string(&file_name)),
+        ("arch", json_string(&spec.arch)),
+        ("env", json_string(&spec.env)),
+        (
+            "implementation",
+            json_string(&format!("{:?}", spec.implementation)),
+        ),
+        ("version", version),
+        (
+            "abiflags",
+            json_string(&spec.effective_abiflags().to_string()),
+        ),
+        ("tool", json_string(&report.tool)),
+        ("sha256", json_string(&hex(&sha256(&contents)))),
+    ];
+
+    let mut entry = String::from("    {");
+
+    for (i, (key, value)) in fields.iter().enumerate() {
+        let sep = if i == 0 { "" } else { "," };
+        let _ = write!(entry, "{}\n      \"{}\": {}", sep, key, value);
+    }
+
+    entry.push_str("\n    }");
+
+    Ok(entry)
+}
+
+/// Formats the JSON string literal.
+fn json_string(s: &str) -> String {
+    let mut literal = String::with_capacity(s.len() + 2);
+
+    literal.push('"');
+
+    for c in s.chars() {
+        match c {
+            '"' => literal.push_str("\\\""),
+            '\\' => literal.push_str("\\\\"),
+            '\n' => literal.push_str("\\n"),
+            '\r' => literal.push_str("\\r"),
+            '\t' => literal.push_str("\\t"),
+            c if c.is_control() => {
+                let _ = write!(literal, "\\u{:04x}", c as u32);
+            }
+            c => literal.push(c),
+        }
+    }
+
+    literal.push('"');
+
+    literal
+}
+
+/// Formats the bytes as a lowercase hex string.
+fn hex(bytes: &[u8]) -> String {
+    bytes.iter().fold(String::new(), |mut s, b| {
+        let _ = write!(s, "{:02x}", b);
+        s
+    })
+}
+
+/// SHA-256 round constants
+const K: [u32; 64] = [
+    0x428a2f98, 0x71374491, 0xb5c0fbcf, 0xe9b5dba5, 0x3956c25b, 0x59f111f1, 0x923f82a4, 0xab1c5ed5,
+    0xd807aa98, 0x12835b01, 0x243185be, 0x550c7dc3, 0x72be5d74, 0x80deb1fe, 0x9bdc06a7, 0xc19bf174,
+    0xe49b69c1, 0xefbe4786, 0x0fc19dc6, 0x240ca1cc, 0x2de92c6f, 0x4a7484aa, 0x5cb0a9dc, 0x76f988da,
+    0x983e5152, 0xa831c66d, 0xb00327c8, 0xbf597fc7, 0xc6e00bf3, 0xd5a79147, 0x06ca6351, 0x14292967,
+    0x27b70a85, 0x2e1b2138, 0x4d2c6dfc, 0x53380d13, 0x650a7354, 0x766a0abb, 0x81c2c92e, 0x92722c85,
+    0xa2bfe8a1, 0xa81a664b, 0xc24b8b70, 0xc76c51a3, 0xd192e819, 0xd6990624, 0xf40e3585, 0x106aa070,
+    0x19a4c116, 0x1e376c08, 0x2748774c, 0x34b0bcb5, 0x391c0cb3, 0x4ed8aa4a, 0x5b9cca4f, 0x682e6ff3,
+    0x748f82ee, 0x78a5636f, 0x84c87814, 0x8cc70208, 0x90befffa, 0xa4506ceb, 0xbef9a3f7, 0xc67178f2,
+];
+
+/// Computes the SHA-256 digest of `data`.
+fn sha256(data: &[u8]) -> [u8; 32] {
+    let mut h: [u32; 8] = [
+        0x6a09e667, 0xbb67ae85, 0x3c6ef372, 0xa54ff53a, 0x510e527f, 0x9b05688c, 0x1f83d9ab,
+        0x5be0cd19,
+    ];
+
+    // Pad the message to a multiple of the 64-byte block size
+    // with the trailing 64-bit big-endian bit length.
+    let mut message = data.to_vec();
+    message.push(0x80);
+    while message.len() % 64 != 56 {
+        message.push(0);
+    }
+    message.extend_from_slice(&(data.len() as u64 * 8).to_be_bytes());
+
+    for block in message.chunks_exact(64) {
+        let mut w = [0u32; 64];
+
+        for (i, word) in block.chunks_exact(4).enumerate() {
+            w[i] = u32::from_be_bytes([word[0], word[1], word[2], word[3]]);
+        }
+
+        for i in 16..64 {
+            let s0 = w[i - 15].rotate_right(7) ^ w[i - 15].rotate_right(18) ^ (w[i - 15] >> 3);
+            let s1 = w[i - 2].rotate_right(17) ^ w[i - 2].rotate_right(19) ^ (w[i - 2] >> 10);
+            w[i] = w[i - 16]
+                .wrapping_add(s0)
+                .wrapping_add(w[i - 7])
+                .wrapping_add(s1);
+        }
+
+        let [mut a, mut b, mut c, mut d, mut e, mut f, mut g, mut hh] = h;
+
+        for i in 0..64 {
+            let s1 = e.rotate_right(6) ^ e.rotate_right(11) ^ e.rotate_right(25);
+            let ch = (e & f) ^ (!e & g);
+            let t1 = hh
+                .wrapping_add(s1)
+                .wrapping_add(ch)
+                .wrapping_add(K[i])
+                .wrapping_add(w[i]);
+            let s0 = a.rotate_right(2) ^ a.rotate_right(13) ^ a.rotate_right(22);
+            let maj = (a & b) ^ (a & c) ^ (b & c);
+            let t2 = s0.wrapping_add(maj);
+
+            hh = g;
+            g = f;
+            f = e;
+            e = d.wrapping_add(t1);
+            d = c;
+            c = b;
+            b = a;
+            a = t1.wrapping_add(t2);
+        }
+
+        for (state, value) in h.iter_mut().zip([a, b, c, d, e, f, g, hh]) {
+            *state = state.wrapping_add(value);
+        }
+    }
+
+    let mut digest = [0; 32];
+
+    for (bytes, word) in digest.chunks_exact_mut(4).zip(h) {
+        bytes.copy_from_slice(&word.to_be_bytes());
+    }
+
+    digest
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn sha256_digest() {
+        assert_eq!(
+            hex(&sha256(b"")),
+            "e3b0c44298fc1c149afbf4c8996fb92427ae41e4649b934ca495991b7852b855"
+        );
+        assert_eq!(
+            hex(&sha256(b"abc")),
+            "ba7816bf8f01cfea414140de5dae2223b00361a396177a9cb410ff61f20015ad"
+        );
+        // Two-block message
+        assert_eq!(
+            hex(&sha256(
+                b"abcdbcdecdefdefgefghfghighijhijkijkljklmklmnlmnomnopnopq"
+            )),
+            "248d6a61d20638b8e5c026930c3e6039a33ce45964ff2167f6ecedd419db06c1"
+        );
+    }
+
+    #[test]
+    fn escape_json_string() {
+        assert_eq!(json_string("python3.lib"), "\"python3.lib\"");
+        assert_eq!(json_string("C:\\a \"b\"\n"), "\"C:\\\\a \\\"b\\\"\\n\"");
+        assert_eq!(json_string("\u{1}"), "\"\\u0001\"");
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn write_manifest() {
+        let mut dir = PathBuf::from(env!("CARGO_MANIFEST_DIR"));
+        dir.push("target");
+        dir.push("x86_64-pc-windows-msvc");
+        dir.push("python3-manifest");
+
+        let mut versioned = ImportLibraryGenerator::new("x86_64", "msvc");
+        versioned.version(Some((3, 13))).abiflags(Some("t"));
+
+        let specs = [ImportLibraryGenerator::new("x86_64", "msvc"), versioned];
+
+        let manifest_path = generate_manifest(&specs, &dir).unwrap();
+        assert_eq!(manifest_path, dir.join(MANIFEST_FILE));
+
+        let manifest = std::fs::read_to_string(&manifest_path).unwrap();
+        let implib = read(dir.join("python313t.lib")).unwrap();
+
+        assert!(manifest.contains("\"path\": \"python3.lib\""));
+        assert!(manifest.contains("\"version\": null"));
+        assert!(manifest.contains("\"path\": \"python313t.lib\""));
+        assert!(manifest.contains("\"version\": \"3.13\""));
+        assert!(manifest.contains("\"abiflags\": \"t\""));
+        assert!(manifest.contains(&format!("\"sha256\": \"{}\"", hex(&sha256(&implib)))));
+
+        let specs = [ImportLibraryGenerator::new("loongarch64", "msvc")];
+        assert!(generate_manifest(&specs, &dir).is_err());
+    }
+}
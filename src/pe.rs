@@ -0,0 +1,537 @@
+//! Minimal PE image export table reader
+//!
+//! Reads the exported names of a DLL image and converts them
+//! into the module-definition file text. Only the headers, the section
+//! table and the export directory are parsed, the rest of the image
+//! is never touched.
+
+use std::fmt::Write;
+
+use crate::def::Export;
+use crate::{Error, Result};
+
+/// `IMAGE_NT_OPTIONAL_HDR32_MAGIC`
+const PE32_MAGIC: u16 = 0x10b;
+
+/// `IMAGE_NT_OPTIONAL_HDR64_MAGIC`
+const PE32_PLUS_MAGIC: u16 = 0x20b;
+
+/// `IMAGE_SCN_MEM_EXECUTE` section characteristics flag
+const SCN_MEM_EXECUTE: u32 = 0x2000_0000;
+
+/// Export table read from a DLL image
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub(crate) struct DllExports {
+    /// The target architecture name matching the image machine type
+    pub arch: &'static str,
+    /// The DLL name recorded in the export directory, e.g. `python312.dll`
+    pub dll_name: String,
+    /// The exports with names, in the export name table order
+    pub exports: Vec<DllExport>,
+}
+
+/// Named DLL export
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub(crate) struct DllExport {
+    /// The exported symbol name
+    pub name: String,
+    /// The export ordinal number
+    pub ordinal: u16,
+    /// The export refers to data, not code
+    pub data: bool,
+    /// The forwarding target, e.g. `otherdll.PyFoo`
+    pub forwarder: Option<String>,
+}
+
+/// Image section table entry
+struct Section {
+    /// The section start address relative to the image base
+    rva: u32,
+    /// The section size in memory
+    size: u32,
+    /// The section data file offset
+    offset: u32,
+    /// The section data size in the file
+    raw_size: u32,
+    /// The section characteristics flags
+    characteristics: u32,
+}
+
+impl DllExports {
+    /// Reads the export table from the DLL image bytes.
+    ///
+    /// Exports are classified as data when their address lies
+    /// in a non-executable section, the PE export table itself
+    /// does not distinguish between the code and the data exports.
+    pub fn parse(image: &[u8]) -> Result<Self> {
+        let image = Image { bytes: image };
+
+        if image.bytes.get(..2) != Some(b"MZ") {
+            return Err(invalid("missing the MZ signature"));
+        }
+
+        let pe = image.u32(0x3c)? as usize;
+        if image.bytes.get(pe..pe + 4) != Some(b"PE\0\0") {
+            return Err(invalid("missing the PE signature"));
+        }
+
+        let coff = pe + 4;
+        let machine = image.u16(coff)?;
+        let arch = match machine {
+            0x8664 => "x86_64",
+            0x14c => "x86",
+            0xaa64 => "aarch64",
+            _ => {
+                let machine = format!("PE machine type 0x{:04x}", machine);
+                return Err(Error::UnsupportedArch(machine));
+            }
+        };
+
+        let section_count = image.u16(coff + 2)? as usize;
+        let optional = coff + 20;
+        let optional_size = image.u16(coff + 16)? as usize;
+
+        // The data directories follow the fixed optional header fields.
+        let directories = match image.u16(optional)? {
+            PE32_MAGIC => optional + 92,
+            PE32_PLUS_MAGIC => optional + 108,
+            magic => {
+                let msg = format!("unknown optional header magic 0x{:x}", magic);
+                return Err(invalid(&msg));
+            }
+        };
+
+        if image.u32(directories)? == 0 {
+            return Err(invalid("no data directories"));
+        }
+
+        let export_rva = image.u32(directories + 4)?;
+        let export_size = image.u32(directories + 8)?;
+        if export_rva == 0 || export_size == 0 {
+            return Err(invalid("no export table"));
+        }
+
+        let sections = (0..section_count)
+            .map(|i| {
+                let header = optional + optional_size + i * 40;
+                Ok(Section {
+                    size: image.u32(header + 8)?,
+                    rva: image.u32(header + 12)?,
+                    raw_size: image.u32(header + 16)?,
+                    offset: image.u32(header + 20)?,
+                    characteristics: image.u32(header + 36)?,
+                })
+            })
+            .collect::<Result<Vec<_>>>()?;
+
+        let mapped = Mapped {
+            image,
+            sections: &sections,
+        };
+
+        let directory = mapped.offset(export_rva)?;
+        let dll_name = mapped.string(image.u32(directory + 12)?)?;
+        if dll_name.contains(['/', '\\']) {
+            return Err(invalid(&format!("DLL name '{}' is a path", dll_name)));
+        }
+        let base = image.u32(directory + 16)?;
+        let function_count = image.u32(directory + 20)?;
+        let name_count = image.u32(directory + 24)? as usize;
+        let functions = mapped.offset(image.u32(directory + 28)?)?;
+        let names = mapped.offset(image.u32(directory + 32)?)?;
+        let ordinals = mapped.offset(image.u32(directory + 36)?)?;
+
+        let exports = (0..name_count)
+            .map(|i| {
+                let name = mapped.string(image.u32(names + i * 4)?)?;
+                let index = image.u16(ordinals + i * 2)?;
+
+                if u32::from(index) >= function_count {
+                    let msg = format!("export '{}' function index out of range", name);
+                    return Err(invalid(&msg));
+                }
+
+                let ordinal = base
+                    .checked_add(u32::from(index))
+                    .and_then(|ordinal| u16::try_from(ordinal).ok())
+                    .ok_or_else(|| invalid(&format!("export '{}' ordinal out of range", name)))?;
+
+                let rva = image.u32(functions + usize::from(index) * 4)?;
+
+                // The forwarder exports point to the target name string
+                // within the export table.
+                if rva >= export_rva && rva - export_rva < export_size {
+                    return Ok(DllExport {
+                        name,
+                        ordinal,
+                        data: false,
+                        forwarder: Some(mapped.string(rva)?),
+                    });
+                }
+
+                let data = match mapped.section(rva) {
+                    Some(section) => section.characteristics & SCN_MEM_EXECUTE == 0,
+                    None => {
+                        let msg = format!("export '{}' address outside of the image", name);
+                        return Err(invalid(&msg));
+                    }
+                };
+
+                Ok(DllExport {
+                    name,
+                    ordinal,
+                    data,
+                    forwarder: None,
+                })
+            })
+            .collect::<Result<_>>()?;
+
+        Ok(DllExports {
+            arch,
+            dll_name,
+            exports,
+        })
+    }
+
+    /// Formats the exports as the module-definition file text.
+    pub fn to_def(&self) -> String {
+        let mut def = format!("LIBRARY \"{}\"\nEXPORTS\n", self.dll_name);
+
+        for export in &self.exports {
+            let export = Export {
+                name: export.name.clone(),
+                internal_name: export.forwarder.clone(),
+                ordinal: Some(export.ordinal),
+                data: export.data,
+                ..Export::default()
+            };
+            let _ = writeln!(def, "{}", export);
+        }
+
+        def
+    }
+}
+
+/// Raw image bytes with the bounds-checked little-endian field access
+#[derive(Clone, Copy)]
+struct Image<'a> {
+    bytes: &'a [u8],
+}
+
+impl Image<'_> {
+    /// Reads the bytes at the file offset.
+    fn bytes<const N: usize>(&self, offset: usize) -> Result<[u8; N]> {
+        self.bytes
+            .get(offset..)
+            .and_then(|bytes| bytes.get(..N))
+            .and_then(|bytes| bytes.try_into().ok())
+            .ok_or_else(|| invalid("truncated image"))
+    }
+
+    fn u16(&self, offset: usize) -> Result<u16> {
+        self.bytes(offset).map(u16::from_le_bytes)
+    }
+
+    fn u32(&self, offset: usize) -> Result<u32> {
+        self.bytes(offset).map(u32::from_le_bytes)
+    }
+}
+
+/// Image with the section table mapping the relative virtual addresses
+struct Mapped<'a> {
+    image: Image<'a>,
+    sections: &'a [Section],
+}
+
+impl Mapped<'_> {
+    /// Finds the section containing the relative virtual address.
+    fn section(&self, rva: u32) -> Option<&Section> {
+        self.sections.iter().find(|section| {
+            let size = section.size.max(section.raw_size);
+            rva >= section.rva && rva - section.rva < size
+        })
+    }
+
+    /// Maps the relative virtual address to the file offset.
+    fn offset(&self, rva: u32) -> Result<usize> {
+        self.section(rva)
+            .filter(|section| rva - section.rva < section.raw_size)
+            .map(|section| section.offset as usize + (rva - section.rva) as usize)
+            .ok_or_else(|| invalid(&format!("address 0x{:x} is not in the file", rva)))
+    }
+
+    /// Reads the NUL-terminated ASCII string at the relative virtual address.
+    fn string(&self, rva: u32) -> Result<String> {
+        let offset = self.offset(rva)?;
+        let bytes = self
+            .image
+            .bytes
+            .get(offset..)
+            .ok_or_else(|| invalid("truncated image"))?;
+
+        let len = bytes
+            .iter()
+            .position(|&b| b == 0)
+            .ok_or_else(|| invalid("unterminated string"))?;
+
+        // The module-definition files can not quote the names
+        // with the double quotes or the control characters.
+        let name = &bytes[..len];
+        if name.is_empty()
+            || !name
+                .iter()
+                .all(|&b| (b' '..=b'~').contains(&b) && b != b'"')
+        {
+            return Err(invalid(&format!("invalid name at 0x{:x}", rva)));
+        }
+
+        Ok(String::from_utf8_lossy(name).into_owned())
+    }
+}
+
+/// Constructs the invalid DLL image error.
+fn invalid(msg: &str) -> Error {
+    Error::InvalidDll(msg.to_owned())
+}
+
+/// Builds a minimal DLL image exporting `exports` for the tests.
+///
+/// Each export is either `Ok(true)` for code, `Ok(false)` for data
+/// or `Err(target)` for a forwarder.
+#[cfg(test)]
+pub(crate) fn test_dll(
+    machine: u16,
+    dll_name: &str,
+    exports: &[(&str, std::result::Result<bool, &str>)],
+) -> Vec<u8> {
+    const TEXT_RVA: u32 = 0x1000;
+    const RDATA_RVA: u32 = 0x2000;
+    const FILE_ALIGN: usize = 0x200;
+
+    let pe32_plus = machine != 0x14c;
+    let optional_size: u16 = if pe32_plus { 240 } else { 224 };
+
+    fn put(buf: &mut Vec<u8>, offset: usize, bytes: &[u8]) {
+        if buf.len() < offset + bytes.len() {
+            buf.resize(offset + bytes.len(), 0);
+        }
+        buf[offset..offset + bytes.len()].copy_from_slice(bytes);
+    }
+
+    // The `.rdata` section contents: the export directory, the address,
+    // name and ordinal tables, the strings and finally the data symbols.
+    let count = exports.len();
+    let functions = 40;
+    let names = functions + count * 4;
+    let ordinals = names + count * 4;
+    let mut strings = ordinals + count * 2;
+
+    let mut rdata = Vec::new();
+    let mut string = |rdata: &mut Vec<u8>, s: &str| {
+        let rva = RDATA_RVA + strings as u32;
+        put(rdata, strings, s.as_bytes());
+        put(rdata, strings + s.len(), b"\0");
+        strings += s.len() + 1;
+        rva
+    };
+
+    let dll_name_rva = string(&mut rdata, dll_name);
+    for (i, (name, kind)) in exports.iter().enumerate() {
+        let name_rva = string(&mut rdata, name);
+        put(&mut rdata, names + i * 4, &name_rva.to_le_bytes());
+        put(&mut rdata, ordinals + i * 2, &(i as u16).to_le_bytes());
+
+        let rva = match kind {
+            Ok(true) => TEXT_RVA + i as u32 * 16,
+            Ok(false) => RDATA_RVA + 0x100 + i as u32 * 8,
+            Err(target) => string(&mut rdata, target),
+        };
+        put(&mut rdata, functions + i * 4, &rva.to_le_bytes());
+    }
+    let export_size = strings as u32;
+
+    let mut directory = [0; 40];
+    directory[12..16].copy_from_slice(&dll_name_rva.to_le_bytes());
+    directory[16..20].copy_from_slice(&1u32.to_le_bytes());
+    directory[20..24].copy_from_slice(&(count as u32).to_le_bytes());
+    directory[24..28].copy_from_slice(&(count as u32).to_le_bytes());
+    directory[28..32].copy_from_slice(&(RDATA_RVA + functions as u32).to_le_bytes());
+    directory[32..36].copy_from_slice(&(RDATA_RVA + names as u32).to_le_bytes());
+    directory[36..40].copy_from_slice(&(RDATA_RVA + ordinals as u32).to_le_bytes());
+    put(&mut rdata, 0, &directory);
+    rdata.resize(0x100 + count * 8, 0);
+
+    let mut image = Vec::new();
+    put(&mut image, 0, b"MZ");
+    put(&mut image, 0x3c, &0x40u32.to_le_bytes());
+    put(&mut image, 0x40, b"PE\0\0");
+
+    let coff = 0x44;
+    put(&mut image, coff, &machine.to_le_bytes());
+    put(&mut image, coff + 2, &2u16.to_le_bytes());
+    put(&mut image, coff + 16, &optional_size.to_le_bytes());
+
+    let optional = coff + 20;
+    let (magic, directories) = if pe32_plus {
+        (PE32_PLUS_MAGIC, optional + 108)
+    } else {
+        (PE32_MAGIC, optional + 92)
+    };
+    put(&mut image, optional, &magic.to_le_bytes());
+    put(&mut image, directories, &16u32.to_le_bytes());
+    put(&mut image, directories + 4, &RDATA_RVA.to_le_bytes());
+    put(&mut image, directories + 8, &export_size.to_le_bytes());
+
+    let text = vec![0xc3; 16 * count.max(1)];
+    let rdata_offset = FILE_ALIGN * 2;
+    let rdata_raw_size = rdata.len().div_ceil(FILE_ALIGN) * FILE_ALIGN;
+    let sections = [
+        (TEXT_RVA, FILE_ALIGN, FILE_ALIGN, 0x6000_0020u32),
+        (RDATA_RVA, rdata_offset, rdata_raw_size, 0x4000_0040),
+    ];
+
+    for (i, (rva, offset, raw_size, characteristics)) in sections.into_iter().enumerate() {
+        let header = optional + usize::from(optional_size) + i * 40;
+        put(&mut image, header + 8, &(raw_size as u32).to_le_bytes());
+        put(&mut image, header + 12, &rva.to_le_bytes());
+        put(&mut image, header + 16, &(raw_size as u32).to_le_bytes());
+        put(&mut image, header + 20, &(offset as u32).to_le_bytes());
+        put(&mut image, header + 36, &characteristics.to_le_bytes());
+    }
+
+    put(&mut image, FILE_ALIGN, &text);
+    put(&mut image, rdata_offset, &rdata);
+    image.resize(rdata_offset + rdata_raw_size, 0);
+
+    image
+}
+
+#[cfg(test)]
+mod tests {
+    use proptest::collection::vec;
+    use proptest::prelude::*;
+
+    use super::*;
+    use crate::def::ModuleDef;
+
+    #[test]
+    fn parse_exports() {
+        let exports = [
+            ("PyExc_TypeError", Ok(false)),
+            ("Py_IsInitialized", Ok(true)),
+            ("PyObject_Call", Err("python313.PyObject_Call")),
+        ];
+
+        for (machine, arch) in [(0x8664, "x86_64"), (0x14c, "x86"), (0xaa64, "aarch64")] {
+            let image = test_dll(machine, "python313.dll", &exports);
+            let dll = DllExports::parse(&image).unwrap();
+
+            assert_eq!(dll.arch, arch);
+            assert_eq!(dll.dll_name, "python313.dll");
+            assert_eq!(
+                dll.exports,
+                [
+                    DllExport {
+                        name: "PyExc_TypeError".to_owned(),
+                        ordinal: 1,
+                        data: true,
+                        forwarder: None,
+                    },
+                    DllExport {
+                        name: "Py_IsInitialized".to_owned(),
+                        ordinal: 2,
+                        data: false,
+                        forwarder: None,
+                    },
+                    DllExport {
+                        name: "PyObject_Call".to_owned(),
+                        ordinal: 3,
+                        data: false,
+                        forwarder: Some("python313.PyObject_Call".to_owned()),
+                    },
+                ]
+            );
+
+            assert_eq!(
+                dll.to_def(),
+                "LIBRARY \"python313.dll\"\n\
+                 EXPORTS\n\
+                 PyExc_TypeError @1 DATA\n\
+                 Py_IsInitialized @2\n\
+                 PyObject_Call=python313.PyObject_Call @3\n"
+            );
+        }
+    }
+
+    #[test]
+    fn invalid_images() {
+        let image = test_dll(0x8664, "python3.dll", &[("Py_Main", Ok(true))]);
+
+        let truncated = &image[..0x100];
+        assert!(matches!(
+            DllExports::parse(truncated),
+            Err(Error::InvalidDll(_))
+        ));
+
+        let mut no_mz = image.clone();
+        no_mz[0] = b'X';
+        assert!(matches!(
+            DllExports::parse(&no_mz),
+            Err(Error::InvalidDll(_))
+        ));
+
+        let mut arm = image.clone();
+        arm[0x44..0x46].copy_from_slice(&0x1c4u16.to_le_bytes());
+        assert!(matches!(
+            DllExports::parse(&arm),
+            Err(Error::UnsupportedArch(_))
+        ));
+
+        assert!(matches!(
+            DllExports::parse(b"not a DLL"),
+            Err(Error::InvalidDll(_))
+        ));
+    }
+
+    proptest! {
+        /// Malformed images must be rejected with an error, never a panic.
+        #[test]
+        fn parse_arbitrary(image in vec(any::<u8>(), 0..1024)) {
+            let _ = DllExports::parse(&image);
+        }
+
+        /// Corrupting any of the header or export table bytes
+        /// of a valid image must not panic either.
+        #[test]
+        fn parse_corrupted(
+            machine in prop::sample::select(&[0x8664u16, 0x14c, 0xaa64][..]),
+            corruptions in vec((0..0x600usize, any::<u8>()), 1..8),
+            truncate in prop::option::of(0..0x600usize),
+        ) {
+            let mut image = test_dll(
+                machine,
+                "python313.dll",
+                &[
+                    ("PyExc_TypeError", Ok(false)),
+                    ("Py_IsInitialized", Ok(true)),
+                    ("PyObject_Call", Err("python3.PyObject_Call")),
+                ],
+            );
+
+            for (offset, byte) in corruptions {
+                if let Some(b) = image.get_mut(offset) {
+                    *b = byte;
+                }
+            }
+            if let Some(len) = truncate {
+                image.truncate(len);
+            }
+
+            // The names read from the image must not break the definitions.
+            if let Ok(dll) = DllExports::parse(&image) {
+                let def = dll.to_def();
+                prop_assert!(ModuleDef::parse(&def).is_ok(), "{}", def);
+            }
+        }
+    }
+}
@@ -0,0 +1,110 @@
+//! Embedded module-definitions data provenance
+
+use crate::{cpython_def, AbiFlags, PythonImplementation, PYPY_DEFS, PYSTON_VERSIONS};
+
+/// Provenance table maintained alongside the embedded `.def` files
+const PROVENANCE: &str = include_str!("provenance.txt");
+
+/// Embedded module-definitions data provenance
+///
+/// Describes where the embedded symbol list for a Python implementation
+/// and version comes from, see [`def_data_provenance()`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[non_exhaustive]
+pub struct DefProvenance {
+    /// The embedded module-definitions file name, e.g. `python312.def`
+    pub def_file: &'static str,
+    /// The data source, e.g. `python312.dll` or the CPython `stable_abi.toml`
+    pub source: &'static str,
+    /// The source interpreter version or revision, e.g. `3.12.7`, if known
+    pub source_version: Option<&'static str>,
+    /// The data generation date in the `YYYY-MM-DD` format, if known
+    pub generated: Option<&'static str>,
+}
+
+/// Returns the provenance of the embedded symbol list used for
+/// the Python `implementation` and `version`.
+///
+/// The version-agnostic Stable ABI `python3.def` data is described
+/// when `version` is `None`. Returns `None` for the unsupported
+/// implementation and version combinations.
+pub fn def_data_provenance(
+    implementation: PythonImplementation,
+    version: Option<(u8, u8)>,
+) -> Option<DefProvenance> {
+    let def_file = match implementation {
+        PythonImplementation::CPython => cpython_def(version, AbiFlags::default()).ok()?.0,
+        PythonImplementation::PyPy => {
+            let &(_, def_file, _) = PYPY_DEFS.iter().find(|(v, _, _)| Some(*v) == version)?;
+            def_file
+        }
+        PythonImplementation::Pyston => match version {
+            Some(version) if PYSTON_VERSIONS.contains(&version) => {
+                cpython_def(Some(version), AbiFlags::default()).ok()?.0
+            }
+            _ => return None,
+        },
+    };
+
+    provenance_entries().find(|entry| entry.def_file == def_file)
+}
+
+/// Parses the provenance table entries.
+fn provenance_entries() -> impl Iterator<Item = DefProvenance> {
+    let known = |value: &'static str| Some(value).filter(|&value| value != "-");
+
+    PROVENANCE
+        .lines()
+        .filter(|line| !line.is_empty() && !line.starts_with('#'))
+        .filter_map(move |line| {
+            let mut columns = line.split('\t');
+
+            Some(DefProvenance {
+                def_file: columns.next()?,
+                source: columns.next()?,
+                source_version: known(columns.next()?),
+                generated: known(columns.next()?),
+            })
+        })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn provenance_table() {
+        // Every embedded module-definitions file is described.
+        let entries: Vec<_> = provenance_entries().collect();
+        assert_eq!(
+            entries.len(),
+            PROVENANCE.lines().filter(|l| !l.starts_with('#')).count()
+        );
+
+        for dir_entry in std::fs::read_dir(concat!(env!("CARGO_MANIFEST_DIR"), "/src")).unwrap() {
+            let path = dir_entry.unwrap().path();
+            if path.extension().is_some_and(|ext| ext == "def") {
+                let name = path.file_name().unwrap().to_str().unwrap();
+                assert!(
+                    entries.iter().any(|entry| entry.def_file == name),
+                    "{}",
+                    name
+                );
+            }
+        }
+
+        let stable = def_data_provenance(PythonImplementation::CPython, None).unwrap();
+        assert_eq!(stable.def_file, "python3.def");
+        assert_eq!(stable.source_version, Some("main"));
+
+        let pypy = def_data_provenance(PythonImplementation::PyPy, Some((3, 8))).unwrap();
+        assert_eq!(pypy.def_file, "libpypy3-c.def");
+        assert_eq!(pypy.source, "libpypy3-c.dll");
+
+        let pyston = def_data_provenance(PythonImplementation::Pyston, Some((3, 8))).unwrap();
+        assert_eq!(pyston.def_file, "python38.def");
+
+        assert!(def_data_provenance(PythonImplementation::CPython, Some((3, 6))).is_none());
+        assert!(def_data_provenance(PythonImplementation::Pyston, Some((3, 9))).is_none());
+    }
+}
@@ -0,0 +1,151 @@
+//! Minimal PyO3 interpreter configuration file reader
+//!
+//! Reads the `key=value` lines of the `PYO3_CONFIG_FILE` format
+//! relevant to the import library generation, all other keys are skipped.
+
+use crate::{parse_version, AbiFlags, Error, PythonImplementation, Result};
+
+/// PyO3 interpreter configuration fields relevant to the import library generator
+#[derive(Debug, Clone, Copy)]
+pub(crate) struct PyO3Config {
+    /// The Python interpreter implementation
+    pub implementation: PythonImplementation,
+    /// The Python version (the minimum version for `abi3`)
+    pub version: (u8, u8),
+    /// The Python ABI flags derived from the `build_flags`
+    pub abiflags: AbiFlags,
+    /// The Stable ABI is used
+    pub abi3: bool,
+    /// The Python library is linked dynamically
+    pub shared: bool,
+}
+
+impl PyO3Config {
+    /// Parses the PyO3 interpreter configuration file text.
+    pub fn parse(config: &str) -> Result<Self> {
+        let mut implementation = PythonImplementation::CPython;
+        let mut version = None;
+        let mut abiflags = AbiFlags::default();
+        let mut abi3 = false;
+        let mut shared = true;
+
+        for (i, line) in config.lines().enumerate() {
+            let line = line.trim();
+
+            if line.is_empty() {
+                continue;
+            }
+
+            let (key, value) = line
+                .split_once('=')
+                .ok_or_else(|| invalid(i, "expected a key=value line"))?;
+
+            match key {
+                "implementation" => {
+                    implementation = match value {
+                        "CPython" => PythonImplementation::CPython,
+                        "PyPy" => PythonImplementation::PyPy,
+                        _ => {
+                            let msg = format!("Unsupported Python implementation '{}'", value);
+                            return Err(Error::UnsupportedPython(msg));
+                        }
+                    }
+                }
+                "version" => match parse_version(value) {
+                    Ok((parsed, flags)) if flags.is_empty() => version = Some(parsed),
+                    _ => return Err(invalid(i, &format!("invalid version '{}'", value))),
+                },
+                "abi3" => {
+                    abi3 = value
+                        .parse()
+                        .map_err(|_| invalid(i, &format!("invalid abi3 value '{}'", value)))?
+                }
+                "shared" => {
+                    shared = value
+                        .parse()
+                        .map_err(|_| invalid(i, &format!("invalid shared value '{}'", value)))?
+                }
+                "build_flags" => {
+                    for flag in value.split(',') {
+                        match flag {
+                            "Py_GIL_DISABLED" => abiflags.free_threaded = true,
+                            "Py_DEBUG" => abiflags.debug = true,
+                            _ => {}
+                        }
+                    }
+                }
+                _ => {}
+            }
+        }
+
+        Ok(PyO3Config {
+            implementation,
+            version: version
+                .ok_or_else(|| Error::InvalidPyO3Config("missing version".to_owned()))?,
+            abiflags,
+            abi3,
+            shared,
+        })
+    }
+}
+
+/// Constructs the invalid PyO3 configuration error.
+fn invalid(index: usize, msg: &str) -> Error {
+    Error::InvalidPyO3Config(format!("line {}: {}", index + 1, msg))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parse() {
+        let config = PyO3Config::parse(
+            "implementation=CPython\n\
+             version=3.13\n\
+             shared=true\n\
+             abi3=false\n\
+             lib_name=python313t\n\
+             lib_dir=C:\\Python313\\libs\n\
+             pointer_width=64\n\
+             build_flags=Py_GIL_DISABLED\n\
+             suppress_build_script_link_lines=false\n",
+        )
+        .unwrap();
+        assert!(matches!(
+            config.implementation,
+            PythonImplementation::CPython
+        ));
+        assert_eq!(config.version, (3, 13));
+        assert!(config.abiflags.free_threaded && !config.abiflags.debug);
+        assert!(!config.abi3);
+        assert!(config.shared);
+
+        let config =
+            PyO3Config::parse("implementation=PyPy\nversion=3.10\nbuild_flags=\n").unwrap();
+        assert!(matches!(config.implementation, PythonImplementation::PyPy));
+        assert!(config.abiflags.is_empty());
+
+        let config = PyO3Config::parse("version=3.8\nabi3=true\n").unwrap();
+        assert!(config.abi3);
+        assert!(config.shared);
+
+        let config = PyO3Config::parse("version=3.12\nshared=false\n").unwrap();
+        assert!(!config.shared);
+
+        for config in [
+            "",
+            "abi3=true",
+            "version",
+            "version=3.13t",
+            "version=3.8\nabi3=yes",
+            "version=3.8\nshared=no",
+        ] {
+            let err = PyO3Config::parse(config).unwrap_err();
+            assert!(matches!(err, Error::InvalidPyO3Config(_)), "{}", config);
+        }
+
+        let err = PyO3Config::parse("implementation=GraalPy\nversion=3.11\n").unwrap_err();
+        assert!(matches!(err, Error::UnsupportedPython(_)));
+    }
+}
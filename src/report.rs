@@ -0,0 +1,230 @@
+//! Import library generation report
+
+use std::env;
+use std::ffi::OsString;
+use std::fmt;
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+
+use crate::{Error, ImportLibraryGenerator, Result};
+
+/// Build script environment variables used as the relative path bases
+const PATH_BASE_VARS: [&str; 2] = ["OUT_DIR", "CARGO_MANIFEST_DIR"];
+
+/// Import library generation result
+///
+/// Describes the files produced by [`ImportLibraryGenerator::generate()`].
+///
+/// [`ImportLibraryGenerator::generate()`]: crate::ImportLibraryGenerator::generate
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[non_exhaustive]
+pub struct GenerationReport {
+    /// The generated import library file path
+    pub implib_path: PathBuf,
+    /// The intermediate module-definition file path
+    pub def_path: PathBuf,
+    /// The generated `.exp` exports file path (MSVC `lib.exe` only)
+    pub export_path: Option<PathBuf>,
+    /// The import library tool program, e.g. `llvm-dlltool`
+    pub tool: String,
+    /// The import library tool command line, the program followed
+    /// by its arguments, see [`ImportLibraryGenerator::tool_argv()`]
+    ///
+    /// The arguments reference `def_path` and `implib_path`, `generate()`
+    /// runs the same command on the temporary copies of these files
+    /// before moving them in place.
+    pub tool_argv: Vec<OsString>,
+    /// The library name to link against, e.g. `python39`,
+    /// see [`ImportLibraryGenerator::link_lib_name()`]
+    pub link_lib_name: String,
+    /// The wall-clock time spent writing the module-definitions file
+    pub def_write_time: Duration,
+    /// The wall-clock time spent running the import library tool,
+    /// excluding the `append_to()` merge
+    ///
+    /// [`append_to()`]: crate::ImportLibraryGenerator::append_to
+    pub tool_time: Duration,
+}
+
+/// Batch import library generation result
+///
+/// Collects the reports of the successfully generated import libraries
+/// and the errors of the failed ones, see [`generate_all()`].
+///
+/// [`generate_all()`]: crate::generate_all
+#[derive(Debug, Default)]
+#[non_exhaustive]
+pub struct BatchReport {
+    /// Successfully generated import libraries
+    pub generated: Vec<GenerationReport>,
+    /// Failed generator configurations with their errors
+    pub failed: Vec<(ImportLibraryGenerator, Error)>,
+}
+
+impl BatchReport {
+    /// Returns `true` if all the import libraries have been generated.
+    pub fn is_success(&self) -> bool {
+        self.failed.is_empty()
+    }
+
+    /// Converts the report into the generated import library reports,
+    /// or the [`Error::BatchFailed`] error listing all the failures
+    /// if any of the configurations have failed.
+    pub fn into_result(self) -> Result<Vec<GenerationReport>> {
+        if self.failed.is_empty() {
+            Ok(self.generated)
+        } else {
+            Err(Error::BatchFailed {
+                failed: self.failed,
+            })
+        }
+    }
+}
+
+impl GenerationReport {
+    /// Emits the `cargo:rustc-link-search` instruction for the import library
+    /// directory and the `cargo:rustc-link-lib` instruction for the import
+    /// library itself from a build script.
+    ///
+    /// The link search path is always absolute, even when the report
+    /// is displayed using [`GenerationReport::display_relative()`].
+    pub fn emit_cargo(&self) {
+        for instruction in self.cargo_instructions() {
+            println!("{}", instruction);
+        }
+    }
+
+    /// Lists the build script instructions printed by `emit_cargo()`.
+    fn cargo_instructions(&self) -> Vec<String> {
+        let mut instructions = Vec::new();
+
+        if let Some(dir) = self.implib_path.parent() {
+            instructions.push(format!("cargo:rustc-link-search=native={}", dir.display()));
+        }
+
+        instructions.push(format!("cargo:rustc-link-lib={}", self.link_lib_name));
+
+        instructions
+    }
+
+    /// Displays the report with the file paths relative to the `OUT_DIR`
+    /// or the `CARGO_MANIFEST_DIR` build script directories when possible.
+    ///
+    /// This keeps the absolute build paths out of reproducible build logs.
+    /// The relative paths are prefixed with the directory variable name,
+    /// e.g. `$OUT_DIR/python3.lib`.
+    pub fn display_relative(&self) -> impl fmt::Display + '_ {
+        Display {
+            report: self,
+            relative: true,
+        }
+    }
+}
+
+impl fmt::Display for GenerationReport {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        Display {
+            report: self,
+            relative: false,
+        }
+        .fmt(f)
+    }
+}
+
+/// Generation report display adapter
+struct Display<'a> {
+    report: &'a GenerationReport,
+    relative: bool,
+}
+
+impl Display<'_> {
+    /// Formats the path, optionally relative to the build script directories.
+    fn path(&self, path: &Path) -> PathBuf {
+        if self.relative {
+            relative_path(path, &PATH_BASE_VARS)
+        } else {
+            path.to_owned()
+        }
+    }
+}
+
+impl fmt::Display for Display<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let report = self.report;
+
+        write!(
+            f,
+            "generated {} from {}",
+            self.path(&report.implib_path).display(),
+            self.path(&report.def_path).display()
+        )?;
+
+        if let Some(export_path) = &report.export_path {
+            write!(f, ", exports in {}", self.path(export_path).display())?;
+        }
+
+        Ok(())
+    }
+}
+
+/// Strips the first matching directory prefix found in the environment
+/// variables `vars` from `path`, replacing it with the variable name.
+fn relative_path(path: &Path, vars: &[&str]) -> PathBuf {
+    for var in vars {
+        if let Some(base) = env::var_os(var) {
+            if let Ok(relative) = path.strip_prefix(base) {
+                return Path::new("$")
+                    .with_file_name(format!("${}", var))
+                    .join(relative);
+            }
+        }
+    }
+
+    path.to_owned()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn display_relative() {
+        let mut dir = PathBuf::from(env!("CARGO_MANIFEST_DIR"));
+        dir.push("target");
+
+        let report = GenerationReport {
+            implib_path: dir.join("python3.lib"),
+            def_path: dir.join("python3.def"),
+            export_path: None,
+            tool: "llvm-dlltool".to_owned(),
+            tool_argv: Vec::new(),
+            link_lib_name: "python3".to_owned(),
+            def_write_time: Duration::ZERO,
+            tool_time: Duration::ZERO,
+        };
+
+        let expected = format!(
+            "generated {} from {}",
+            dir.join("python3.lib").display(),
+            dir.join("python3.def").display()
+        );
+        assert_eq!(report.to_string(), expected);
+
+        // `cargo test` sets `CARGO_MANIFEST_DIR` but not `OUT_DIR`.
+        let relative_dir = Path::new("$CARGO_MANIFEST_DIR").join("target");
+        let expected = format!(
+            "generated {} from {}",
+            relative_dir.join("python3.lib").display(),
+            relative_dir.join("python3.def").display()
+        );
+        assert_eq!(report.display_relative().to_string(), expected);
+
+        assert_eq!(
+            report.cargo_instructions(),
+            [
+                format!("cargo:rustc-link-search=native={}", dir.display()),
+                "cargo:rustc-link-lib=python3".to_owned(),
+            ]
+        );
+    }
+}
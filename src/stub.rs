@@ -0,0 +1,261 @@
+//! Stub Python DLL generator for the linkage tests
+//!
+//! Builds a tiny DLL exporting no-op functions and zero-filled data
+//! for all the symbols listed in the module-definitions file,
+//! which lets the downstream test suites link and load
+//! the Python extension modules without a real Python installation.
+
+use std::collections::HashSet;
+use std::fmt::Write as _;
+use std::fs::{copy, read, remove_file, rename, write};
+use std::path::{Path, PathBuf};
+use std::process::Command;
+
+use crate::def::ModuleDef;
+use crate::temp::TempPath;
+use crate::{
+    command_line, find_program, prepare_out_dir, temp_file_path, Error, ImportLibraryGenerator,
+    Result,
+};
+
+/// The stub C source file name in the work directory
+const STUB_SOURCE: &str = "python3-dll-a-stub.c";
+
+/// The stub data export size in bytes, large enough for the Python singletons
+const STUB_DATA_SIZE: usize = 256;
+
+/// Generates the stub Python DLL matching the `generator` configuration
+/// in `out_dir`.
+///
+/// The stub DLL exports the same symbols as the import library
+/// generated by [`ImportLibraryGenerator::generate()`]: the functions
+/// do nothing and the data is zero-filled. It is only useful for testing
+/// that the Python extension modules link and load.
+///
+/// The target C compiler is selected based on the target `env`:
+///
+/// - MinGW-w64 `gcc` for `gnu`, e.g. `x86_64-w64-mingw32-gcc`,
+///   or the unprefixed `gcc` on Windows hosts,
+/// - LLVM MinGW-w64 `clang` for `gnullvm`, e.g. `x86_64-w64-mingw32-clang`,
+/// - Visual Studio `cl.exe` on Windows hosts for `msvc`
+///   (with the `lib-exe` crate feature),
+///   `clang` with `lld` otherwise.
+///
+/// Returns the path of the generated DLL, e.g. `python3.dll`.
+///
+/// Requires the `stub-dll` crate feature.
+pub fn generate_stub_dll(generator: &ImportLibraryGenerator, out_dir: &Path) -> Result<PathBuf> {
+    prepare_out_dir(out_dir, generator.out_dir_mode)?;
+
+    let (def_file, def_file_content) = generator.def_file()?;
+    let def = ModuleDef::parse(&def_file_content)?;
+
+    let dll_name = match def.library() {
+        Some(dll_name) => dll_name.to_owned(),
+        None => Path::new(&def_file)
+            .with_extension("dll")
+            .display()
+            .to_string(),
+    };
+
+    // The compilers write the intermediate files next to the DLL,
+    // including the import library clashing with the generated one.
+    let work_dir = TempPath::create()?;
+    write(work_dir.join(&def_file), &def_file_content)?;
+    write(work_dir.join(STUB_SOURCE), stub_source(&def)?)?;
+
+    let mut command = stub_compiler(&generator.arch, &generator.env, &def_file, &dll_name)?;
+    command.current_dir(&*work_dir);
+
+    let status = command.status().map_err(|source| Error::ToolSpawn {
+        command: command_line(&command),
+        source,
+    })?;
+
+    if !status.success() {
+        return Err(Error::ToolFailed {
+            command: command_line(&command),
+            reason: status.to_string(),
+        });
+    }
+
+    let stub_path = work_dir.join(&dll_name);
+
+    if !read(&stub_path).is_ok_and(|dll| dll.starts_with(b"MZ")) {
+        return Err(Error::ToolFailed {
+            command: command_line(&command),
+            reason: format!("no valid DLL in {}", stub_path.display()),
+        });
+    }
+
+    // Publish the complete DLL only, same as the import libraries.
+    let dll_path = out_dir.join(&dll_name);
+    let temp_path = temp_file_path(&dll_path);
+
+    let result = copy(&stub_path, &temp_path).and_then(|_| rename(&temp_path, &dll_path));
+
+    if result.is_err() {
+        let _ = remove_file(&temp_path);
+    }
+
+    result?;
+
+    Ok(dll_path)
+}
+
+/// Generates the stub C source defining all the exported symbols.
+fn stub_source(def: &ModuleDef) -> Result<String> {
+    let mut source = String::from("/* Generated by python3-dll-a, do not edit. */\n");
+    let mut defined = HashSet::new();
+
+    for export in def.exports() {
+        let symbol = export.internal_name.as_deref().unwrap_or(&export.name);
+
+        // The aliases share the internal symbol definition.
+        if !defined.insert(symbol) {
+            continue;
+        }
+
+        let is_identifier = symbol.starts_with(|c: char| c.is_ascii_alphabetic() || c == '_')
+            && symbol
+                .chars()
+                .all(|c| c.is_ascii_alphanumeric() || c == '_');
+
+        if !is_identifier {
+            let msg = format!("can not define the stub symbol '{}' in C", symbol);
+            return Err(Error::InvalidDef(msg));
+        }
+
+        if export.data || export.constant {
+            let _ = writeln!(source, "char {}[{}];", symbol, STUB_DATA_SIZE);
+        } else {
+            let _ = writeln!(source, "void {}(void) {{}}", symbol);
+        }
+    }
+
+    Ok(source)
+}
+
+/// Builds the target C compiler command linking the stub DLL.
+fn stub_compiler(arch: &str, env: &str, def_file: &str, dll_name: &str) -> Result<Command> {
+    let (mingw_triple, msvc_triple) = match arch {
+        "x86_64" => ("x86_64-w64-mingw32", "x86_64-pc-windows-msvc"),
+        "x86" => ("i686-w64-mingw32", "i686-pc-windows-msvc"),
+        "aarch64" => ("aarch64-w64-mingw32", "aarch64-pc-windows-msvc"),
+        _ => return Err(Error::UnsupportedArch(arch.to_owned())),
+    };
+
+    match env {
+        "gnu" | "gnullvm" => {
+            let compiler = if env == "gnu" { "gcc" } else { "clang" };
+            let prefixed = format!("{}-{}", mingw_triple, compiler);
+
+            // Native MSYS2 shells only provide the unprefixed compilers.
+            let mut command = if cfg!(windows) && find_program(&prefixed).is_none() {
+                Command::new(compiler)
+            } else {
+                Command::new(prefixed)
+            };
+
+            // MinGW-w64 linkers take the module-definitions file as an input.
+            command
+                .arg("-shared")
+                .arg("-o")
+                .arg(dll_name)
+                .arg(STUB_SOURCE)
+                .arg(def_file);
+
+            Ok(command)
+        }
+        "msvc" => {
+            if let Some(mut command) = find_cl_exe(msvc_triple) {
+                command
+                    .args(["/nologo", "/LD", STUB_SOURCE])
+                    .arg(format!("/Fe{}", dll_name))
+                    .arg("/link")
+                    .arg(format!("/DEF:{}", def_file))
+                    .arg("/NOENTRY");
+
+                return Ok(command);
+            }
+
+            // There is no Windows SDK to link against on non-Windows hosts.
+            let mut command = Command::new("clang");
+            command
+                .arg(format!("--target={}", msvc_triple))
+                .args(["-fuse-ld=lld", "-shared", "-nostdlib"])
+                .arg("-Wl,/NOENTRY")
+                .arg(format!("-Wl,/DEF:{}", def_file))
+                .arg("-o")
+                .arg(dll_name)
+                .arg(STUB_SOURCE);
+
+            Ok(command)
+        }
+        _ => Err(Error::UnsupportedEnv(env.to_owned())),
+    }
+}
+
+/// Finds Visual Studio `cl.exe` when running on Windows
+/// with the `lib-exe` crate feature enabled.
+#[cfg(all(windows, feature = "lib-exe"))]
+fn find_cl_exe(target: &str) -> Option<Command> {
+    cc::windows_registry::find(target, "cl.exe")
+}
+
+#[cfg(not(all(windows, feature = "lib-exe")))]
+fn find_cl_exe(_target: &str) -> Option<Command> {
+    None
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn stub_source_symbols() {
+        let def = ModuleDef::parse(
+            "LIBRARY python3.dll\n\
+             EXPORTS\n\
+             Py_Initialize\n\
+             Py_None DATA\n\
+             PyAlias=Py_Initialize @1\n",
+        )
+        .unwrap();
+
+        assert_eq!(
+            stub_source(&def).unwrap(),
+            "/* Generated by python3-dll-a, do not edit. */\n\
+             void Py_Initialize(void) {}\n\
+             char Py_None[256];\n"
+        );
+
+        let def = ModuleDef::parse("EXPORTS\n\"Not C\"\n").unwrap();
+        assert!(matches!(stub_source(&def), Err(Error::InvalidDef(_))));
+    }
+
+    #[test]
+    fn stub_compiler_command() {
+        let command = stub_compiler("x86_64", "gnu", "python3.def", "python3.dll").unwrap();
+        assert_eq!(command.get_program(), "x86_64-w64-mingw32-gcc");
+        assert_eq!(
+            command.get_args().collect::<Vec<_>>(),
+            ["-shared", "-o", "python3.dll", STUB_SOURCE, "python3.def"]
+        );
+
+        let command = stub_compiler("aarch64", "gnullvm", "python3.def", "python3.dll").unwrap();
+        assert_eq!(command.get_program(), "aarch64-w64-mingw32-clang");
+
+        #[cfg(unix)]
+        {
+            let command = stub_compiler("x86", "msvc", "python3.def", "python3.dll").unwrap();
+            assert_eq!(command.get_program(), "clang");
+            assert!(command
+                .get_args()
+                .any(|arg| arg == "--target=i686-pc-windows-msvc"));
+        }
+
+        assert!(stub_compiler("riscv64", "gnu", "python3.def", "python3.dll").is_err());
+        assert!(stub_compiler("x86_64", "musl", "python3.def", "python3.dll").is_err());
+    }
+}
@@ -0,0 +1,278 @@
+//! Minimal rustc JSON target specification reader
+//!
+//! Only the top-level string fields such as `arch`, `os` and `env`
+//! are extracted, all other values are validated and skipped.
+
+use std::collections::HashMap;
+use std::iter::Peekable;
+use std::str::Chars;
+
+use crate::{Error, Result};
+
+/// Target specification fields relevant to the import library generator
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub(crate) struct TargetSpec {
+    /// The target architecture name (as in `CARGO_CFG_TARGET_ARCH`)
+    pub arch: String,
+    /// The target environment ABI name (as in `CARGO_CFG_TARGET_ENV`)
+    pub env: String,
+}
+
+impl TargetSpec {
+    /// Parses the rustc JSON target specification text.
+    pub fn parse(json: &str) -> Result<Self> {
+        let mut fields = Parser::new(json).top_level_strings()?;
+
+        let arch = fields
+            .remove("arch")
+            .ok_or_else(|| invalid("missing \"arch\" field"))?;
+
+        // Windows is the only target OS for the Python DLL import libraries,
+        // Cygwin is treated as a separate Windows environment ABI.
+        let cygwin = match fields.get("os").map(String::as_str) {
+            Some("windows") => false,
+            Some("cygwin") => true,
+            Some(os) => return Err(invalid(&format!("unsupported target os '{}'", os))),
+            None => return Err(invalid("missing \"os\" field")),
+        };
+
+        // An unset `env` field means an empty `CARGO_CFG_TARGET_ENV` value.
+        let env = if cygwin {
+            "cygwin".to_owned()
+        } else {
+            fields.remove("env").unwrap_or_default()
+        };
+
+        Ok(TargetSpec { arch, env })
+    }
+}
+
+/// Constructs the invalid target specification error.
+fn invalid(msg: &str) -> Error {
+    Error::InvalidTargetSpec(msg.to_owned())
+}
+
+/// Minimal JSON parser
+struct Parser<'a> {
+    chars: Peekable<Chars<'a>>,
+}
+
+impl<'a> Parser<'a> {
+    fn new(json: &'a str) -> Self {
+        Parser {
+            chars: json.chars().peekable(),
+        }
+    }
+
+    /// Collects the string-valued fields of the top-level JSON object.
+    fn top_level_strings(&mut self) -> Result<HashMap<String, String>> {
+        let mut fields = HashMap::new();
+
+        self.expect('{')?;
+
+        if self.peek() == Some('}') {
+            self.chars.next();
+        } else {
+            loop {
+                let key = self.string()?;
+                self.expect(':')?;
+
+                if self.peek() == Some('"') {
+                    let value = self.string()?;
+                    fields.insert(key, value);
+                } else {
+                    self.skip_value()?;
+                }
+
+                match self.next() {
+                    Some(',') => continue,
+                    Some('}') => break,
+                    _ => return Err(invalid("expected ',' or '}'")),
+                }
+            }
+        }
+
+        if self.peek().is_some() {
+            return Err(invalid("trailing characters"));
+        }
+
+        Ok(fields)
+    }
+
+    /// Skips over any JSON value.
+    fn skip_value(&mut self) -> Result<()> {
+        match self.peek() {
+            Some('"') => self.string().map(drop),
+            Some(open @ ('{' | '[')) => {
+                let close = if open == '{' { '}' } else { ']' };
+                self.chars.next();
+
+                if self.peek() == Some(close) {
+                    self.chars.next();
+                    return Ok(());
+                }
+
+                loop {
+                    if open == '{' {
+                        self.string()?;
+                        self.expect(':')?;
+                    }
+
+                    self.skip_value()?;
+
+                    match self.next() {
+                        Some(',') => continue,
+                        Some(c) if c == close => return Ok(()),
+                        _ => return Err(invalid("unterminated object or array")),
+                    }
+                }
+            }
+            // Numbers, `true`, `false` and `null`
+            Some(c) if c == '-' || c.is_ascii_alphanumeric() => {
+                while let Some(&c) = self.chars.peek() {
+                    if c.is_ascii_alphanumeric() || "+-.".contains(c) {
+                        self.chars.next();
+                    } else {
+                        break;
+                    }
+                }
+                Ok(())
+            }
+            _ => Err(invalid("expected a value")),
+        }
+    }
+
+    /// Parses a JSON string literal.
+    fn string(&mut self) -> Result<String> {
+        self.expect('"')?;
+
+        let mut s = String::new();
+
+        loop {
+            match self.chars.next() {
+                Some('"') => return Ok(s),
+                Some('\\') => {
+                    let c = match self.chars.next() {
+                        Some('"') => '"',
+                        Some('\\') => '\\',
+                        Some('/') => '/',
+                        Some('b') => '\u{8}',
+                        Some('f') => '\u{c}',
+                        Some('n') => '\n',
+                        Some('r') => '\r',
+                        Some('t') => '\t',
+                        Some('u') => self.unicode_escape()?,
+                        _ => return Err(invalid("invalid string escape")),
+                    };
+                    s.push(c);
+                }
+                Some(c) => s.push(c),
+                None => return Err(invalid("unterminated string")),
+            }
+        }
+    }
+
+    /// Parses the `\uXXXX` escape sequence, including UTF-16 surrogate pairs.
+    fn unicode_escape(&mut self) -> Result<char> {
+        let high = self.hex4()?;
+
+        let units = if (0xD800..0xDC00).contains(&high) {
+            if self.chars.next() != Some('\\') || self.chars.next() != Some('u') {
+                return Err(invalid("unpaired surrogate"));
+            }
+            vec![high, self.hex4()?]
+        } else {
+            vec![high]
+        };
+
+        char::decode_utf16(units)
+            .next()
+            .and_then(|c| c.ok())
+            .ok_or_else(|| invalid("invalid unicode escape"))
+    }
+
+    /// Parses four hex digits.
+    fn hex4(&mut self) -> Result<u16> {
+        let mut value = 0;
+
+        for _ in 0..4 {
+            let digit = self
+                .chars
+                .next()
+                .and_then(|c| c.to_digit(16))
+                .ok_or_else(|| invalid("invalid unicode escape"))?;
+            value = value * 16 + digit as u16;
+        }
+
+        Ok(value)
+    }
+
+    /// Consumes the next non-whitespace character, which must be `expected`.
+    fn expect(&mut self, expected: char) -> Result<()> {
+        if self.next() == Some(expected) {
+            Ok(())
+        } else {
+            Err(invalid(&format!("expected '{}'", expected)))
+        }
+    }
+
+    /// Returns the next non-whitespace character.
+    fn next(&mut self) -> Option<char> {
+        self.skip_whitespace();
+        self.chars.next()
+    }
+
+    /// Peeks at the next non-whitespace character.
+    fn peek(&mut self) -> Option<char> {
+        self.skip_whitespace();
+        self.chars.peek().copied()
+    }
+
+    fn skip_whitespace(&mut self) {
+        while self.chars.next_if(|c| c.is_whitespace()).is_some() {}
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parse() {
+        let json = r#"{
+            "llvm-target": "aarch64-pc-windows-msvc",
+            "arch": "aarch64",
+            "data-layout": "e-m:w-p:64:64-i32:64-i64:64-i128:128-n32:64-S128",
+            "max-atomic-width": 128,
+            "is-like-windows": true,
+            "pre-link-args": { "msvc": ["/NOLOGO"], "msvc-lld": [] },
+            "metadata": { "description": "Custom \"ARM64\" Windows é😀" },
+            "os": "windows",
+            "env": "msvc"
+        }"#;
+
+        let spec = TargetSpec::parse(json).unwrap();
+        assert_eq!(spec.arch, "aarch64");
+        assert_eq!(spec.env, "msvc");
+
+        let spec = TargetSpec::parse(r#"{"arch": "x86", "os": "windows"}"#).unwrap();
+        assert_eq!(spec.env, "");
+
+        let spec = TargetSpec::parse(r#"{"arch": "x86_64", "os": "cygwin"}"#).unwrap();
+        assert_eq!(spec.env, "cygwin");
+
+        for json in [
+            "",
+            "{",
+            "[]",
+            r#"{"arch": "x86_64"}"#,
+            r#"{"arch": "x86_64", "os": "linux"}"#,
+            r#"{"arch": "x86_64", "os": "windows",}"#,
+            r#"{"arch": "x86_64", "os": "windows"} x"#,
+            r#"{"arch": "\ud83d", "os": "windows"}"#,
+            r#"{"os": "windows", "x": [1, 2}"#,
+        ] {
+            assert!(TargetSpec::parse(json).is_err(), "{}", json);
+        }
+    }
+}
@@ -0,0 +1,73 @@
+//! Self-removing temporary output directory
+
+use std::env;
+use std::fs::{create_dir, remove_dir_all};
+use std::io;
+use std::mem;
+use std::ops::Deref;
+use std::path::{Path, PathBuf};
+
+use crate::temp_file_path;
+
+/// Temporary import library output directory path
+///
+/// Returned by [`ImportLibraryGenerator::generate_temp()`].
+/// The directory and the generated files in it are removed
+/// when the value is dropped, unless [`TempPath::keep()`] is called.
+///
+/// [`ImportLibraryGenerator::generate_temp()`]: crate::ImportLibraryGenerator::generate_temp
+#[derive(Debug)]
+pub struct TempPath {
+    path: PathBuf,
+}
+
+impl TempPath {
+    /// Creates a new uniquely named directory in the system temporary directory.
+    pub(crate) fn create() -> io::Result<Self> {
+        let base = env::temp_dir().join("python3-dll-a");
+
+        loop {
+            let path = temp_file_path(&base);
+
+            match create_dir(&path) {
+                Ok(()) => return Ok(TempPath { path }),
+                Err(e) if e.kind() == io::ErrorKind::AlreadyExists => continue,
+                Err(e) => return Err(e),
+            }
+        }
+    }
+
+    /// Removes the temporary directory with its contents now,
+    /// reporting the removal errors.
+    pub fn close(mut self) -> io::Result<()> {
+        remove_dir_all(mem::take(&mut self.path))
+    }
+
+    /// Persists the temporary directory, returning its path.
+    pub fn keep(mut self) -> PathBuf {
+        mem::take(&mut self.path)
+    }
+}
+
+impl Deref for TempPath {
+    type Target = Path;
+
+    fn deref(&self) -> &Path {
+        &self.path
+    }
+}
+
+impl AsRef<Path> for TempPath {
+    fn as_ref(&self) -> &Path {
+        &self.path
+    }
+}
+
+impl Drop for TempPath {
+    fn drop(&mut self) {
+        // Already closed or persisted.
+        if !self.path.as_os_str().is_empty() {
+            let _ = remove_dir_all(&self.path);
+        }
+    }
+}
@@ -0,0 +1,387 @@
+//! Round-trip linkage tests
+//!
+//! Links a tiny DLL referencing a couple of Python API functions and data
+//! against each generated import library using the LLVM `lld` linker,
+//! which catches the symbol decoration and the `DATA` export bugs
+//! not detected by the import library tools themselves.
+//!
+//! The probe object file is assembled with `llvm-mc`, the tests are skipped
+//! when either `llvm-mc` or `lld` are not available.
+
+use std::env;
+use std::ffi::OsStr;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::process::Command;
+
+use python3_dll_a::{DllToolFlavor, ImportLibraryGenerator};
+
+/// The probe DLL entry point, also referencing the imported symbols
+const PROBE_SYMBOL: &str = "probe";
+
+/// Python API functions called through the import thunks
+const PROBE_FUNCTIONS: [&str; 2] = ["Py_IsInitialized", "_Py_Dealloc"];
+
+/// Python API data accessed through the `__imp_` pointers
+const PROBE_DATA: [&str; 2] = ["PyExc_TypeError", "_Py_NoneStruct"];
+
+/// Returns the C symbol name decorated for the target architecture.
+fn decorate(arch: &str, name: &str) -> String {
+    if arch == "x86" {
+        format!("_{}", name)
+    } else {
+        name.to_owned()
+    }
+}
+
+/// Generates the probe DLL entry point assembly source.
+fn entry_source(arch: &str) -> String {
+    let entry = decorate(arch, PROBE_SYMBOL);
+    format!("\t.text\n\t.globl\t{}\n{}:\n", entry, entry)
+}
+
+/// Generates the probe DLL assembly source for the target architecture.
+fn probe_source(arch: &str) -> String {
+    let mut source = entry_source(arch);
+
+    for data in PROBE_DATA {
+        let imp = format!("__imp_{}", decorate(arch, data));
+        source += &match arch {
+            "x86_64" => format!("\tmovq\t{}(%rip), %rax\n", imp),
+            "x86" => format!("\tmovl\t{}, %eax\n", imp),
+            "aarch64" => format!("\tadrp\tx0, {}\n\tldr\tx0, [x0, :lo12:{}]\n", imp, imp),
+            _ => unreachable!("unexpected probe arch {}", arch),
+        };
+    }
+
+    for function in PROBE_FUNCTIONS {
+        let function = decorate(arch, function);
+        source += &match arch {
+            "x86_64" | "x86" => format!("\tcall\t{}\n", function),
+            "aarch64" => format!("\tbl\t{}\n", function),
+            _ => unreachable!("unexpected probe arch {}", arch),
+        };
+    }
+
+    source += "\tret\n";
+    source
+}
+
+/// Generates the assembly source calling the data symbol `data` as a function.
+///
+/// The `DATA` exports have no import thunks, so linking it must fail.
+fn data_thunk_source(arch: &str, data: &str) -> String {
+    let mut source = entry_source(arch);
+
+    let data = decorate(arch, data);
+    source += &match arch {
+        "x86_64" | "x86" => format!("\tjmp\t{}\n", data),
+        "aarch64" => format!("\tb\t{}\n", data),
+        _ => unreachable!("unexpected probe arch {}", arch),
+    };
+
+    source
+}
+
+/// Finds the LLVM `lld` linker, either `lld-link` in `PATH`
+/// or the `rust-lld` program shipped with the Rust toolchain.
+///
+/// Returns the linker command configured for the `link.exe` flavor.
+fn find_lld() -> Option<Command> {
+    if Command::new("lld-link").arg("--version").output().is_ok() {
+        return Some(Command::new("lld-link"));
+    }
+
+    let rustc = env::var_os("RUSTC").unwrap_or_else(|| "rustc".into());
+
+    let output = Command::new(&rustc)
+        .args(["--print", "sysroot"])
+        .output()
+        .ok()?;
+    let sysroot = PathBuf::from(String::from_utf8(output.stdout).ok()?.trim());
+
+    let output = Command::new(&rustc).arg("-vV").output().ok()?;
+    let version = String::from_utf8(output.stdout).ok()?;
+    let host = version
+        .lines()
+        .find_map(|line| line.strip_prefix("host: "))?;
+
+    let mut lld = sysroot.join("lib").join("rustlib").join(host).join("bin");
+    lld.push(format!("rust-lld{}", env::consts::EXE_SUFFIX));
+
+    if !lld.is_file() {
+        return None;
+    }
+
+    let mut command = Command::new(lld);
+    command.args(["-flavor", "link"]);
+    Some(command)
+}
+
+/// Runs the command, returning its combined output if it fails.
+fn try_run(command: &mut Command) -> Result<(), String> {
+    let output = command
+        .output()
+        .unwrap_or_else(|e| panic!("{:?} failed with {}", command, e));
+
+    if output.status.success() {
+        Ok(())
+    } else {
+        Err(format!(
+            "{:?} failed with {}:\n{}{}",
+            command,
+            output.status,
+            String::from_utf8_lossy(&output.stdout),
+            String::from_utf8_lossy(&output.stderr)
+        ))
+    }
+}
+
+/// Runs the command, panicking with its output if it fails.
+fn run(command: &mut Command) {
+    if let Err(msg) = try_run(command) {
+        panic!("{}", msg);
+    }
+}
+
+/// Assembles the `source` file and links the DLL against `implib`.
+fn link(arch: &str, source: &str, dir: &Path, name: &str, implib: &Path) -> Result<(), String> {
+    link_dll(arch, source, dir, name, [implib.as_os_str()])
+}
+
+/// Assembles the `source` file and links the DLL `name` with the extra
+/// linker arguments `args`.
+fn link_dll(
+    arch: &str,
+    source: &str,
+    dir: &Path,
+    name: &str,
+    args: impl IntoIterator<Item = impl AsRef<OsStr>>,
+) -> Result<(), String> {
+    let (triple, machine) = match arch {
+        "x86_64" => ("x86_64-pc-windows-msvc", "x64"),
+        "x86" => ("i686-pc-windows-msvc", "x86"),
+        "aarch64" => ("aarch64-pc-windows-msvc", "arm64"),
+        _ => unreachable!("unexpected probe arch {}", arch),
+    };
+
+    let source_file = dir.join(format!("{}.s", name));
+    let object = dir.join(format!("{}.obj", name));
+    let dll = dir.join(format!("{}.dll", name));
+    fs::write(&source_file, source).unwrap();
+
+    run(Command::new("llvm-mc")
+        .arg(format!("-triple={}", triple))
+        .arg("-filetype=obj")
+        .arg("-o")
+        .arg(&object)
+        .arg(&source_file));
+
+    // `lld` accepts both the MSVC and the MinGW-w64 import library formats.
+    let mut lld = find_lld().unwrap();
+    lld.args(["/nologo", "/dll", "/noentry", "/nodefaultlib"])
+        .arg(format!("/machine:{}", machine))
+        .arg(format!("/include:{}", decorate(arch, PROBE_SYMBOL)))
+        .arg(format!("/out:{}", dll.display()))
+        .arg(&object)
+        .args(args);
+
+    if arch == "x86" {
+        lld.arg("/safeseh:no");
+    }
+
+    try_run(&mut lld)
+}
+
+/// Generates the assembly source of a stand-in Python DLL
+/// defining the probed functions and data.
+fn python_dll_source(arch: &str) -> String {
+    let mut source = entry_source(arch);
+    source += "\tret\n";
+
+    for function in PROBE_FUNCTIONS {
+        let function = decorate(arch, function);
+        source += &format!("\t.globl\t{}\n{}:\n\tret\n", function, function);
+    }
+
+    source += "\t.data\n";
+    for data in PROBE_DATA {
+        let data = decorate(arch, data);
+        source += &format!("\t.globl\t{}\n{}:\n\t.long\t0\n", data, data);
+    }
+
+    source
+}
+
+/// Links the probe DLL against the import library generated by `generator`
+/// and checks that the DLL imports the expected Python DLL.
+fn link_probe(generator: &ImportLibraryGenerator, arch: &str, env: &str, dll_name: &str) {
+    link_probe_in(generator, arch, env, "python3-link", dll_name);
+}
+
+/// Same as `link_probe()`, generating the import library in
+/// the target `subdir` directory.
+fn link_probe_in(
+    generator: &ImportLibraryGenerator,
+    arch: &str,
+    env: &str,
+    subdir: &str,
+    dll_name: &str,
+) {
+    let triple = match arch {
+        "x86" => "i686",
+        arch => arch,
+    };
+
+    let mut dir = PathBuf::from(env!("CARGO_MANIFEST_DIR"));
+    dir.push("target");
+    dir.push(format!("{}-pc-windows-{}", triple, env));
+    dir.push(subdir);
+
+    let report = generator.generate(&dir).unwrap();
+
+    let probe_dir = dir.join(Path::new(dll_name).with_extension("probe"));
+    fs::create_dir_all(&probe_dir).unwrap();
+
+    let implib = &report.implib_path;
+    if let Err(msg) = link(arch, &probe_source(arch), &probe_dir, "probe", implib) {
+        panic!("{}", msg);
+    }
+
+    // The import directory refers to the DLL by its name.
+    let dll = probe_dir.join("probe.dll");
+    let image = fs::read(&dll).unwrap();
+    let imported = format!("{}\0", dll_name);
+    assert!(
+        image
+            .windows(imported.len())
+            .any(|w| w.eq_ignore_ascii_case(imported.as_bytes())),
+        "{} does not import {}",
+        dll.display(),
+        dll_name
+    );
+
+    // The data exported as code would be silently called through a thunk.
+    for data in PROBE_DATA {
+        let source = data_thunk_source(arch, data);
+        let result = link(arch, &source, &probe_dir, "data-thunk", implib);
+        assert!(
+            result.is_err(),
+            "{} has a thunk for {}",
+            implib.display(),
+            data
+        );
+    }
+}
+
+#[test]
+fn link_round_trip() {
+    if Command::new("llvm-mc").arg("--version").output().is_err() || find_lld().is_none() {
+        eprintln!("llvm-mc or lld not found, skipping the linkage tests");
+        return;
+    }
+
+    for arch in ["x86_64", "x86", "aarch64"] {
+        for env in ["gnu", "msvc"] {
+            link_probe(
+                &ImportLibraryGenerator::new(arch, env),
+                arch,
+                env,
+                "python3.dll",
+            );
+
+            link_probe(
+                ImportLibraryGenerator::new(arch, env).abiflags(Some("t")),
+                arch,
+                env,
+                "python3t.dll",
+            );
+
+            link_probe(
+                ImportLibraryGenerator::new(arch, env).version(Some((3, 12))),
+                arch,
+                env,
+                "python312.dll",
+            );
+
+            link_probe(
+                ImportLibraryGenerator::new(arch, env)
+                    .version(Some((3, 13)))
+                    .abiflags(Some("t")),
+                arch,
+                env,
+                "python313t.dll",
+            );
+        }
+    }
+}
+
+#[test]
+fn link_from_dll() {
+    if Command::new("llvm-mc").arg("--version").output().is_err() || find_lld().is_none() {
+        eprintln!("llvm-mc or lld not found, skipping the linkage tests");
+        return;
+    }
+
+    for arch in ["x86_64", "x86", "aarch64"] {
+        let triple = match arch {
+            "x86" => "i686",
+            arch => arch,
+        };
+
+        let mut dir = PathBuf::from(env!("CARGO_MANIFEST_DIR"));
+        dir.push("target");
+        dir.push(format!("{}-pc-windows-msvc", triple));
+        dir.push("python3-link-dll");
+        fs::create_dir_all(&dir).unwrap();
+
+        // The exports are classified from the DLL sections alone.
+        let exports = PROBE_FUNCTIONS
+            .iter()
+            .chain(&PROBE_DATA)
+            .map(|name| format!("/export:{}", name));
+        let source = python_dll_source(arch);
+        if let Err(msg) = link_dll(arch, &source, &dir, "python399", exports) {
+            panic!("{}", msg);
+        }
+
+        let dll = dir.join("python399.dll");
+        for env in ["gnu", "msvc"] {
+            let generator = ImportLibraryGenerator::from_dll(&dll, env).unwrap();
+            link_probe(&generator, arch, env, "python399.dll");
+        }
+    }
+}
+
+/// The MSVC import libraries written by MinGW `dlltool` as configured
+/// by `mingw_for_msvc()`.
+///
+/// `lld-link` stands in for `link.exe` here, the Visual Studio linker
+/// itself is not exercised by these tests.
+#[test]
+fn link_mingw_for_msvc() {
+    if Command::new("llvm-mc").arg("--version").output().is_err() || find_lld().is_none() {
+        eprintln!("llvm-mc or lld not found, skipping the linkage tests");
+        return;
+    }
+
+    for (arch, dlltool) in [
+        ("x86_64", "x86_64-w64-mingw32-dlltool"),
+        ("x86", "i686-w64-mingw32-dlltool"),
+    ] {
+        if Command::new(dlltool).arg("--version").output().is_err() {
+            eprintln!("{} not found, skipping the {} linkage test", dlltool, arch);
+            continue;
+        }
+
+        link_probe_in(
+            ImportLibraryGenerator::new(arch, "msvc")
+                .mingw_for_msvc(true)
+                .dlltool_command(Command::new(dlltool), DllToolFlavor::Mingw),
+            arch,
+            "msvc",
+            "python3-link-mingw",
+            "python3.dll",
+        );
+    }
+}